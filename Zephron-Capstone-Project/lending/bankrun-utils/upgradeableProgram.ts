@@ -0,0 +1,52 @@
+import { PublicKey } from '@solana/web3.js';
+import { ProgramTestContext } from 'solana-bankrun';
+
+export const BPF_LOADER_UPGRADEABLE_ID = new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111');
+
+// UpgradeableLoaderState::ProgramData header: enum tag (4), slot (8), Option<upgrade authority> (1 + 32)
+const PROGRAM_DATA_HEADER_LEN = 45;
+
+export const programDataAddress = (programId: PublicKey) =>
+  PublicKey.findProgramAddressSync([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_ID)[0];
+
+// Bankrun may load workspace programs without an upgrade authority. Rewrite the program as an
+// upgradeable deployment owned by `authority`, so upgrade-authority-only instructions can run.
+export async function setUpgradeAuthority(
+  context: ProgramTestContext,
+  programId: PublicKey,
+  authority: PublicKey
+): Promise<PublicKey> {
+  const program = await context.banksClient.getAccount(programId);
+  const programData = programDataAddress(programId);
+
+  let elf: Buffer;
+  if (program.owner.equals(BPF_LOADER_UPGRADEABLE_ID)) {
+    const existing = await context.banksClient.getAccount(programData);
+    elf = Buffer.from(existing.data).subarray(PROGRAM_DATA_HEADER_LEN);
+  } else {
+    elf = Buffer.from(program.data);
+  }
+
+  const header = Buffer.alloc(PROGRAM_DATA_HEADER_LEN);
+  header.writeUInt32LE(3, 0); // ProgramData, deployed at slot 0
+  header.writeUInt8(1, 12); // Some(authority)
+  authority.toBuffer().copy(header, 13);
+  context.setAccount(programData, {
+    lamports: 1_000_000_000,
+    data: Buffer.concat([header, elf]),
+    owner: BPF_LOADER_UPGRADEABLE_ID,
+    executable: false,
+  });
+
+  const programAccount = Buffer.alloc(36);
+  programAccount.writeUInt32LE(2, 0); // Program, pointing at its ProgramData
+  programData.toBuffer().copy(programAccount, 4);
+  context.setAccount(programId, {
+    lamports: program.lamports,
+    data: programAccount,
+    owner: BPF_LOADER_UPGRADEABLE_ID,
+    executable: true,
+  });
+
+  return programData;
+}
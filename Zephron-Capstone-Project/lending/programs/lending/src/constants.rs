@@ -5,3 +5,10 @@ pub const GOLD_FEED_ID: &str = "0x765d2ba906dbc32ca17cc11f5310a89e9ee1f6420508c6
 pub const SOL_USD_FEED_ID: &str = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
 pub const USDC_USD_FEED_ID: &str = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
 pub const MAXIMUM_AGE: u64 = 100; // allow price feed 100 sec old, to avoid stale price feed errors
+pub const USD_DECIMALS: u32 = 6; // asset values are compared in USD with 6 decimals
+pub const MAX_USER_POSITIONS: usize = 8; // banks a single user account can hold deposits or borrows in
+pub const MAX_LIQUIDATION_BONUS: u64 = 20; // liquidators can be paid at most 20% on top of the debt they repay
+pub const MAX_INTEREST_RATE: u64 = 10_000; // annual interest rate ceiling (basis points, 10_000 = 100%)
+pub const BASIS_POINTS: u64 = 10_000; // rates and utilization are tracked in basis points
+pub const NO_EMODE: u8 = 0; // e-mode category id meaning "no category"
+pub const SECONDS_PER_YEAR: u64 = 31_536_000; // annual rates are spread over a 365 day year
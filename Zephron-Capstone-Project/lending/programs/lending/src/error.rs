@@ -16,4 +16,32 @@ pub enum ErrorCode {
     NotUndercollateralized,
     #[msg("Oracle price error")] 
     OracleError,
+    #[msg("Math overflow.")]
+    MathOverflow,
+    #[msg("Amount is too small to mint any shares.")]
+    ZeroShares,
+    #[msg("User already holds the maximum number of positions.")]
+    TooManyPositions,
+    #[msg("User has no position in this bank.")]
+    PositionNotFound,
+    #[msg("Expected a bank and price update account for every position.")]
+    InvalidPositionAccounts,
+    #[msg("Invalid oracle configuration.")]
+    InvalidOracleConfig,
+    #[msg("Invalid bank configuration.")]
+    InvalidBankConfig,
+    #[msg("Signer is not the bank, protocol or upgrade authority.")]
+    Unauthorized,
+    #[msg("Liquidation amount is zero after applying the close factor.")]
+    InvalidLiquidationAmount,
+    #[msg("Isolated collateral cannot be combined with other collateral.")]
+    IsolationViolation,
+    #[msg("Borrow exceeds the isolated collateral's debt ceiling.")]
+    IsolatedDebtCeilingExceeded,
+    #[msg("Borrow is outside the user's e-mode category.")]
+    EModeViolation,
+    #[msg("Mint has a Token-2022 extension the protocol does not support.")]
+    UnsupportedMintExtension,
+    #[msg("Collateral and borrowed banks must be different banks.")]
+    SameBankLiquidation,
 }
\ No newline at end of file
@@ -1,16 +1,47 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 use crate::state::*;
+use crate::constants::{ MAX_INTEREST_RATE, MAX_LIQUIDATION_BONUS, NO_EMODE };
+use crate::error::ErrorCode;
+use crate::instructions::extensions::inspect_mint_extensions;
+use crate::instructions::utils::accrue_interest;
+use crate::program::LendingProtocol;
 
 #[derive(Accounts)]
-pub struct InitBank<'info> {
+pub struct InitProtocol<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
+    #[account(
+        init,
+        space = 8 + Protocol::INIT_SPACE,
+        payer = signer,
+        seeds = [b"protocol"],
+        bump,
+    )]
+    pub protocol: Account<'info, Protocol>,
+    // Only the program's upgrade authority can take over the protocol
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, LendingProtocol>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program <'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitBank<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"protocol"],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol: Account<'info, Protocol>,
     pub mint: InterfaceAccount<'info, Mint>,
     #[account(
         init, 
         space = 8 + Bank::INIT_SPACE, 
-        payer = signer,
+        payer = authority,
         seeds = [mint.key().as_ref()],
         bump, 
     )]
@@ -19,15 +50,64 @@ pub struct InitBank<'info> {
         init, 
         token::mint = mint, 
         token::authority = bank_token_account,
-        payer = signer,
+        payer = authority,
         seeds = [b"treasury", mint.key().as_ref()],
         bump,
     )]
     pub bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    // Required when the config assigns the bank to an e-mode category
+    pub emode_category: Option<Account<'info, EModeCategory>>,
     pub token_program: Interface<'info, TokenInterface>, 
     pub system_program: Program <'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateBank<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub bank: Account<'info, Bank>,
+    // Required when the config assigns the bank to an e-mode category
+    pub emode_category: Option<Account<'info, EModeCategory>>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u8)]
+pub struct InitEModeCategory<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"protocol"],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        init,
+        space = 8 + EModeCategory::INIT_SPACE,
+        payer = authority,
+        seeds = [b"emode", id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub emode_category: Account<'info, EModeCategory>,
+    pub system_program: Program <'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEModeCategory<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"protocol"],
+        bump,
+        has_one = authority @ ErrorCode::Unauthorized,
+    )]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut)]
+    pub emode_category: Account<'info, EModeCategory>,
+}
+
 #[derive(Accounts)]
 pub struct InitUser<'info> {
     #[account(mut)]
@@ -43,19 +123,116 @@ pub struct InitUser<'info> {
     pub system_program: Program <'info, System>,
 }
 
-pub fn process_init_bank(ctx: Context<InitBank>, liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
+pub fn process_init_protocol(ctx: Context<InitProtocol>) -> Result<()> {
+    ctx.accounts.protocol.authority = ctx.accounts.signer.key();
+    Ok(())
+}
+
+pub fn process_init_bank(ctx: Context<InitBank>, config: BankConfig) -> Result<()> {
     let bank = &mut ctx.accounts.bank;
     bank.mint_address = ctx.accounts.mint.key();
-    bank.authority = ctx.accounts.signer.key();
-    bank.liquidation_threshold = liquidation_threshold;
-    bank.max_ltv = max_ltv;
+    bank.authority = ctx.accounts.authority.key();
+    bank.decimals = ctx.accounts.mint.decimals;
+
+    let extensions = inspect_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+    bank.transfer_fee = extensions.transfer_fee;
+    bank.transfer_hook = extensions.transfer_hook;
+
+    bank.last_updated = Clock::get()?.unix_timestamp;
+    apply_bank_config(bank, &config, ctx.accounts.emode_category.as_deref())
+}
+
+pub fn process_update_bank(ctx: Context<UpdateBank>, config: BankConfig) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+    // Interest up to now is charged at the old rate before the new curve applies
+    accrue_interest(bank, Clock::get()?.unix_timestamp)?;
+    apply_bank_config(bank, &config, ctx.accounts.emode_category.as_deref())
+}
+
+// Validate and store a bank's risk and oracle parameters
+fn apply_bank_config(bank: &mut Bank, config: &BankConfig, emode_category: Option<&EModeCategory>) -> Result<()> {
+    // Borrowing must stop before a position becomes liquidatable
+    if config.max_ltv == 0 || config.max_ltv >= config.liquidation_threshold || config.liquidation_threshold > 100 {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // Collateral seized with the bonus must still fit inside the collateral backing the debt
+    if config.liquidation_bonus > MAX_LIQUIDATION_BONUS
+        || config.liquidation_threshold * (100 + config.liquidation_bonus) > 100 * 100
+    {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    if config.liquidation_close_factor == 0 || config.liquidation_close_factor > 100 {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // The borrow rate curve must rise with utilization and bend strictly inside 0-100%
+    if config.interest_rate > config.max_interest_rate
+        || config.max_interest_rate > MAX_INTEREST_RATE
+        || config.optimal_utilization == 0
+        || config.optimal_utilization >= 100
+    {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // Isolated collateral needs a debt ceiling and keeps its own risk parameters outside e-mode
+    if config.isolated && (config.isolated_debt_ceiling == 0 || config.emode_category != NO_EMODE) {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // Existing deposits were made under the bank's isolation rules, so they cannot change underneath them
+    if config.isolated != bank.isolated && bank.total_deposit_shares > 0 {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // A bank can only join a category that has been created
+    if config.emode_category != NO_EMODE && emode_category.map(|category| category.id) != Some(config.emode_category) {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    // Every asset is priced through its own feed, so any mint with a Pyth feed (GOLD included) can be listed
+    if config.oracle_feed_id == [0u8; 32] || config.max_price_age == 0 {
+        return Err(ErrorCode::InvalidOracleConfig.into());
+    }
+
+    bank.liquidation_threshold = config.liquidation_threshold;
+    bank.max_ltv = config.max_ltv;
+    bank.liquidation_bonus = config.liquidation_bonus;
+    bank.liquidation_close_factor = config.liquidation_close_factor;
+    bank.interest_rate = config.interest_rate;
+    bank.max_interest_rate = config.max_interest_rate;
+    bank.optimal_utilization = config.optimal_utilization;
+    bank.oracle_feed_id = config.oracle_feed_id;
+    bank.max_price_age = config.max_price_age;
+    bank.isolated = config.isolated;
+    bank.isolated_debt_ceiling = config.isolated_debt_ceiling;
+    bank.emode_category = config.emode_category;
+    Ok(())
+}
+
+pub fn process_init_emode_category(ctx: Context<InitEModeCategory>, id: u8, max_ltv: u64, liquidation_threshold: u64) -> Result<()> {
+    if id == NO_EMODE {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    let category = &mut ctx.accounts.emode_category;
+    category.id = id;
+    apply_emode_params(category, max_ltv, liquidation_threshold)
+}
+
+pub fn process_update_emode_category(ctx: Context<UpdateEModeCategory>, max_ltv: u64, liquidation_threshold: u64) -> Result<()> {
+    apply_emode_params(&mut ctx.accounts.emode_category, max_ltv, liquidation_threshold)
+}
+
+// Validate and store an e-mode category's LTV and liquidation threshold
+fn apply_emode_params(category: &mut EModeCategory, max_ltv: u64, liquidation_threshold: u64) -> Result<()> {
+    if max_ltv == 0 || max_ltv >= liquidation_threshold || liquidation_threshold > 100 {
+        return Err(ErrorCode::InvalidBankConfig.into());
+    }
+    category.max_ltv = max_ltv;
+    category.liquidation_threshold = liquidation_threshold;
     Ok(())
 }
 
-pub fn process_init_user(ctx: Context<InitUser>, usdc_address: Pubkey) -> Result<()> {
+pub fn process_init_user(ctx: Context<InitUser>) -> Result<()> {
     let user = &mut ctx.accounts.user_account;
     user.owner = ctx.accounts.signer.key();
-    user.usdc_address = usdc_address;
+    user.positions = Vec::new();
+    user.isolated_bank = Pubkey::default();
+    user.emode_category = NO_EMODE;
     
     let now = Clock::get()?.unix_timestamp; 
     user.last_updated = now;
@@ -1,12 +1,14 @@
-use std::f32::consts::E;
-
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
 use crate::state::*;
 use crate::error::ErrorCode;
+use crate::constants::NO_EMODE;
+use crate::instructions::utils::{
+    accrue_interest, assets_to_shares_up, check_health, get_asset_value, get_or_open_position, position_accounts_len,
+    value_positions,
+};
 
 #[derive(Accounts)]
 pub struct Borrow<'info> {
@@ -40,46 +42,55 @@ pub struct Borrow<'info> {
     )]
     pub user_token_account: InterfaceAccount<'info, TokenAccount>, 
     pub price_update: Account<'info, PriceUpdateV2>,
+    // Required when the user is in isolation mode, its debt ceiling is charged for the borrow.
+    // Left out when it is the borrowed bank itself, two copies of one bank would overwrite each other
+    #[account(
+        mut,
+        constraint = isolated_bank.key() != bank.key() @ ErrorCode::IsolationViolation,
+    )]
+    pub isolated_bank: Option<Account<'info, Bank>>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+pub fn process_borrow<'info>(ctx: Context<'_, '_, 'info, 'info, Borrow<'info>>, amount: u64) -> Result<()> {
     let bank = &mut ctx.accounts.bank;
     let user = &mut ctx.accounts.user_account;
+    accrue_interest(bank, Clock::get()?.unix_timestamp)?;
 
-    let price_update = &mut ctx.accounts.price_update;
-
-    let total_collateral: u64;
+    // Every deposit counts at its own max LTV against every borrow, existing ones included,
+    // each priced by the oracle passed with its bank
+    let values = value_positions(user, ctx.remaining_accounts)?;
+    let borrow_value = get_asset_value(bank, &ctx.accounts.price_update, amount, &Clock::get()?)?;
+    check_health(&values, bank, 0, borrow_value, ErrorCode::OverLTV)?;
 
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-                .map_err(|_| error!(ErrorCode::OracleError))?; 
-            let sol_price = price_update
-                .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            let accrued_interest = calculate_accrued_interest(user.deposited_sol, bank.interest_rate, user.last_updated)?;
-            total_collateral = sol_price.price as u64 * (user.deposited_sol + accrued_interest);
-        },
-        _ => {
-            let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            let usdc_price = price_update
-                .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-                .map_err(|_| error!(ErrorCode::OracleError))?;
-            total_collateral = usdc_price.price as u64 * user.deposited_usdc;
+    // Users in e-mode can only borrow assets from their category
+    if user.emode_category != NO_EMODE && bank.emode_category != user.emode_category {
+        return Err(ErrorCode::EModeViolation.into());
+    }
 
+    // Borrows backed by isolated collateral count against that bank's debt ceiling, in USD
+    let mut isolated_debt = 0;
+    if user.isolated_bank != Pubkey::default() {
+        let isolated_bank: &mut Account<'info, Bank> = if user.isolated_bank == bank.key() {
+            &mut *bank
+        } else {
+            ctx.accounts.isolated_bank
+                .as_mut()
+                .filter(|isolated_bank| isolated_bank.key() == user.isolated_bank)
+                .ok_or(ErrorCode::IsolationViolation)?
+        };
+        isolated_debt = u64::try_from(borrow_value).map_err(|_| error!(ErrorCode::MathOverflow))?;
+        let total_isolated_debt = isolated_bank.isolated_debt
+            .checked_add(isolated_debt)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if total_isolated_debt > isolated_bank.isolated_debt_ceiling {
+            return Err(ErrorCode::IsolatedDebtCeilingExceeded.into());
         }
+        isolated_bank.isolated_debt = total_isolated_debt;
     }
 
-    let borrowable_amount = total_collateral as u64 *  bank.liquidation_threshold;
-
-    if borrowable_amount < amount {
-        return Err(ErrorCode::OverBorrowableAmount.into());
-    }       
-
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
@@ -96,39 +107,26 @@ pub fn process_borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
             &[ctx.bumps.bank_token_account],
         ],
     ];
-    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts).with_signer(signer_seeds);
+    // Remaining accounts after the position accounts are the extra accounts a transfer hook mint needs
+    let hook_accounts = ctx.remaining_accounts[position_accounts_len(user)..].to_vec();
+    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts)
+        .with_signer(signer_seeds)
+        .with_remaining_accounts(hook_accounts);
     let decimals = ctx.accounts.mint.decimals;
 
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
 
-    if bank.total_borrowed == 0 {
-        bank.total_borrowed = amount;
-        bank.total_borrowed_shares = amount;
-    } 
-
-    let borrow_ratio = amount.checked_div(bank.total_borrowed).unwrap();
-    let users_shares = bank.total_borrowed_shares.checked_mul(borrow_ratio).unwrap();
+    // Debt shares are minted at the current exchange rate, rounded up in the bank's favor
+    let users_shares = assets_to_shares_up(amount, bank.total_borrowed, bank.total_borrowed_shares)?;
 
     bank.total_borrowed += amount;
     bank.total_borrowed_shares += users_shares; 
 
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.borrowed_usdc += amount;
-            user.deposited_usdc_shares += users_shares;
-        },
-        _ => {
-            user.borrowed_sol += amount;
-            user.deposited_sol_shares += users_shares;
-        }
-    }
+    let position = get_or_open_position(user, &bank.key())?;
+    position.borrow_shares += users_shares;
+    position.isolated_debt += isolated_debt;
 
-    Ok(())
-}
+    user.last_updated = Clock::get()?.unix_timestamp;
 
-fn calculate_accrued_interest(deposited: u64, interest_rate: u64, last_update: i64) -> Result<u64> {
-    let current_time = Clock::get()?.unix_timestamp;
-    let time_elapsed = current_time - last_update;
-    let new_value = (deposited as f64 * E.powf(interest_rate as f32 * time_elapsed as f32) as f64) as u64;
-    Ok(new_value)
+    Ok(())
 }
\ No newline at end of file
@@ -2,6 +2,8 @@ use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
 use crate::state::*;
+use crate::error::ErrorCode;
+use crate::instructions::utils::{ accrue_interest, assets_to_shares_down, get_or_open_position };
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -38,7 +40,23 @@ pub struct Deposit<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+// Returns the deposit shares minted, also set as return data for programs depositing through CPI
+pub fn process_deposit<'info>(ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>, amount: u64) -> Result<u64> {
+    let bank = &mut ctx.accounts.bank;
+    let user = &mut ctx.accounts.user_account;
+    accrue_interest(bank, Clock::get()?.unix_timestamp)?;
+
+    // Isolated collateral is held alone, so entering isolation needs an account with no deposits in other banks
+    // and an isolated account cannot add other collateral
+    if bank.isolated {
+        if user.positions.iter().any(|position| position.bank != bank.key() && position.deposit_shares > 0) {
+            return Err(ErrorCode::IsolationViolation.into());
+        }
+        user.isolated_bank = bank.key();
+    } else if user.isolated_bank != Pubkey::default() {
+        return Err(ErrorCode::IsolationViolation.into());
+    }
+
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.user_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
@@ -47,39 +65,30 @@ pub fn process_deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     };
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts);
+    // Remaining accounts are the extra accounts a transfer hook mint needs
+    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
     let decimals = ctx.accounts.mint.decimals;
 
+    // A transfer fee mint withholds part of the amount, only what reaches the treasury is credited
+    let balance_before = ctx.accounts.bank_token_account.amount;
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+    ctx.accounts.bank_token_account.reload()?;
+    let received = ctx.accounts.bank_token_account.amount - balance_before;
 
-    let bank = &mut ctx.accounts.bank;
-
-    if bank.total_deposits == 0 {
-        bank.total_deposits = amount;
-        bank.total_deposit_shares = amount;
-    }
-    
-    let deposit_ratio = amount.checked_div(bank.total_deposits).unwrap();
-    let users_shares = bank.total_deposit_shares.checked_mul(deposit_ratio).unwrap();
-    
-    let user = &mut ctx.accounts.user_account;
-    
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.deposited_usdc += amount;
-            user.deposited_usdc_shares += users_shares;
-        },
-        _ => {
-            user.deposited_sol += amount;
-            user.deposited_sol_shares += users_shares; 
-        }
+    // Shares are minted at the current exchange rate, rounded down in the bank's favor
+    let users_shares = assets_to_shares_down(received, bank.total_deposits, bank.total_deposit_shares)?;
+    if users_shares == 0 {
+        return Err(ErrorCode::ZeroShares.into());
     }
 
+    let position = get_or_open_position(user, &bank.key())?;
+    position.deposit_shares += users_shares;
 
-    bank.total_deposits += amount;
+    bank.total_deposits += received;
     bank.total_deposit_shares += users_shares;
 
     user.last_updated = Clock::get()?.unix_timestamp;
 
-    Ok(())
+    Ok(users_shares)
 }
\ No newline at end of file
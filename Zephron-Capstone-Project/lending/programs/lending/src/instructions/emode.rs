@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use crate::instructions::utils::value_positions;
+
+#[derive(Accounts)]
+pub struct SetEMode<'info> {
+    pub signer: Signer<'info>,
+    #[account(
+        mut, 
+        seeds = [signer.key().as_ref()],
+        bump,
+    )]  
+    pub user_account: Account<'info, User>,
+}
+
+// Opt into an e-mode category, or leave e-mode with category 0. Remaining accounts carry a
+// (bank, price update) pair per position followed by the new category account.
+pub fn process_set_emode(ctx: Context<SetEMode>, category: u8) -> Result<()> {
+    let user = &mut ctx.accounts.user_account;
+    user.emode_category = category;
+
+    // Every existing borrow must belong to the category and still fit under its LTVs
+    let values = value_positions(user, ctx.remaining_accounts)?;
+    if values.borrows_outside_emode {
+        return Err(ErrorCode::EModeViolation.into());
+    }
+    if values.borrow_value > values.borrowing_value {
+        return Err(ErrorCode::OverLTV.into());
+    }
+
+    user.last_updated = Clock::get()?.unix_timestamp;
+
+    Ok(())
+}
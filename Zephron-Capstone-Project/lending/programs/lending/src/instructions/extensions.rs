@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::{ BaseStateWithExtensions, ExtensionType, StateWithExtensions };
+use anchor_spl::token_2022::spl_token_2022::state::Mint as MintState;
+use crate::error::ErrorCode;
+
+// Token-2022 mint extensions a bank can list. Transfer fees and hooks are handled by crediting the
+// amount that actually arrives and forwarding hook accounts. Interest-bearing mints are rejected, their
+// oracle prices the UI amount while banks account and value raw amounts. Anything else (permanent
+// delegate, non-transferable, default-frozen accounts, confidential transfers, closable mints, ...)
+// could lock or drain the treasury and is rejected too.
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 8] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::TransferHook,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+// Extensions on a bank's mint that change how its transfers behave
+pub struct MintExtensions {
+    pub transfer_fee: bool,
+    pub transfer_hook: bool,
+}
+
+// Read a mint's extensions, failing on any the protocol cannot handle. Legacy SPL Token mints have none.
+pub fn inspect_mint_extensions(mint: &AccountInfo) -> Result<MintExtensions> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    let extension_types = mint.get_extension_types()?;
+
+    if extension_types.iter().any(|extension| !SUPPORTED_MINT_EXTENSIONS.contains(extension)) {
+        return Err(ErrorCode::UnsupportedMintExtension.into());
+    }
+
+    Ok(MintExtensions {
+        transfer_fee: extension_types.contains(&ExtensionType::TransferFeeConfig),
+        transfer_hook: extension_types.contains(&ExtensionType::TransferHook),
+    })
+}
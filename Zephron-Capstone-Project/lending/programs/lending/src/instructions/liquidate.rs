@@ -1,17 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
-use crate::constants::{MAXIMUM_AGE, SOL_USD_FEED_ID, USDC_USD_FEED_ID};
 use crate::state::*;
 use crate::error::ErrorCode;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::instructions::utils::{
+    accrue_interest, assets_to_shares_down, assets_to_shares_up, close_empty_positions, find_position, get_asset_amount,
+    get_asset_value, isolated_debt_released, position_accounts_len, shares_to_assets_down, shares_to_assets_up, value_positions,
+};
 
 #[derive(Accounts)]
 pub struct Liquidate<'info> {
     #[account(mut)]
     pub liquidator: Signer<'info>,
-    pub price_update: Account<'info, PriceUpdateV2>,
     pub collateral_mint: InterfaceAccount<'info, Mint>,
+    // Two copies of one bank would overwrite each other, so the debt and collateral are in different banks
+    #[account(constraint = borrowed_mint.key() != collateral_mint.key() @ ErrorCode::SameBankLiquidation)]
     pub borrowed_mint: InterfaceAccount<'info, Mint>,
     #[account(
         mut, 
@@ -37,18 +41,21 @@ pub struct Liquidate<'info> {
         bump, 
     )]  
     pub borrowed_bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub owner: SystemAccount<'info>,
     #[account(
         mut, 
-        seeds = [liquidator.key().as_ref()],
+        seeds = [owner.key().as_ref()],
         bump,
     )]  
     pub user_account: Account<'info, User>,
+    pub collateral_price_update: Account<'info, PriceUpdateV2>,
+    pub borrowed_price_update: Account<'info, PriceUpdateV2>,
     #[account( 
         init_if_needed, 
         payer = liquidator,
         associated_token::mint = collateral_mint, 
         associated_token::authority = liquidator,
-        associated_token::token_program = token_program,
+        associated_token::token_program = collateral_token_program,
     )]
     pub liquidator_collateral_token_account: InterfaceAccount<'info, TokenAccount>, 
     #[account( 
@@ -56,44 +63,63 @@ pub struct Liquidate<'info> {
         payer = liquidator,
         associated_token::mint = borrowed_mint, 
         associated_token::authority = liquidator,
-        associated_token::token_program = token_program,
+        associated_token::token_program = borrowed_token_program,
     )]
     pub liquidator_borrowed_token_account: InterfaceAccount<'info, TokenAccount>, 
-    pub token_program: Interface<'info, TokenInterface>,
+    // Each mint's own token program, so SPL Token and Token-2022 assets can be liquidated against each other
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub borrowed_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> { 
+// Repay part of an unhealthy user's debt in one bank and seize their collateral in another, plus the bonus
+pub fn process_liquidate<'info>(ctx: Context<'_, '_, 'info, 'info, Liquidate<'info>>, repay_amount: u64) -> Result<()> { 
     let collateral_bank = &mut ctx.accounts.collateral_bank;
+    let borrowed_bank = &mut ctx.accounts.borrowed_bank;
     let user = &mut ctx.accounts.user_account;
 
-    let price_update = &mut ctx.accounts.price_update;
+    let clock = Clock::get()?;
+    accrue_interest(collateral_bank, clock.unix_timestamp)?;
+    accrue_interest(borrowed_bank, clock.unix_timestamp)?;
 
-    let sol_feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?; 
-    let usdc_feed_id = get_feed_id_from_hex(USDC_USD_FEED_ID)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-    let sol_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &sol_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-    let usdc_price = price_update
-        .get_price_no_older_than(&Clock::get()?, MAXIMUM_AGE, &usdc_feed_id)
-        .map_err(|_| error!(ErrorCode::OracleError))?;
-
-
-    let total_collateral = (sol_price.price as u64 * user.deposited_sol) + (usdc_price.price as u64 * user.deposited_usdc);
-    let total_borrowed = (sol_price.price as u64 * user.borrowed_sol) + (usdc_price.price as u64 * user.borrowed_usdc);    
-
-    let health_factor = (total_collateral * collateral_bank.liquidation_threshold)/total_borrowed;
+    if repay_amount == 0 {
+        return Err(ErrorCode::InvalidLiquidationAmount.into());
+    }
 
-    if health_factor >= 1 {
+    // Every position is valued, remaining accounts start with a (bank, price update) pair per position.
+    // The user is liquidatable once their threshold-weighted collateral no longer covers their debt.
+    let values = value_positions(user, ctx.remaining_accounts)?;
+    if values.borrow_value == 0 || values.liquidation_value >= values.borrow_value {
         return Err(ErrorCode::NotUndercollateralized.into());
     }
 
-    let liquidation_amount = total_borrowed * collateral_bank.liquidation_close_factor;
-
+    let borrow_index = find_position(user, &borrowed_bank.key()).ok_or(ErrorCode::PositionNotFound)?;
+    let collateral_index = find_position(user, &collateral_bank.key()).ok_or(ErrorCode::PositionNotFound)?;
+
+    // A single liquidation repays at most close_factor percent of the debt in the borrowed bank
+    let borrow_shares = user.positions[borrow_index].borrow_shares;
+    let debt = shares_to_assets_up(borrow_shares, borrowed_bank.total_borrowed, borrowed_bank.total_borrowed_shares)?;
+    let max_repay = (debt as u128 * borrowed_bank.liquidation_close_factor as u128 / 100) as u64;
+    let mut repay = repay_amount.min(max_repay);
+
+    // Collateral seized is worth the repaid debt plus the collateral bank's bonus
+    let repay_value = get_asset_value(borrowed_bank, &ctx.accounts.borrowed_price_update, repay, &clock)?;
+    let seize_value = repay_value * (100 + collateral_bank.liquidation_bonus as u128) / 100;
+    let mut seize = get_asset_amount(collateral_bank, &ctx.accounts.collateral_price_update, seize_value, &clock)?;
+
+    // When the position cannot cover the bonus, everything is seized and the repayment shrinks to match
+    let deposit_shares = user.positions[collateral_index].deposit_shares;
+    let collateral = shares_to_assets_down(deposit_shares, collateral_bank.total_deposits, collateral_bank.total_deposit_shares)?;
+    if seize > collateral {
+        let collateral_value = get_asset_value(collateral_bank, &ctx.accounts.collateral_price_update, collateral, &clock)?;
+        let repay_value = collateral_value * 100 / (100 + collateral_bank.liquidation_bonus as u128);
+        repay = get_asset_amount(borrowed_bank, &ctx.accounts.borrowed_price_update, repay_value, &clock)?.min(repay);
+        seize = collateral;
+    }
+    if repay == 0 || seize == 0 {
+        return Err(ErrorCode::InvalidLiquidationAmount.into());
+    }
 
     let transfer_to_bank = TransferChecked {
         from: ctx.accounts.liquidator_borrowed_token_account.to_account_info(),
@@ -102,14 +128,54 @@ pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> {
         authority: ctx.accounts.liquidator.to_account_info(),
     };
 
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx_to_bank = CpiContext::new(cpi_program.clone(), transfer_to_bank);
+    // Remaining accounts after the position accounts are the extra accounts transfer hook mints need
+    let hook_accounts = ctx.remaining_accounts[position_accounts_len(user)..].to_vec();
+    let cpi_program = ctx.accounts.borrowed_token_program.to_account_info();
+    let cpi_ctx_to_bank = CpiContext::new(cpi_program, transfer_to_bank)
+        .with_remaining_accounts(hook_accounts.clone());
     let decimals = ctx.accounts.borrowed_mint.decimals;
 
-    token_interface::transfer_checked(cpi_ctx_to_bank, liquidation_amount, decimals)?;
+    let balance_before = ctx.accounts.borrowed_bank_token_account.amount;
+    token_interface::transfer_checked(cpi_ctx_to_bank, repay, decimals)?;
+    ctx.accounts.borrowed_bank_token_account.reload()?;
+    let received = ctx.accounts.borrowed_bank_token_account.amount - balance_before;
+
+    // A transfer fee mint withholds part of the repayment, only what arrives repays debt and earns collateral
+    if received < repay {
+        seize = (seize as u128 * received as u128 / repay as u128) as u64;
+        repay = received;
+    }
+
+    // Debt shares burned round down, collateral shares burned round up, both in the banks' favor
+    let borrow_shares_burned = if repay == debt {
+        borrow_shares
+    } else {
+        assets_to_shares_down(repay, borrowed_bank.total_borrowed, borrowed_bank.total_borrowed_shares)?
+    };
+    let deposit_shares_burned = if seize == collateral {
+        deposit_shares
+    } else {
+        assets_to_shares_up(seize, collateral_bank.total_deposits, collateral_bank.total_deposit_shares)?
+            .min(deposit_shares)
+    };
+
+    // An isolated user's only collateral is the isolated bank, which frees the repaid isolated debt
+    let released = isolated_debt_released(&user.positions[borrow_index], borrow_shares_burned);
+    if released > 0 && user.isolated_bank == collateral_bank.key() {
+        collateral_bank.isolated_debt = collateral_bank.isolated_debt.saturating_sub(released);
+    }
+    user.positions[borrow_index].isolated_debt -= released;
+
+    user.positions[borrow_index].borrow_shares -= borrow_shares_burned;
+    user.positions[collateral_index].deposit_shares -= deposit_shares_burned;
+    close_empty_positions(user);
+    user.last_updated = clock.unix_timestamp;
+
+    borrowed_bank.total_borrowed = borrowed_bank.total_borrowed.saturating_sub(repay);
+    borrowed_bank.total_borrowed_shares -= borrow_shares_burned;
+    collateral_bank.total_deposits -= seize;
+    collateral_bank.total_deposit_shares -= deposit_shares_burned;
 
-    let liquidation_bonus = (liquidation_amount * collateral_bank.liquidation_bonus) + liquidation_amount;
-    
     let transfer_to_liquidator = TransferChecked {
         from: ctx.accounts.collateral_bank_token_account.to_account_info(),
         mint: ctx.accounts.collateral_mint.to_account_info(),
@@ -125,9 +191,12 @@ pub fn process_liquidate(ctx: Context<Liquidate>) -> Result<()> {
             &[ctx.bumps.collateral_bank_token_account],
         ],
     ];
-    let cpi_ctx_to_liquidator = CpiContext::new(cpi_program.clone(), transfer_to_liquidator).with_signer(signer_seeds);
+    let cpi_program = ctx.accounts.collateral_token_program.to_account_info();
+    let cpi_ctx_to_liquidator = CpiContext::new(cpi_program, transfer_to_liquidator)
+        .with_signer(signer_seeds)
+        .with_remaining_accounts(hook_accounts);
     let collateral_decimals = ctx.accounts.collateral_mint.decimals;   
-    token_interface::transfer_checked(cpi_ctx_to_liquidator, liquidation_bonus, collateral_decimals)?;
+    token_interface::transfer_checked(cpi_ctx_to_liquidator, seize, collateral_decimals)?;
 
     Ok(())
 }
\ No newline at end of file
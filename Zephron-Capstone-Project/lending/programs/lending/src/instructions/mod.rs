@@ -9,4 +9,10 @@ pub mod withdraw;
 pub use repay::*;
 pub mod repay;
 pub use liquidate::*;
-pub mod liquidate;
\ No newline at end of file
+pub mod liquidate;
+pub use emode::*;
+pub mod emode;
+pub use extensions::*;
+pub mod extensions;
+pub use utils::*;
+pub mod utils;
@@ -3,6 +3,7 @@ use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
 use crate::state::*;
 use crate::error::ErrorCode;
+use crate::instructions::utils::{ accrue_interest, assets_to_shares_down, close_empty_positions, find_position, isolated_debt_released, shares_to_assets_up };
 
 #[derive(Accounts)]
 pub struct Repay<'info> {
@@ -35,29 +36,28 @@ pub struct Repay<'info> {
         associated_token::token_program = token_program,
     )]
     pub user_token_account: InterfaceAccount<'info, TokenAccount>, 
+    // Required when the repaid borrow counts against an isolated bank's debt ceiling.
+    // Left out when it is the borrowed bank itself, two copies of one bank would overwrite each other
+    #[account(
+        mut,
+        constraint = isolated_bank.key() != bank.key() @ ErrorCode::IsolationViolation,
+    )]
+    pub isolated_bank: Option<Account<'info, Bank>>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+pub fn process_repay<'info>(ctx: Context<'_, '_, 'info, 'info, Repay<'info>>, amount: u64) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
     let user = &mut ctx.accounts.user_account;
+    accrue_interest(bank, Clock::get()?.unix_timestamp)?;
 
-    let borrowed_asset; 
+    let index = find_position(user, &bank.key()).ok_or(ErrorCode::PositionNotFound)?;
+    let user_shares = user.positions[index].borrow_shares;
 
-
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            borrowed_asset = user.borrowed_usdc;
-        },
-        _ => {
-            borrowed_asset = user.borrowed_sol;
-        }
-    }
-
-    if amount > borrowed_asset {
-        return Err(ErrorCode::OverRepay.into());
-    }
+    // Debt is worth the user's shares at the current exchange rate, rounded up
+    let borrowed_asset = shares_to_assets_up(user_shares, bank.total_borrowed, bank.total_borrowed_shares)?;
 
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.user_token_account.to_account_info(),
@@ -67,32 +67,47 @@ pub fn process_repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
     };
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts);
+    // Remaining accounts are the extra accounts a transfer hook mint needs
+    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts)
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
     let decimals = ctx.accounts.mint.decimals;
 
+    // A transfer fee mint withholds part of the amount, only what reaches the treasury repays debt
+    let balance_before = ctx.accounts.bank_token_account.amount;
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
+    ctx.accounts.bank_token_account.reload()?;
+    let received = ctx.accounts.bank_token_account.amount - balance_before;
 
+    if received > borrowed_asset {
+        return Err(ErrorCode::OverRepay.into());
+    }
 
-    let bank = &mut ctx.accounts.bank;
+    // Shares burned are rounded down, a full repayment clears every share
+    let users_shares = if received == borrowed_asset {
+        user_shares
+    } else {
+        assets_to_shares_down(received, bank.total_borrowed, bank.total_borrowed_shares)?
+    };
 
-    let borrowed_ratio = amount.checked_div(bank.total_borrowed).unwrap();
-    let users_shares = bank.total_borrowed_shares.checked_mul(borrowed_ratio).unwrap();
-    
-    let user = &mut ctx.accounts.user_account;
-    
-    match ctx.accounts.mint.to_account_info().key() {
-        key if key == user.usdc_address => {
-            user.borrowed_usdc -= amount;
-            user.borrowed_usdc_shares -= users_shares;
-        },
-        _ => {
-            user.borrowed_sol -= amount;
-            user.borrowed_sol_shares -= users_shares; 
-        }
+    // Repaying frees the same share of the isolated debt the borrow was charged
+    let released = isolated_debt_released(&user.positions[index], users_shares);
+    if released > 0 {
+        let isolated_bank: &mut Account<'info, Bank> = if user.isolated_bank == bank.key() {
+            &mut *bank
+        } else {
+            ctx.accounts.isolated_bank
+                .as_mut()
+                .filter(|isolated_bank| isolated_bank.key() == user.isolated_bank)
+                .ok_or(ErrorCode::IsolationViolation)?
+        };
+        isolated_bank.isolated_debt = isolated_bank.isolated_debt.saturating_sub(released);
+        user.positions[index].isolated_debt -= released;
     }
 
+    user.positions[index].borrow_shares -= users_shares;
+    close_empty_positions(user);
 
-    bank.total_borrowed -= amount;
+    bank.total_borrowed = bank.total_borrowed.saturating_sub(received);
     bank.total_borrowed_shares -= users_shares;
 
     Ok(())
@@ -0,0 +1,316 @@
+use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+use crate::constants::{BASIS_POINTS, MAX_USER_POSITIONS, NO_EMODE, SECONDS_PER_YEAR, USD_DECIMALS};
+use crate::error::ErrorCode;
+use crate::state::*;
+
+// Shares are converted at the bank's exchange rate, total_assets / total_shares, in u128 fixed point.
+// Every conversion rounds in the bank's favor so no sequence of calls can extract more than was put in.
+
+// Shares minted or burned for `amount` assets, rounded down
+pub fn assets_to_shares_down(amount: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    if total_assets == 0 || total_shares == 0 {
+        return Ok(amount);
+    }
+    let shares = (amount as u128)
+        .checked_mul(total_shares as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / total_assets as u128;
+    u64::try_from(shares).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Shares minted or burned for `amount` assets, rounded up
+pub fn assets_to_shares_up(amount: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    if total_assets == 0 || total_shares == 0 {
+        return Ok(amount);
+    }
+    let shares = (amount as u128)
+        .checked_mul(total_shares as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .div_ceil(total_assets as u128);
+    u64::try_from(shares).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Assets represented by `shares`, rounded down
+pub fn shares_to_assets_down(shares: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    let assets = (shares as u128)
+        .checked_mul(total_assets as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / total_shares as u128;
+    u64::try_from(assets).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Assets represented by `shares`, rounded up
+pub fn shares_to_assets_up(shares: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    let assets = (shares as u128)
+        .checked_mul(total_assets as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .div_ceil(total_shares as u128);
+    u64::try_from(assets).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// Index of the user's position in `bank`, if they hold one
+pub fn find_position(user: &User, bank: &Pubkey) -> Option<usize> {
+    user.positions.iter().position(|position| position.bank == *bank)
+}
+
+// The user's position in `bank`, opening an empty one if they don't hold one yet
+pub fn get_or_open_position<'a>(user: &'a mut User, bank: &Pubkey) -> Result<&'a mut Position> {
+    let index = match find_position(user, bank) {
+        Some(index) => index,
+        None => {
+            if user.positions.len() >= MAX_USER_POSITIONS {
+                return Err(ErrorCode::TooManyPositions.into());
+            }
+            user.positions.push(Position {
+                bank: *bank,
+                deposit_shares: 0,
+                borrow_shares: 0,
+                isolated_debt: 0,
+            });
+            user.positions.len() - 1
+        }
+    };
+    Ok(&mut user.positions[index])
+}
+
+// Drop positions with neither deposits nor borrows so their slots can be reused
+pub fn close_empty_positions(user: &mut User) {
+    user.positions.retain(|position| position.deposit_shares > 0 || position.borrow_shares > 0);
+
+    // Leaving the isolated bank entirely, with every debt it backed repaid, takes the user out of isolation mode
+    if user.isolated_bank != Pubkey::default()
+        && find_position(user, &user.isolated_bank).is_none()
+        && user.positions.iter().all(|position| position.isolated_debt == 0)
+    {
+        user.isolated_bank = Pubkey::default();
+    }
+}
+
+// Part of a borrow position's isolated debt released when `shares_burned` of its borrow shares are repaid
+pub fn isolated_debt_released(position: &Position, shares_burned: u64) -> u64 {
+    if shares_burned >= position.borrow_shares {
+        return position.isolated_debt;
+    }
+    (position.isolated_debt as u128 * shares_burned as u128 / position.borrow_shares as u128) as u64
+}
+
+// Max LTV and liquidation threshold that apply to a bank's deposits, the e-mode category's
+// when the user opted into the category the bank belongs to
+pub fn risk_params(bank: &Bank, emode: Option<&EModeCategory>) -> (u64, u64) {
+    match emode {
+        Some(category) if bank.emode_category == category.id => (category.max_ltv, category.liquidation_threshold),
+        _ => (bank.max_ltv, bank.liquidation_threshold),
+    }
+}
+
+// Deserialize an account passed through remaining_accounts after checking its owner
+pub fn load_remaining_account<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<T> {
+    if info.owner != &T::owner() {
+        return Err(ErrorCode::InvalidPositionAccounts.into());
+    }
+    let data = info.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+// Share of deposits currently lent out, in basis points
+pub fn utilization(bank: &Bank) -> u64 {
+    if bank.total_deposits == 0 {
+        return 0;
+    }
+    ((bank.total_borrowed as u128 * BASIS_POINTS as u128 / bank.total_deposits as u128) as u64).min(BASIS_POINTS)
+}
+
+// Annual borrow rate in basis points, rising linearly to interest_rate at the optimal utilization
+// and then steeply to max_interest_rate at full utilization
+pub fn borrow_rate(bank: &Bank) -> u64 {
+    let utilization = utilization(bank);
+    let optimal = bank.optimal_utilization * BASIS_POINTS / 100;
+    if utilization <= optimal {
+        bank.interest_rate * utilization / optimal
+    } else {
+        bank.interest_rate
+            + (bank.max_interest_rate - bank.interest_rate) * (utilization - optimal) / (BASIS_POINTS - optimal)
+    }
+}
+
+// Compound interest since last_updated into the bank's totals. Borrowers owe it and depositors earn it,
+// so both exchange rates rise without touching any user's shares.
+pub fn accrue_interest(bank: &mut Bank, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(bank.last_updated);
+    if elapsed <= 0 {
+        return Ok(());
+    }
+    if bank.total_borrowed == 0 {
+        bank.last_updated = now;
+        return Ok(());
+    }
+
+    let interest = (bank.total_borrowed as u128)
+        .checked_mul(borrow_rate(bank) as u128)
+        .and_then(|value| value.checked_mul(elapsed as u128))
+        .ok_or(ErrorCode::MathOverflow)?
+        / (BASIS_POINTS as u128 * SECONDS_PER_YEAR as u128);
+    // Keep the clock where it was until a whole unit has accrued, so frequent touches cannot round interest away
+    if interest == 0 {
+        return Ok(());
+    }
+    let interest = u64::try_from(interest).map_err(|_| error!(ErrorCode::MathOverflow))?;
+
+    bank.total_borrowed = bank.total_borrowed.checked_add(interest).ok_or(ErrorCode::MathOverflow)?;
+    bank.total_deposits = bank.total_deposits.checked_add(interest).ok_or(ErrorCode::MathOverflow)?;
+    bank.last_updated = now;
+    Ok(())
+}
+
+// USD value of `amount` of a bank's asset, scaled by USD_DECIMALS.
+// The price must come from the bank's own feed and be no older than the bank's staleness limit.
+pub fn get_asset_value(bank: &Bank, price_update: &PriceUpdateV2, amount: u64, clock: &Clock) -> Result<u128> {
+    let price = price_update
+        .get_price_no_older_than(clock, bank.max_price_age, &bank.oracle_feed_id)
+        .map_err(|_| error!(ErrorCode::OracleError))?;
+    let price_value = u128::try_from(price.price).map_err(|_| error!(ErrorCode::OracleError))?;
+
+    let value = (amount as u128)
+        .checked_mul(price_value)
+        .and_then(|value| value.checked_mul(10u128.pow(USD_DECIMALS)))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Drop the token decimals and the price exponent, e.g. 9 decimals and a -8 exponent divide by 10^17
+    let scale = bank.decimals as i32 - price.exponent;
+    if scale >= 0 {
+        Ok(value / 10u128.pow(scale as u32))
+    } else {
+        value
+            .checked_mul(10u128.pow((-scale) as u32))
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+}
+
+// Amount of a bank's asset worth `value` USD (scaled by USD_DECIMALS), rounded down
+pub fn get_asset_amount(bank: &Bank, price_update: &PriceUpdateV2, value: u128, clock: &Clock) -> Result<u64> {
+    let price = price_update
+        .get_price_no_older_than(clock, bank.max_price_age, &bank.oracle_feed_id)
+        .map_err(|_| error!(ErrorCode::OracleError))?;
+    let price_value = u128::try_from(price.price).map_err(|_| error!(ErrorCode::OracleError))?;
+    if price_value == 0 {
+        return Err(ErrorCode::OracleError.into());
+    }
+
+    let scale = bank.decimals as i32 - price.exponent;
+    let denominator = price_value
+        .checked_mul(10u128.pow(USD_DECIMALS))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let amount = if scale >= 0 {
+        value
+            .checked_mul(10u128.pow(scale as u32))
+            .ok_or(ErrorCode::MathOverflow)?
+            / denominator
+    } else {
+        value / denominator / 10u128.pow((-scale) as u32)
+    };
+    u64::try_from(amount).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+// USD values of a user's positions, scaled by USD_DECIMALS
+pub struct PositionValues {
+    pub deposit_value: u128,     // deposits at oracle prices
+    pub borrowing_value: u128,   // deposits weighted by each bank's max LTV
+    pub liquidation_value: u128, // deposits weighted by each bank's liquidation threshold
+    pub borrow_value: u128,      // borrows at oracle prices
+    pub emode: Option<EModeCategory>, // category the user opted into
+    pub borrows_outside_emode: bool,  // some borrow is in a bank outside the user's category
+}
+
+// Number of leading remaining accounts used to value the user's positions: a (bank, price update)
+// pair per position plus the e-mode category account when the user opted into one.
+// Any accounts after them are transfer hook extra accounts.
+pub fn position_accounts_len(user: &User) -> usize {
+    let emode_accounts = if user.emode_category == NO_EMODE { 0 } else { 1 };
+    user.positions.len() * 2 + emode_accounts
+}
+
+// Value all the user's deposits and borrows, each priced through its bank's oracle config.
+// remaining_accounts must start with a (bank, price update) pair for every position, in position order,
+// followed by the user's e-mode category account when they opted into one.
+pub fn value_positions(user: &User, remaining_accounts: &[AccountInfo]) -> Result<PositionValues> {
+    let position_accounts = user.positions.len() * 2;
+    if remaining_accounts.len() < position_accounts_len(user) {
+        return Err(ErrorCode::InvalidPositionAccounts.into());
+    }
+
+    let emode = if user.emode_category == NO_EMODE {
+        None
+    } else {
+        let category = load_remaining_account::<EModeCategory>(&remaining_accounts[position_accounts])?;
+        if category.id != user.emode_category {
+            return Err(ErrorCode::InvalidPositionAccounts.into());
+        }
+        Some(category)
+    };
+
+    let clock = Clock::get()?;
+    let mut values = PositionValues {
+        deposit_value: 0,
+        borrowing_value: 0,
+        liquidation_value: 0,
+        borrow_value: 0,
+        emode: None,
+        borrows_outside_emode: false,
+    };
+
+    for (position, accounts) in user.positions.iter().zip(remaining_accounts[..position_accounts].chunks(2)) {
+        if accounts[0].key() != position.bank {
+            return Err(ErrorCode::InvalidPositionAccounts.into());
+        }
+        // Banks are read as passed in, so accrue a copy to value positions at current exchange rates
+        let mut bank = load_remaining_account::<Bank>(&accounts[0])?;
+        accrue_interest(&mut bank, clock.unix_timestamp)?;
+        let price_update = load_remaining_account::<PriceUpdateV2>(&accounts[1])?;
+
+        let deposited = shares_to_assets_down(position.deposit_shares, bank.total_deposits, bank.total_deposit_shares)?;
+        let borrowed = shares_to_assets_up(position.borrow_shares, bank.total_borrowed, bank.total_borrowed_shares)?;
+
+        let (max_ltv, liquidation_threshold) = risk_params(&bank, emode.as_ref());
+        let deposit_value = get_asset_value(&bank, &price_update, deposited, &clock)?;
+        values.deposit_value += deposit_value;
+        values.borrowing_value += deposit_value * max_ltv as u128 / 100;
+        values.liquidation_value += deposit_value * liquidation_threshold as u128 / 100;
+        values.borrow_value += get_asset_value(&bank, &price_update, borrowed, &clock)?;
+
+        if position.borrow_shares > 0 && emode.is_some() && bank.emode_category != user.emode_category {
+            values.borrows_outside_emode = true;
+        }
+    }
+
+    values.emode = emode;
+    Ok(values)
+}
+
+// Health check shared by withdraw and borrow. Starting from the user's current position values, drop
+// `withdrawn_value` of collateral from `bank` and add `borrowed_value` of debt, then require the debt
+// to stay within every deposit's max LTV (its e-mode category's when the user opted in).
+pub fn check_health(
+    values: &PositionValues,
+    bank: &Bank,
+    withdrawn_value: u128,
+    borrowed_value: u128,
+    error: ErrorCode,
+) -> Result<()> {
+    let (max_ltv, _) = risk_params(bank, values.emode.as_ref());
+    let borrowing_value = values
+        .borrowing_value
+        .saturating_sub(withdrawn_value * max_ltv as u128 / 100);
+    let borrow_value = values.borrow_value.checked_add(borrowed_value).ok_or(ErrorCode::MathOverflow)?;
+
+    if borrow_value > borrowing_value {
+        return Err(error.into());
+    }
+    Ok(())
+}
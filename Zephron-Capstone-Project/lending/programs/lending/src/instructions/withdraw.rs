@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token_interface::{ self, Mint, TokenAccount, TokenInterface, TransferChecked };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
 use crate::state::*;
 use crate::error::ErrorCode;
+use crate::instructions::utils::{
+    accrue_interest, assets_to_shares_up, check_health, close_empty_positions, find_position, get_asset_value,
+    load_remaining_account, position_accounts_len, shares_to_assets_down, value_positions,
+};
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
@@ -40,21 +45,36 @@ pub struct Withdraw<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+pub fn process_withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>, amount: u64) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
     let user = &mut ctx.accounts.user_account;
+    accrue_interest(bank, Clock::get()?.unix_timestamp)?;
 
-    let deposited_value; 
+    let index = find_position(user, &bank.key()).ok_or(ErrorCode::PositionNotFound)?;
+    let user_shares = user.positions[index].deposit_shares;
 
-    if ctx.accounts.mint.to_account_info().key() == user.usdc_address {
-        deposited_value = user.deposited_usdc;
-    } else {
-        deposited_value = user.deposited_sol;
-    }
+    // Deposit is worth the user's shares at the current exchange rate, rounded down
+    let deposited_value = shares_to_assets_down(user_shares, bank.total_deposits, bank.total_deposit_shares)?;
 
     if amount > deposited_value {
         return Err(ErrorCode::InsufficientFunds.into());
     }
 
+    // With outstanding borrows, the remaining deposits must still cover them at max LTV.
+    // Remaining accounts then start with a (bank, price update) pair per position, as for borrow.
+    let mut hook_accounts = ctx.remaining_accounts;
+    if user.positions.iter().any(|position| position.borrow_shares > 0) {
+        let values = value_positions(user, ctx.remaining_accounts)?;
+        hook_accounts = &ctx.remaining_accounts[position_accounts_len(user)..];
+        let price_update = load_remaining_account::<PriceUpdateV2>(&ctx.remaining_accounts[index * 2 + 1])?;
+        let withdrawn_value = get_asset_value(bank, &price_update, amount, &Clock::get()?)?;
+        check_health(&values, bank, withdrawn_value, 0, ErrorCode::UnderCollateralized)?;
+    }
+
+    // Shares burned are rounded up so the bank never pays out more than the shares are worth
+    let shares_to_remove = assets_to_shares_up(amount, bank.total_deposits, bank.total_deposit_shares)?
+        .min(user_shares);
+
     let transfer_cpi_accounts = TransferChecked {
         from: ctx.accounts.bank_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
@@ -71,25 +91,22 @@ pub fn process_withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
             &[ctx.bumps.bank_token_account],
         ],
     ];
-    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts).with_signer(signer_seeds);
+    // The rest of the remaining accounts are the extra accounts a transfer hook mint needs
+    let cpi_ctx = CpiContext::new(cpi_program, transfer_cpi_accounts)
+        .with_signer(signer_seeds)
+        .with_remaining_accounts(hook_accounts.to_vec());
 
     let decimals = ctx.accounts.mint.decimals;
 
     token_interface::transfer_checked(cpi_ctx, amount, decimals)?;
 
-    let bank = &mut ctx.accounts.bank;
-    let shares_to_remove = (amount as f64 / bank.total_deposits as f64) * bank.total_deposit_shares as f64;
-
-    let user = &mut ctx.accounts.user_account;
-    
-    if ctx.accounts.mint.to_account_info().key() == user.usdc_address {
-        user.deposited_usdc -= shares_to_remove as u64;
-    } else {
-        user.deposited_sol -= shares_to_remove as u64;
-    }
+    user.positions[index].deposit_shares -= shares_to_remove;
+    close_empty_positions(user);
 
     bank.total_deposits -= amount;
-    bank.total_deposit_shares -= shares_to_remove as u64;
+    bank.total_deposit_shares -= shares_to_remove;
+
+    user.last_updated = Clock::get()?.unix_timestamp;
     
     Ok(())    
 }
\ No newline at end of file
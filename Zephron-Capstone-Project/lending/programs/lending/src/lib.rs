@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
 use instructions::*;
+use state::BankConfig;
 
-mod state;
+// state, error and constants are public so programs built with the `cpi` feature can read banks and users
+pub mod state;
 mod instructions;
-mod error;
-mod constants;
+pub mod error;
+pub mod constants;
 
 declare_id!("33s5M4sRp6LBV8mwHJz1EssyhQ3EHrHnDqQ94N1vy74q");
 
@@ -13,31 +15,51 @@ pub mod lending_protocol {
 
     use super::*;
 
-    pub fn init_bank(ctx: Context<InitBank>, liquidation_threshold: u64, max_ltv: u64) -> Result<()> {
-        process_init_bank(ctx, liquidation_threshold, max_ltv)
+    pub fn init_protocol(ctx: Context<InitProtocol>) -> Result<()> {
+        process_init_protocol(ctx)
     }
 
-    pub fn init_user(ctx: Context<InitUser>, usdc_address: Pubkey) -> Result<()> {
-        process_init_user(ctx, usdc_address)
+    pub fn init_bank(ctx: Context<InitBank>, config: BankConfig) -> Result<()> {
+        process_init_bank(ctx, config)
     }
 
-    pub fn deposit (ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    pub fn update_bank(ctx: Context<UpdateBank>, config: BankConfig) -> Result<()> {
+        process_update_bank(ctx, config)
+    }
+
+    pub fn init_emode_category(ctx: Context<InitEModeCategory>, id: u8, max_ltv: u64, liquidation_threshold: u64) -> Result<()> {
+        process_init_emode_category(ctx, id, max_ltv, liquidation_threshold)
+    }
+
+    pub fn update_emode_category(ctx: Context<UpdateEModeCategory>, max_ltv: u64, liquidation_threshold: u64) -> Result<()> {
+        process_update_emode_category(ctx, max_ltv, liquidation_threshold)
+    }
+
+    pub fn init_user(ctx: Context<InitUser>) -> Result<()> {
+        process_init_user(ctx)
+    }
+
+    pub fn set_emode(ctx: Context<SetEMode>, category: u8) -> Result<()> {
+        process_set_emode(ctx, category)
+    }
+
+    pub fn deposit<'info>(ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>, amount: u64) -> Result<u64> {
         process_deposit(ctx, amount)
     }
 
-    pub fn withdraw (ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    pub fn withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>, amount: u64) -> Result<()> {
         process_withdraw(ctx, amount)
     }
 
-    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+    pub fn borrow<'info>(ctx: Context<'_, '_, 'info, 'info, Borrow<'info>>, amount: u64) -> Result<()> {
         process_borrow(ctx, amount)
     }
 
-    pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+    pub fn repay<'info>(ctx: Context<'_, '_, 'info, 'info, Repay<'info>>, amount: u64) -> Result<()> {
         process_repay(ctx, amount)
     }
 
-    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        process_liquidate(ctx)
+    pub fn liquidate<'info>(ctx: Context<'_, '_, 'info, 'info, Liquidate<'info>>, repay_amount: u64) -> Result<()> {
+        process_liquidate(ctx, repay_amount)
     }
 }
\ No newline at end of file
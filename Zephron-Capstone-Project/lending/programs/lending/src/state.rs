@@ -1,4 +1,13 @@
 use anchor_lang::prelude::*;
+use crate::constants::MAX_USER_POSITIONS;
+
+// Protocol-wide admin, set once by the upgrade authority with init_protocol. Only its authority can list banks
+// and create or change e-mode categories.
+#[account]
+#[derive(InitSpace)]
+pub struct Protocol {
+    pub authority: Pubkey,
+}
 
 #[account]
 #[derive(InitSpace)]
@@ -15,21 +24,66 @@ pub struct Bank {
     pub max_ltv: u64,
     pub last_updated: i64,
     pub interest_rate: u64,
+    pub max_interest_rate: u64,
+    pub optimal_utilization: u64,
+    pub oracle_feed_id: [u8; 32],
+    pub max_price_age: u64,
+    pub decimals: u8,
+    pub isolated: bool,
+    pub isolated_debt_ceiling: u64,
+    pub isolated_debt: u64,
+    pub emode_category: u8,
+    pub transfer_fee: bool,     // mint withholds a fee on transfers, deposits and repays credit what arrives
+    pub transfer_hook: bool,    // mint calls a hook program, transfers need its extra accounts
+}
+
+// Risk and oracle parameters set by init_bank and update_bank, percentages are whole numbers (80 = 80%).
+// Borrow rates are annual basis points: interest_rate is charged at optimal_utilization and
+// max_interest_rate at full utilization, interpolated linearly on either side of the kink. An isolated
+// bank's deposits can only be held alone as collateral, and the USD value borrowed against them is capped
+// by isolated_debt_ceiling (USD_DECIMALS). emode_category is 0 for banks outside every e-mode category.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BankConfig {
+    pub liquidation_threshold: u64,
+    pub max_ltv: u64,
+    pub liquidation_bonus: u64,
+    pub liquidation_close_factor: u64,
+    pub interest_rate: u64,
+    pub max_interest_rate: u64,
+    pub optimal_utilization: u64,
+    pub oracle_feed_id: [u8; 32],
+    pub max_price_age: u64,
+    pub isolated: bool,
+    pub isolated_debt_ceiling: u64,
+    pub emode_category: u8,
+}
+
+// Efficiency-mode category, correlated banks (e.g. SOL and its LSTs) share a higher LTV and threshold
+// for users who opt into the category
+#[account]
+#[derive(InitSpace)]
+pub struct EModeCategory {
+    pub id: u8,
+    pub max_ltv: u64,
+    pub liquidation_threshold: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Position {
+    pub bank: Pubkey,
+    pub deposit_shares: u64,
+    pub borrow_shares: u64,
+    pub isolated_debt: u64, // USD value of this borrow counted against the isolated bank's debt ceiling
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct User {
     pub owner: Pubkey,
-    pub deposited_sol: u64,
-    pub deposited_sol_shares: u64,
-    pub borrowed_sol: u64,
-    pub borrowed_sol_shares: u64, 
-    pub deposited_usdc: u64,
-    pub deposited_usdc_shares: u64, 
-    pub borrowed_usdc: u64,
-    pub borrowed_usdc_shares: u64, 
-    pub usdc_address: Pubkey,
+    #[max_len(MAX_USER_POSITIONS)]
+    pub positions: Vec<Position>,
     pub health_factor: u64,
     pub last_updated: i64,
+    pub isolated_bank: Pubkey, // isolated bank backing this account's borrows, default when not isolated
+    pub emode_category: u8,    // e-mode category the user opted into, 0 for none
 }
@@ -27,6 +27,16 @@ class PythSolanaReceiver {
 import IDL from "../target/idl/lending_protocol.json";
 import { LendingProtocol } from "../target/types/lending_protocol";
 import { BankrunContextWrapper } from "../bankrun-utils/bankrunConnection";
+import { setUpgradeAuthority } from "../bankrun-utils/upgradeableProgram";
+
+// Helper to print a user's positions
+function formatPositions(userInfo: any) {
+  return userInfo.positions.map((position: any) => ({
+    bank: position.bank.toBase58(),
+    depositShares: position.depositShares.toString(),
+    borrowShares: position.borrowShares.toString(),
+  }));
+}
 
 // Helper function to create Solana Explorer links
 function getExplorerLink(txSignature: string | any, cluster: string = "devnet"): string {
@@ -54,6 +64,7 @@ describe("Lending Smart Contract Tests", async () => {
   let banksClient: BanksClient;
   let context: ProgramTestContext;
   let bankrunContextWrapper: BankrunContextWrapper;
+  let programData: PublicKey;
 
   // Mints
   let mintUSDC: PublicKey;
@@ -61,8 +72,34 @@ describe("Lending Smart Contract Tests", async () => {
 
   // Pyth setup
   const pyth = new PublicKey("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
-  const SOL_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+
+  // A (bank, price update) pair for every position the user holds, in position order
+  const positionAccounts = (userInfo: any) =>
+    userInfo.positions.flatMap((position: any) => [
+      { pubkey: position.bank, isWritable: false, isSigner: false },
+      { pubkey: pyth, isWritable: false, isSigner: false },
+    ]);
+
+  const SOL_PRICE_FEED_ID = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
   const USDC_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const MAX_PRICE_AGE = new BN(100); // seconds
+  // Banks store the Pyth feed ID as raw bytes
+  const feedIdBytes = (feedId: string) => Array.from(Buffer.from(feedId.replace(/^0x/, ""), "hex"));
+  // 80% liquidation threshold, 75% max LTV, 5% liquidation bonus, 50% close factor, 5% borrow rate at 80% utilization rising to 50% at full
+  const bankConfig = (feedId: string) => ({
+    liquidationThreshold: new BN(80),
+    maxLtv: new BN(75),
+    liquidationBonus: new BN(5),
+    liquidationCloseFactor: new BN(50),
+    interestRate: new BN(500),
+    maxInterestRate: new BN(5000),
+    optimalUtilization: new BN(80),
+    isolated: false,
+    isolatedDebtCeiling: new BN(0),
+    emodeCategory: 0,
+    oracleFeedId: feedIdBytes(feedId),
+    maxPriceAge: MAX_PRICE_AGE,
+  });
 
   before(async () => {
     // Setup Bankrun context
@@ -82,6 +119,8 @@ describe("Lending Smart Contract Tests", async () => {
     program = new Program<LendingProtocol>(IDL as LendingProtocol, provider);
     banksClient = context.banksClient;
     signer = provider.wallet.payer;
+    // The test wallet deploys the program, so it can initialize the protocol
+    programData = await setUpgradeAuthority(context, program.programId, signer.publicKey);
 
     // Create mints
     mintUSDC = await createMint(
@@ -138,7 +177,7 @@ describe("Lending Smart Contract Tests", async () => {
 
   it("Test Init User", async () => {
     const initUserTx = await program.methods
-      .initUser(mintUSDC)
+      .initUser()
       .accounts({
         signer: signer.publicKey,
       })
@@ -151,18 +190,31 @@ describe("Lending Smart Contract Tests", async () => {
     const userAccountInfo = await program.account.user.fetch(userAccount);
     console.log("User Account Info:", {
       owner: userAccountInfo.owner.toBase58(),
-      usdcAddress: userAccountInfo.usdcAddress.toBase58(),
+      positions: userAccountInfo.positions.length,
       lastUpdated: userAccountInfo.lastUpdated.toString()
     });
   });
 
+  it("Test Init Protocol", async () => {
+    const initTx = await program.methods
+      .initProtocol()
+      .accounts({
+        signer: signer.publicKey,
+        programData,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    console.log("✅ Init Protocol:", initTx);
+  });
+
   it("Test Init and Fund USDC Bank", async () => {
     const initUSDCBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(USDC_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -205,11 +257,12 @@ describe("Lending Smart Contract Tests", async () => {
 
   it("Test Init and Fund SOL Bank", async () => {
     const initSOLBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(SOL_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -331,10 +384,7 @@ describe("Lending Smart Contract Tests", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after USDC deposit:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("User after USDC deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -360,10 +410,7 @@ describe("Lending Smart Contract Tests", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after SOL deposit:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("User after SOL deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -379,6 +426,7 @@ describe("Lending Smart Contract Tests", async () => {
     
     try {
       const borrowAmount = new BN(1 * 10 ** 9); // 1 SOL
+      const userBefore = await program.account.user.fetch(userAccount);
       const borrowSOL = await program.methods
         .borrow(borrowAmount)
         .accounts({
@@ -386,7 +434,9 @@ describe("Lending Smart Contract Tests", async () => {
           mint: mintSOL,
           priceUpdate: pyth, // Mock Pyth price update account
           tokenProgram: TOKEN_PROGRAM_ID,
+          isolatedBank: null,
         })
+        .remainingAccounts(positionAccounts(userBefore))
         .rpc({ commitment: "confirmed" });
 
       console.log("✅ Borrow SOL with Mock Oracle:", borrowSOL);
@@ -394,10 +444,7 @@ describe("Lending Smart Contract Tests", async () => {
 
       // Verify user account state
       const userInfo = await program.account.user.fetch(userAccount);
-      console.log("User after SOL borrow:", {
-        borrowedSol: userInfo.borrowedSol.toString(),
-        borrowedSolShares: userInfo.borrowedSolShares.toString()
-      });
+      console.log("User after SOL borrow:", formatPositions(userInfo));
 
       // Verify bank state
       const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -413,6 +460,7 @@ describe("Lending Smart Contract Tests", async () => {
 
   it("Test Withdraw USDC", async () => {
     const withdrawAmount = new BN(1_000 * 10 ** 6); // 1k USDC
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawUSDC = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -420,6 +468,7 @@ describe("Lending Smart Contract Tests", async () => {
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw USDC:", withdrawUSDC);
@@ -427,10 +476,7 @@ describe("Lending Smart Contract Tests", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after USDC withdraw:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("User after USDC withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -450,6 +496,7 @@ describe("Lending Smart Contract Tests", async () => {
 
   it("Test Withdraw SOL", async () => {
     const withdrawAmount = new BN(1 * 10 ** 9); // 1 SOL
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawSOL = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -457,6 +504,7 @@ describe("Lending Smart Contract Tests", async () => {
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw SOL:", withdrawSOL);
@@ -464,10 +512,7 @@ describe("Lending Smart Contract Tests", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after SOL withdraw:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("User after SOL withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -492,10 +537,7 @@ describe("Lending Smart Contract Tests", async () => {
     const userInfo = await program.account.user.fetch(userAccount);
     console.log("Final User State:", {
       owner: userInfo.owner.toBase58(),
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedSol: userInfo.depositedSol.toString(),
-      borrowedSol: userInfo.borrowedSol.toString(),
-      borrowedUsdc: userInfo.borrowedUsdc.toString(),
+      positions: formatPositions(userInfo),
       lastUpdated: userInfo.lastUpdated.toString()
     });
 
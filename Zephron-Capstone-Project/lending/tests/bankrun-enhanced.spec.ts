@@ -13,6 +13,7 @@ import { PublicKey, Keypair, Connection } from "@solana/web3.js";
 import IDL from "../target/idl/lending_protocol.json";
 import { LendingProtocol } from "../target/types/lending_protocol";
 import { BankrunContextWrapper } from "../bankrun-utils/bankrunConnection";
+import { setUpgradeAuthority } from "../bankrun-utils/upgradeableProgram";
 
 describe("Lending Smart Contract Tests - Enhanced Version", async () => {
   let signer: Keypair;
@@ -35,8 +36,35 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   // Pyth setup
   const pyth = new PublicKey("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
-  const SOL_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const SOL_PRICE_FEED_ID = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
   const USDC_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const MAX_PRICE_AGE = new BN(100); // seconds
+  // Banks store the Pyth feed ID as raw bytes
+  const feedIdBytes = (feedId: string) => Array.from(Buffer.from(feedId.replace(/^0x/, ""), "hex"));
+  // 80% liquidation threshold, 75% max LTV, 5% liquidation bonus, 50% close factor, 5% borrow rate at 80% utilization rising to 50% at full
+  const bankConfig = (feedId: string) => ({
+    liquidationThreshold: new BN(80),
+    maxLtv: new BN(75),
+    liquidationBonus: new BN(5),
+    liquidationCloseFactor: new BN(50),
+    interestRate: new BN(500),
+    maxInterestRate: new BN(5000),
+    optimalUtilization: new BN(80),
+    isolated: false,
+    isolatedDebtCeiling: new BN(0),
+    emodeCategory: 0,
+    oracleFeedId: feedIdBytes(feedId),
+    maxPriceAge: MAX_PRICE_AGE,
+  });
+
+  // Helper to print a user's positions
+  function formatPositions(userInfo: any) {
+    return userInfo.positions.map((position: any) => ({
+      bank: position.bank.toBase58(),
+      depositShares: position.depositShares.toString(),
+      borrowShares: position.borrowShares.toString(),
+    }));
+  }
 
   // Helper function to create Solana Explorer links
   function getExplorerLink(txSignature: string | any, cluster: string = "devnet"): string {
@@ -76,6 +104,14 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     .toBase58();
 
   const solUsdPriceFeedAccountPubkey = new PublicKey(solUsdPriceFeedAccount);
+
+  // A (bank, price update) pair for every position the user holds, in position order
+  const positionAccounts = (userInfo: any) =>
+    userInfo.positions.flatMap((position: any) => [
+      { pubkey: position.bank, isWritable: false, isSigner: false },
+      { pubkey: solUsdPriceFeedAccountPubkey, isWritable: false, isSigner: false },
+    ]);
+
   const feedAccountInfo = await devnetConnection.getAccountInfo(
     solUsdPriceFeedAccountPubkey
   );
@@ -88,6 +124,8 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
   program = new Program<LendingProtocol>(IDL as LendingProtocol, provider);
   banksClient = context.banksClient;
   signer = provider.wallet.payer;
+  // The test wallet deploys the program, so it can initialize the protocol
+  const programData = await setUpgradeAuthority(context, program.programId, signer.publicKey);
 
   // Create mints with proper decimals
   mintUSDC = await createMint(
@@ -143,7 +181,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init User", async () => {
     const initUserTx = await program.methods
-      .initUser(mintUSDC)
+      .initUser()
       .accounts({
         signer: signer.publicKey,
       })
@@ -156,18 +194,31 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     const userAccountInfo = await program.account.user.fetch(userAccount);
     console.log("👤 User Account Info:", {
       owner: userAccountInfo.owner.toBase58(),
-      usdcAddress: userAccountInfo.usdcAddress.toBase58(),
+      positions: userAccountInfo.positions.length,
       lastUpdated: userAccountInfo.lastUpdated.toString()
     });
   });
 
+  it("Test Init Protocol", async () => {
+    const initTx = await program.methods
+      .initProtocol()
+      .accounts({
+        signer: signer.publicKey,
+        programData,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    console.log("✅ Init Protocol:", initTx);
+  });
+
   it("Test Init and Fund USDC Bank", async () => {
     const initUSDCBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(USDC_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -209,11 +260,12 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init and Fund SOL Bank", async () => {
     const initSOLBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(SOL_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -326,10 +378,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("👤 User after USDC deposit:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("👤 User after USDC deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -355,10 +404,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("👤 User after SOL deposit:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("👤 User after SOL deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -373,6 +419,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     
     try {
       const borrowAmount = new BN(1 * 10 ** 9); // 1 SOL
+      const userBefore = await program.account.user.fetch(userAccount);
       const borrowSOL = await program.methods
         .borrow(borrowAmount)
         .accounts({
@@ -380,7 +427,9 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
           mint: mintSOL,
           tokenProgram: TOKEN_PROGRAM_ID,
           priceUpdate: solUsdPriceFeedAccountPubkey,
+          isolatedBank: null,
         })
+        .remainingAccounts(positionAccounts(userBefore))
         .rpc({ commitment: "confirmed" });
 
       console.log("✅ Borrow SOL with Real Oracle:", borrowSOL);
@@ -388,10 +437,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
       // Verify user account state
       const userInfo = await program.account.user.fetch(userAccount);
-      console.log("👤 User after SOL borrow:", {
-        borrowedSol: userInfo.borrowedSol.toString(),
-        borrowedSolShares: userInfo.borrowedSolShares.toString()
-      });
+      console.log("👤 User after SOL borrow:", formatPositions(userInfo));
 
       // Verify bank state
       const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -414,6 +460,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
           signer: signer.publicKey,
           mint: mintSOL,
           tokenProgram: TOKEN_PROGRAM_ID,
+          isolatedBank: null,
         })
         .rpc({ commitment: "confirmed" });
 
@@ -422,10 +469,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
       // Verify user account state
       const userInfo = await program.account.user.fetch(userAccount);
-      console.log("👤 User after SOL repay:", {
-        borrowedSol: userInfo.borrowedSol.toString(),
-        borrowedSolShares: userInfo.borrowedSolShares.toString()
-      });
+      console.log("👤 User after SOL repay:", formatPositions(userInfo));
 
       // Verify bank state
       const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -441,6 +485,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Withdraw USDC", async () => {
     const withdrawAmount = new BN(1_000 * 10 ** 6); // 1k USDC
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawUSDC = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -448,6 +493,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw USDC:", withdrawUSDC);
@@ -455,10 +501,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("👤 User after USDC withdraw:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("👤 User after USDC withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -478,6 +521,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Withdraw SOL", async () => {
     const withdrawAmount = new BN(1 * 10 ** 9); // 1 SOL
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawSOL = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -485,6 +529,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw SOL:", withdrawSOL);
@@ -492,10 +537,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("👤 User after SOL withdraw:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("👤 User after SOL withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -520,10 +562,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     const userInfo = await program.account.user.fetch(userAccount);
     console.log("👤 Final User State:", {
       owner: userInfo.owner.toBase58(),
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedSol: userInfo.depositedSol.toString(),
-      borrowedSol: userInfo.borrowedSol.toString(),
-      borrowedUsdc: userInfo.borrowedUsdc.toString(),
+      positions: formatPositions(userInfo),
       lastUpdated: userInfo.lastUpdated.toString()
     });
 
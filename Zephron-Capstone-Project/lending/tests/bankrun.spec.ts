@@ -1,12 +1,46 @@
 import { describe, it } from "node:test";
 import { BN, Program } from "@coral-xyz/anchor";
 import { BankrunProvider } from "anchor-bankrun";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  getAssociatedTokenAddressSync,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeInterestBearingMintInstruction,
+  createInitializeMetadataPointerInstruction,
+  createAssociatedTokenAccountInstruction,
+  createMintToInstruction,
+} from "@solana/spl-token";
 import { createAccount, createMint, mintTo, getAccount } from "spl-token-bankrun";
 
-import { startAnchor, BanksClient, ProgramTestContext } from "solana-bankrun";
-
-import { PublicKey, Keypair, Connection } from "@solana/web3.js";
+import { startAnchor, BanksClient, ProgramTestContext, Clock } from "solana-bankrun";
+
+import { PublicKey, Keypair, Connection, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { createHash } from "crypto";
+
+// Pyth push oracle and receiver programs, price update accounts are owned by the receiver
+const PYTH_PUSH_ORACLE_ID = new PublicKey("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+const PYTH_RECEIVER_ID = new PublicKey("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// Borsh layout of a fully verified PriceUpdateV2 account, as the receiver program writes it
+function encodePriceUpdate(feedId: string, price: bigint, exponent: number, publishTime: bigint): Buffer {
+  const data = Buffer.alloc(134);
+  createHash("sha256").update("account:PriceUpdateV2").digest().copy(data, 0, 0, 8);
+  // write_authority stays zeroed, verification_level is Full
+  data.writeUInt8(1, 40);
+  Buffer.from(feedId.replace(/^0x/, ""), "hex").copy(data, 41);
+  data.writeBigInt64LE(price, 73);
+  data.writeBigUInt64LE(BigInt(0), 81); // conf
+  data.writeInt32LE(exponent, 89);
+  data.writeBigInt64LE(publishTime, 93);
+  data.writeBigInt64LE(publishTime, 101); // prev_publish_time
+  data.writeBigInt64LE(price, 109); // ema_price
+  data.writeBigUInt64LE(BigInt(0), 117); // ema_conf
+  return data;
+}
 
 // Mock Pyth implementation to avoid dependency issues
 class PythSolanaReceiver {
@@ -15,9 +49,14 @@ class PythSolanaReceiver {
   }
 
   getPriceFeedAccountAddress(version: number, feedId: string): { toBase58(): string } {
-    // Return a mock price feed account address
-    const mockAddress = "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE";
-    return { toBase58: () => mockAddress };
+    // Same derivation as the push oracle, one account per shard and feed
+    const shard = Buffer.alloc(2);
+    shard.writeUInt16LE(version);
+    const [address] = PublicKey.findProgramAddressSync(
+      [shard, Buffer.from(feedId.replace(/^0x/, ""), "hex")],
+      PYTH_PUSH_ORACLE_ID
+    );
+    return address;
   }
 }
 
@@ -25,6 +64,16 @@ class PythSolanaReceiver {
 import IDL from "../target/idl/lending_protocol.json";
 import { LendingProtocol } from "../target/types/lending_protocol";
 import { BankrunContextWrapper } from "../bankrun-utils/bankrunConnection";
+import { setUpgradeAuthority } from "../bankrun-utils/upgradeableProgram";
+
+// Helper to print a user's positions
+function formatPositions(userInfo: any) {
+  return userInfo.positions.map((position: any) => ({
+    bank: position.bank.toBase58(),
+    depositShares: position.depositShares.toString(),
+    borrowShares: position.borrowShares.toString(),
+  }));
+}
 
 // Helper function to create Solana Explorer links
 function getExplorerLink(txSignature: string | any, cluster: string = "devnet"): string {
@@ -57,8 +106,26 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   // Pyth setup
   const pyth = new PublicKey("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
-  const SOL_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const SOL_PRICE_FEED_ID = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
   const USDC_PRICE_FEED_ID = "0xeaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const MAX_PRICE_AGE = new BN(100); // seconds
+  // Banks store the Pyth feed ID as raw bytes
+  const feedIdBytes = (feedId: string) => Array.from(Buffer.from(feedId.replace(/^0x/, ""), "hex"));
+  // 80% liquidation threshold, 75% max LTV, 5% liquidation bonus, 50% close factor, 5% borrow rate at 80% utilization rising to 50% at full
+  const bankConfig = (feedId: string) => ({
+    liquidationThreshold: new BN(80),
+    maxLtv: new BN(75),
+    liquidationBonus: new BN(5),
+    liquidationCloseFactor: new BN(50),
+    interestRate: new BN(500),
+    maxInterestRate: new BN(5000),
+    optimalUtilization: new BN(80),
+    isolated: false,
+    isolatedDebtCeiling: new BN(0),
+    emodeCategory: 0,
+    oracleFeedId: feedIdBytes(feedId),
+    maxPriceAge: MAX_PRICE_AGE,
+  });
 
   // Setup Bankrun context
   context = await startAnchor(
@@ -80,6 +147,58 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     .toBase58();
 
   const solUsdPriceFeedAccountPubkey = new PublicKey(solUsdPriceFeedAccount);
+  const usdcUsdPriceFeedAccountPubkey = new PublicKey(
+    pythSolanaReceiver.getPriceFeedAccountAddress(0, USDC_PRICE_FEED_ID).toBase58()
+  );
+
+  // Prices in USD with an exponent of -8
+  const prices = { sol: BigInt(150 * 10 ** 8), usdc: BigInt(1 * 10 ** 8) };
+
+  // Publish both price updates at the current bank clock, so they pass each bank's staleness check
+  const publishPrices = async () => {
+    const clock = await context.banksClient.getClock();
+    for (const [pubkey, feedId, price] of [
+      [solUsdPriceFeedAccountPubkey, SOL_PRICE_FEED_ID, prices.sol],
+      [usdcUsdPriceFeedAccountPubkey, USDC_PRICE_FEED_ID, prices.usdc],
+    ] as [PublicKey, string, bigint][]) {
+      context.setAccount(pubkey, {
+        lamports: 1_000_000_000,
+        data: encodePriceUpdate(feedId, price, -8, clock.unixTimestamp),
+        owner: PYTH_RECEIVER_ID,
+        executable: false,
+      });
+    }
+  };
+  await publishPrices();
+
+  // Each bank is priced by its own feed, the Token-2022 test banks are USD pegged like USDC
+  const priceUpdateFor = (bank: PublicKey) =>
+    bank.equals(solBankAccount) ? solUsdPriceFeedAccountPubkey : usdcUsdPriceFeedAccountPubkey;
+
+  // A (bank, price update) pair for every position the user holds, in position order
+  const positionAccounts = (userInfo: any) =>
+    userInfo.positions.flatMap((position: any) => [
+      { pubkey: position.bank, isWritable: false, isSigner: false },
+      { pubkey: priceUpdateFor(position.bank), isWritable: false, isSigner: false },
+    ]);
+
+  // Create a 6 decimal Token-2022 mint with one extension, set up by `extensionIx` before the mint is initialized
+  const createToken2022Mint = async (mint: Keypair, extension: ExtensionType, extensionIx: TransactionInstruction) => {
+    const space = getMintLen([extension]);
+    const rent = await banksClient.getRent();
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: signer.publicKey,
+        newAccountPubkey: mint.publicKey,
+        space,
+        lamports: Number(rent.minimumBalance(BigInt(space))),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      extensionIx,
+      createInitializeMintInstruction(mint.publicKey, 6, signer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+    );
+    await provider.sendAndConfirm(tx, [mint]);
+  };
 
   console.log("🔧 Price Feed Account:", solUsdPriceFeedAccount);
   console.log("🔧 Using Mock Pyth Implementation");
@@ -87,6 +206,8 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
   program = new Program<LendingProtocol>(IDL as LendingProtocol, provider);
   banksClient = context.banksClient;
   signer = provider.wallet.payer;
+  // The test wallet deploys the program, so it can initialize the protocol
+  const programData = await setUpgradeAuthority(context, program.programId, signer.publicKey);
 
   // Create mints with proper decimals
   mintUSDC = await createMint(
@@ -142,7 +263,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init User", async () => {
     const initUserTx = await program.methods
-      .initUser(mintUSDC)
+      .initUser()
       .accounts({
         signer: signer.publicKey,
       })
@@ -155,18 +276,90 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     const userAccountInfo = await program.account.user.fetch(userAccount);
     console.log("👤 User Account Info:", {
       owner: userAccountInfo.owner.toBase58(),
-      usdcAddress: userAccountInfo.usdcAddress.toBase58(),
+      positions: formatPositions(userAccountInfo),
       lastUpdated: userAccountInfo.lastUpdated.toString()
     });
   });
 
+  it("Test Init Protocol", async () => {
+    // Only the program's upgrade authority can take the protocol
+    const outsider = Keypair.generate();
+    context.setAccount(outsider.publicKey, {
+      lamports: 1 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    let rejected = false;
+    try {
+      await program.methods
+        .initProtocol()
+        .accounts({
+          signer: outsider.publicKey,
+          programData,
+        })
+        .signers([outsider])
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Init Protocol from a non-upgrade authority rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("initProtocol accepted a signer other than the upgrade authority");
+    }
+
+    const initTx = await program.methods
+      .initProtocol()
+      .accounts({
+        signer: signer.publicKey,
+        programData,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    console.log("✅ Init Protocol:", initTx);
+  });
+
+  it("Test Init Bank rejects a signer other than the protocol authority", async () => {
+    // Listing a bank picks its oracle and risk parameters, so it is reserved to the protocol authority
+    const outsider = Keypair.generate();
+    context.setAccount(outsider.publicKey, {
+      lamports: 1 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    let rejected = false;
+    try {
+      await program.methods
+        .initBank(bankConfig(SOL_PRICE_FEED_ID))
+        .accounts({
+          authority: outsider.publicKey,
+          mint: mintUSDC,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          emodeCategory: null, // outside every e-mode category
+        })
+        .signers([outsider])
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Init Bank from a non-authority rejected:", error.message);
+      if (!error.message.includes("Unauthorized")) {
+        throw new Error(`initBank rejected an outsider for the wrong reason: ${error.message}`);
+      }
+    }
+    if (!rejected) {
+      throw new Error("initBank accepted a signer other than the protocol authority");
+    }
+  });
+
   it("Test Init and Fund USDC Bank", async () => {
     const initUSDCBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(USDC_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -209,11 +402,12 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Init and Fund SOL Bank", async () => {
     const initSOLBankTx = await program.methods
-      .initBank(new BN(80), new BN(75)) // 80% liquidation threshold, 75% max LTV
+      .initBank(bankConfig(SOL_PRICE_FEED_ID))
       .accounts({
-        signer: signer.publicKey,
+        authority: signer.publicKey,
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
       })
       .rpc({ commitment: "confirmed" });
 
@@ -244,6 +438,149 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     console.log("💰 SOL Treasury Balance:", treasuryBalance.amount.toString());
   });
 
+  it("Test Update Bank", async () => {
+    const updateTx = await program.methods
+      .updateBank({ ...bankConfig(SOL_PRICE_FEED_ID), liquidationBonus: new BN(8) })
+      .accounts({
+        authority: signer.publicKey,
+        bank: solBankAccount,
+        emodeCategory: null, // outside every e-mode category
+      })
+      .rpc({ commitment: "confirmed" });
+
+    console.log("✅ Update SOL Bank:", updateTx);
+    const bankInfo = await program.account.bank.fetch(solBankAccount);
+    console.log("SOL Bank Parameters:", {
+      liquidationThreshold: bankInfo.liquidationThreshold.toString(),
+      maxLtv: bankInfo.maxLtv.toString(),
+      liquidationBonus: bankInfo.liquidationBonus.toString(),
+      liquidationCloseFactor: bankInfo.liquidationCloseFactor.toString(),
+      interestRate: bankInfo.interestRate.toString(),
+      maxInterestRate: bankInfo.maxInterestRate.toString(),
+      optimalUtilization: bankInfo.optimalUtilization.toString()
+    });
+
+    // Max LTV at or above the liquidation threshold is rejected
+    let rejected = false;
+    try {
+      await program.methods
+        .updateBank({ ...bankConfig(SOL_PRICE_FEED_ID), maxLtv: new BN(85) })
+        .accounts({
+          authority: signer.publicKey,
+          bank: solBankAccount,
+          emodeCategory: null, // outside every e-mode category
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Invalid bank config rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("updateBank accepted max LTV above the liquidation threshold");
+    }
+  });
+
+  it("Test Init E-Mode Category", async () => {
+    // Only the protocol authority can create categories
+    const outsider = Keypair.generate();
+    context.setAccount(outsider.publicKey, {
+      lamports: 1 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    let rejected = false;
+    try {
+      await program.methods
+        .initEmodeCategory(1, new BN(90), new BN(93))
+        .accounts({
+          authority: outsider.publicKey,
+        })
+        .signers([outsider])
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ E-mode category from a non-authority rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("initEmodeCategory accepted a signer other than the protocol authority");
+    }
+
+    // Category 1 for correlated SOL assets: 90% max LTV, 93% liquidation threshold
+    const [emodeCategory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("emode"), Buffer.from([1])],
+      program.programId
+    );
+    const initTx = await program.methods
+      .initEmodeCategory(1, new BN(90), new BN(93))
+      .accounts({
+        authority: signer.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    console.log("✅ Init E-Mode Category:", initTx);
+    const categoryInfo = await program.account.eModeCategory.fetch(emodeCategory);
+    console.log("E-Mode Category:", {
+      id: categoryInfo.id,
+      maxLtv: categoryInfo.maxLtv.toString(),
+      liquidationThreshold: categoryInfo.liquidationThreshold.toString()
+    });
+
+    // Max LTV at or above the liquidation threshold is rejected, as for banks
+    rejected = false;
+    try {
+      await program.methods
+        .updateEmodeCategory(new BN(95), new BN(93))
+        .accounts({
+          authority: signer.publicKey,
+          emodeCategory,
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Invalid e-mode category rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("updateEmodeCategory accepted max LTV above the liquidation threshold");
+    }
+
+    // Banks can only join a category that exists
+    const [missingCategory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("emode"), Buffer.from([2])],
+      program.programId
+    );
+    rejected = false;
+    try {
+      await program.methods
+        .updateBank({ ...bankConfig(SOL_PRICE_FEED_ID), liquidationBonus: new BN(8), emodeCategory: 2 })
+        .accounts({
+          authority: signer.publicKey,
+          bank: solBankAccount,
+          emodeCategory: missingCategory,
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Bank in a missing e-mode category rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("updateBank assigned a bank to an e-mode category that does not exist");
+    }
+
+    await program.methods
+      .updateBank({ ...bankConfig(SOL_PRICE_FEED_ID), liquidationBonus: new BN(8), emodeCategory: 1 })
+      .accounts({
+        authority: signer.publicKey,
+        bank: solBankAccount,
+        emodeCategory,
+      })
+      .rpc({ commitment: "confirmed" });
+    const solBankInfo = await program.account.bank.fetch(solBankAccount);
+    if (solBankInfo.emodeCategory !== 1) {
+      throw new Error("SOL bank was not assigned to e-mode category 1");
+    }
+  });
+
   it("Create and Fund User Token Accounts", async () => {
     // Create user token accounts
     usdcTokenAccount = await createAccount(
@@ -327,10 +664,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after USDC deposit:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("User after USDC deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -338,6 +672,12 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
       totalDeposits: bankInfo.totalDeposits.toString(),
       totalDepositShares: bankInfo.totalDepositShares.toString()
     });
+
+    // Shares are minted at the bank exchange rate, so a deposit never rounds to zero shares
+    const usdcPosition = userInfo.positions.find((position: any) => position.bank.equals(usdcBankAccount));
+    if (!usdcPosition || usdcPosition.depositShares.isZero()) {
+      throw new Error("USDC deposit minted zero shares");
+    }
   });
 
   it("Test Deposit SOL", async () => {
@@ -356,10 +696,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after SOL deposit:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("User after SOL deposit:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -369,11 +706,32 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     });
   });
 
+  it("Test Update Bank rejects isolation changes once deposits exist", async () => {
+    let rejected = false;
+    try {
+      await program.methods
+        .updateBank({ ...bankConfig(USDC_PRICE_FEED_ID), isolated: true, isolatedDebtCeiling: new BN(1_000 * 10 ** 6) })
+        .accounts({
+          authority: signer.publicKey,
+          bank: usdcBankAccount,
+          emodeCategory: null, // outside every e-mode category
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Isolation change with deposits rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("updateBank isolated a bank that already holds deposits");
+    }
+  });
+
   it("Test Borrow SOL with Real Pyth Oracle", async () => {
     console.log("🔧 Testing Borrow SOL with Real Pyth Oracle Integration");
     
     try {
       const borrowAmount = new BN(1 * 10 ** 9); // 1 SOL
+      const userBefore = await program.account.user.fetch(userAccount);
       const borrowSOL = await program.methods
         .borrow(borrowAmount)
         .accounts({
@@ -381,7 +739,9 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
           mint: mintSOL,
           tokenProgram: TOKEN_PROGRAM_ID,
           priceUpdate: solUsdPriceFeedAccountPubkey,
+          isolatedBank: null,
         })
+        .remainingAccounts(positionAccounts(userBefore))
         .rpc({ commitment: "confirmed" });
 
       console.log("✅ Borrow SOL with Real Oracle:", borrowSOL);
@@ -389,10 +749,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
       // Verify user account state
       const userInfo = await program.account.user.fetch(userAccount);
-      console.log("👤 User after SOL borrow:", {
-        borrowedSol: userInfo.borrowedSol.toString(),
-        borrowedSolShares: userInfo.borrowedSolShares.toString()
-      });
+      console.log("👤 User after SOL borrow:", formatPositions(userInfo));
 
       // Verify bank state
       const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -406,6 +763,698 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     }
   });
 
+  it("Test Interest Accrual", async () => {
+    const before = await program.account.bank.fetch(solBankAccount);
+    if (before.totalBorrowed.isZero()) {
+      console.log("📝 No SOL borrowed, skipping interest accrual check");
+      return;
+    }
+
+    // Move the clock forward a year, then touch the bank so interest compounds into its totals
+    const currentClock = await banksClient.getClock();
+    context.setClock(
+      new Clock(
+        currentClock.slot,
+        currentClock.epochStartTimestamp,
+        currentClock.epoch,
+        currentClock.leaderScheduleEpoch,
+        currentClock.unixTimestamp + BigInt(365 * 24 * 60 * 60)
+      )
+    );
+    // Prices published before the jump would now be stale
+    await publishPrices();
+
+    await program.methods
+      .deposit(new BN(1 * 10 ** 6))
+      .accounts({
+        signer: signer.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const after = await program.account.bank.fetch(solBankAccount);
+    const interest = after.totalBorrowed.sub(before.totalBorrowed);
+    console.log("SOL Bank interest accrued:", {
+      interest: interest.toString(),
+      totalBorrowed: after.totalBorrowed.toString(),
+      totalDeposits: after.totalDeposits.toString(),
+      lastUpdated: after.lastUpdated.toString(),
+    });
+
+    // Borrowers owe the interest and depositors earn it, so both totals rise by the same amount
+    if (interest.isZero()) {
+      throw new Error("No interest accrued on SOL borrows after a year");
+    }
+    if (!after.totalDeposits.sub(before.totalDeposits).eq(interest.add(new BN(1 * 10 ** 6)))) {
+      throw new Error("Depositors did not earn the interest borrowers were charged");
+    }
+  });
+
+  it("Test Liquidate rejects a healthy user", async () => {
+    // The user's deposits still cover their debt, so a liquidator cannot repay it and seize collateral
+    const userInfo = await program.account.user.fetch(userAccount);
+    let rejected = false;
+    try {
+      await program.methods
+        .liquidate(new BN(0.5 * 10 ** 9))
+        .accounts({
+          liquidator: signer.publicKey,
+          owner: signer.publicKey,
+          collateralMint: mintUSDC,
+          borrowedMint: mintSOL,
+          collateralPriceUpdate: usdcUsdPriceFeedAccountPubkey,
+          borrowedPriceUpdate: solUsdPriceFeedAccountPubkey,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          borrowedTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(positionAccounts(userInfo))
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Healthy user liquidation rejected:", error.message);
+      if (!error.message.includes("NotUndercollateralized")) {
+        throw new Error(`liquidate rejected a healthy user for the wrong reason: ${error.message}`);
+      }
+    }
+    if (!rejected) {
+      throw new Error("liquidate accepted a healthy user");
+    }
+  });
+
+  it("Test Withdraw rejects collateral backing a loan", async () => {
+    const userInfo = await program.account.user.fetch(userAccount);
+    if (!userInfo.positions.some((position: any) => !position.borrowShares.isZero())) {
+      console.log("📝 No outstanding borrows, skipping withdraw health check");
+      return;
+    }
+
+    // Pulling every USDC deposit out would leave the SOL loan without collateral
+    const usdcBankInfo = await program.account.bank.fetch(usdcBankAccount);
+    let rejected = false;
+    try {
+      await program.methods
+        .withdraw(usdcBankInfo.totalDeposits)
+        .accounts({
+          signer: signer.publicKey,
+          mint: mintUSDC,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(positionAccounts(userInfo))
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Undercollateralizing withdraw rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("withdraw released collateral backing an outstanding loan");
+    }
+  });
+
+  it("Test Liquidate an unhealthy user", async () => {
+    // A second user borrows SOL close to the max LTV of their USDC deposit
+    const borrower = Keypair.generate();
+    context.setAccount(borrower.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    const borrowerUsdcAccount = await createAccount(
+      // @ts-ignore
+      banksClient,
+      signer,
+      mintUSDC,
+      borrower.publicKey
+    );
+    await mintTo(
+      // @ts-ignore
+      banksClient,
+      signer,
+      mintUSDC,
+      borrowerUsdcAccount,
+      signer,
+      1_000 * 10 ** 6
+    );
+    const [borrowerAccount] = PublicKey.findProgramAddressSync(
+      [borrower.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initUser()
+      .accounts({ signer: borrower.publicKey })
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .deposit(new BN(1_000 * 10 ** 6)) // 1k USDC
+      .accounts({
+        signer: borrower.publicKey,
+        mint: mintUSDC,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+
+    // $675 of SOL against $750 of borrowing power
+    const borrowerBefore = await program.account.user.fetch(borrowerAccount);
+    await program.methods
+      .borrow(new BN(4.5 * 10 ** 9))
+      .accounts({
+        signer: borrower.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        priceUpdate: solUsdPriceFeedAccountPubkey,
+        isolatedBank: null,
+      })
+      .remainingAccounts(positionAccounts(borrowerBefore))
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+
+    // SOL rallies to $200, the $900 debt now exceeds the $800 liquidation threshold of the deposit
+    prices.sol = BigInt(200 * 10 ** 8);
+    await publishPrices();
+
+    const userBefore = await program.account.user.fetch(borrowerAccount);
+    const usdcBankBefore = await program.account.bank.fetch(usdcBankAccount);
+    const solBankBefore = await program.account.bank.fetch(solBankAccount);
+    // @ts-ignore
+    const liquidatorUsdcBefore = (await getAccount(banksClient, usdcTokenAccount)).amount;
+    // @ts-ignore
+    const liquidatorSolBefore = (await getAccount(banksClient, solTokenAccount)).amount;
+
+    const repayAmount = new BN(2 * 10 ** 9); // 2 SOL, under the 50% close factor
+    const liquidateTx = await program.methods
+      .liquidate(repayAmount)
+      .accounts({
+        liquidator: signer.publicKey,
+        owner: borrower.publicKey,
+        collateralMint: mintUSDC,
+        borrowedMint: mintSOL,
+        collateralPriceUpdate: usdcUsdPriceFeedAccountPubkey,
+        borrowedPriceUpdate: solUsdPriceFeedAccountPubkey,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        borrowedTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(positionAccounts(userBefore))
+      .rpc({ commitment: "confirmed" });
+    console.log("✅ Liquidate:", liquidateTx);
+
+    prices.sol = BigInt(150 * 10 ** 8);
+    await publishPrices();
+
+    const userAfter = await program.account.user.fetch(borrowerAccount);
+    const usdcBankAfter = await program.account.bank.fetch(usdcBankAccount);
+    const solBankAfter = await program.account.bank.fetch(solBankAccount);
+    // @ts-ignore
+    const liquidatorUsdcAfter = (await getAccount(banksClient, usdcTokenAccount)).amount;
+    // @ts-ignore
+    const liquidatorSolAfter = (await getAccount(banksClient, solTokenAccount)).amount;
+    console.log("👤 Borrower after liquidation:", formatPositions(userAfter));
+
+    const position = (userInfo: any, bank: PublicKey) =>
+      userInfo.positions.find((position: any) => position.bank.equals(bank));
+    const depositSharesBurned = position(userBefore, usdcBankAccount).depositShares
+      .sub(position(userAfter, usdcBankAccount).depositShares);
+    const borrowSharesBurned = position(userBefore, solBankAccount).borrowShares
+      .sub(position(userAfter, solBankAccount).borrowShares);
+
+    // $400 of repaid SOL plus the USDC bank's 5% bonus is worth 420 USDC
+    const seized = new BN(420 * 10 ** 6);
+    if (new BN((liquidatorUsdcAfter - liquidatorUsdcBefore).toString()).cmp(seized) !== 0) {
+      throw new Error("liquidator did not receive the repaid value plus the bonus in USDC");
+    }
+    if (new BN((liquidatorSolBefore - liquidatorSolAfter).toString()).cmp(repayAmount) !== 0) {
+      throw new Error("liquidator was not charged the repay amount");
+    }
+
+    // Shares leave the user and the bank together, and the bank totals move by the assets exchanged
+    if (depositSharesBurned.isZero() || borrowSharesBurned.isZero()) {
+      throw new Error("liquidation did not burn the borrower's shares");
+    }
+    if (!usdcBankBefore.totalDepositShares.sub(usdcBankAfter.totalDepositShares).eq(depositSharesBurned)) {
+      throw new Error("USDC bank deposit shares do not match the shares burned from the borrower");
+    }
+    if (!usdcBankBefore.totalDeposits.sub(usdcBankAfter.totalDeposits).eq(seized)) {
+      throw new Error("USDC bank deposits did not fall by the seized collateral");
+    }
+    if (!solBankBefore.totalBorrowedShares.sub(solBankAfter.totalBorrowedShares).eq(borrowSharesBurned)) {
+      throw new Error("SOL bank borrow shares do not match the shares burned from the borrower");
+    }
+    if (!solBankBefore.totalBorrowed.sub(solBankAfter.totalBorrowed).eq(repayAmount)) {
+      throw new Error("SOL bank borrows did not fall by the repay amount");
+    }
+  });
+
+  it("Test Liquidate rejects the same bank as collateral and debt", async () => {
+    // Two copies of one bank in the instruction would overwrite each other's updates
+    const userInfo = await program.account.user.fetch(userAccount);
+    let rejected = false;
+    try {
+      await program.methods
+        .liquidate(new BN(0.5 * 10 ** 9))
+        .accounts({
+          liquidator: signer.publicKey,
+          owner: signer.publicKey,
+          collateralMint: mintSOL,
+          borrowedMint: mintSOL,
+          collateralPriceUpdate: solUsdPriceFeedAccountPubkey,
+          borrowedPriceUpdate: solUsdPriceFeedAccountPubkey,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          borrowedTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(positionAccounts(userInfo))
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Same bank liquidation rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("liquidate accepted one bank as both the collateral and the debt");
+    }
+  });
+
+  it("Test Liquidate Token-2022 collateral against an SPL Token debt", async () => {
+    // A USD pegged Token-2022 mint, its metadata pointer is an extension banks can list
+    const t22Mint = Keypair.generate();
+    await createToken2022Mint(
+      t22Mint,
+      ExtensionType.MetadataPointer,
+      createInitializeMetadataPointerInstruction(t22Mint.publicKey, signer.publicKey, t22Mint.publicKey, TOKEN_2022_PROGRAM_ID)
+    );
+    const [t22BankAccount] = PublicKey.findProgramAddressSync(
+      [t22Mint.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initBank(bankConfig(USDC_PRICE_FEED_ID))
+      .accounts({
+        authority: signer.publicKey,
+        mint: t22Mint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // A fresh user deposits 1,000 Token-2022 tokens and borrows $675 of SOL against them
+    const borrower = Keypair.generate();
+    context.setAccount(borrower.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    const borrowerTokenAccount = getAssociatedTokenAddressSync(
+      t22Mint.publicKey,
+      borrower.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          signer.publicKey,
+          borrowerTokenAccount,
+          borrower.publicKey,
+          t22Mint.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createMintToInstruction(t22Mint.publicKey, borrowerTokenAccount, signer.publicKey, 1_000 * 10 ** 6, [], TOKEN_2022_PROGRAM_ID)
+      )
+    );
+    const [borrowerAccount] = PublicKey.findProgramAddressSync(
+      [borrower.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initUser()
+      .accounts({ signer: borrower.publicKey })
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .deposit(new BN(1_000 * 10 ** 6))
+      .accounts({
+        signer: borrower.publicKey,
+        mint: t22Mint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+    const borrowerBefore = await program.account.user.fetch(borrowerAccount);
+    await program.methods
+      .borrow(new BN(4.5 * 10 ** 9))
+      .accounts({
+        signer: borrower.publicKey,
+        mint: mintSOL,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        priceUpdate: solUsdPriceFeedAccountPubkey,
+        isolatedBank: null,
+      })
+      .remainingAccounts(positionAccounts(borrowerBefore))
+      .signers([borrower])
+      .rpc({ commitment: "confirmed" });
+
+    // SOL rallies to $200. The liquidator repays 2 SOL through SPL Token and seizes collateral through Token-2022.
+    prices.sol = BigInt(200 * 10 ** 8);
+    await publishPrices();
+    const userBefore = await program.account.user.fetch(borrowerAccount);
+    const t22BankBefore = await program.account.bank.fetch(t22BankAccount);
+    // @ts-ignore
+    const liquidatorSolBefore = (await getAccount(banksClient, solTokenAccount)).amount;
+
+    const repayAmount = new BN(2 * 10 ** 9);
+    await program.methods
+      .liquidate(repayAmount)
+      .accounts({
+        liquidator: signer.publicKey,
+        owner: borrower.publicKey,
+        collateralMint: t22Mint.publicKey,
+        borrowedMint: mintSOL,
+        collateralPriceUpdate: usdcUsdPriceFeedAccountPubkey,
+        borrowedPriceUpdate: solUsdPriceFeedAccountPubkey,
+        collateralTokenProgram: TOKEN_2022_PROGRAM_ID,
+        borrowedTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(positionAccounts(userBefore))
+      .rpc({ commitment: "confirmed" });
+
+    prices.sol = BigInt(150 * 10 ** 8);
+    await publishPrices();
+
+    // $400 of repaid SOL plus the 5% bonus is worth 420 tokens, paid into the liquidator's Token-2022 account
+    const seized = new BN(420 * 10 ** 6);
+    const liquidatorTokenAccount = getAssociatedTokenAddressSync(
+      t22Mint.publicKey,
+      signer.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    // @ts-ignore
+    const liquidatorSeized = (await getAccount(banksClient, liquidatorTokenAccount, undefined, TOKEN_2022_PROGRAM_ID)).amount;
+    // @ts-ignore
+    const liquidatorSolAfter = (await getAccount(banksClient, solTokenAccount)).amount;
+    if (new BN(liquidatorSeized.toString()).cmp(seized) !== 0) {
+      throw new Error(`liquidator received ${liquidatorSeized.toString()} Token-2022 collateral, expected 420`);
+    }
+    if (new BN((liquidatorSolBefore - liquidatorSolAfter).toString()).cmp(repayAmount) !== 0) {
+      throw new Error("liquidator was not charged the repay amount in SOL");
+    }
+    const t22BankAfter = await program.account.bank.fetch(t22BankAccount);
+    if (!t22BankBefore.totalDeposits.sub(t22BankAfter.totalDeposits).eq(seized)) {
+      throw new Error("Token-2022 bank deposits did not fall by the seized collateral");
+    }
+  });
+
+  it("Test Init Bank rejects an interest-bearing mint", async () => {
+    // Its UI amount accrues away from the raw amounts the bank accounts in, so it cannot be priced
+    const interestMint = Keypair.generate();
+    await createToken2022Mint(
+      interestMint,
+      ExtensionType.InterestBearingConfig,
+      createInitializeInterestBearingMintInstruction(interestMint.publicKey, signer.publicKey, 500, TOKEN_2022_PROGRAM_ID)
+    );
+
+    let rejected = false;
+    try {
+      await program.methods
+        .initBank(bankConfig(USDC_PRICE_FEED_ID))
+        .accounts({
+          authority: signer.publicKey,
+          mint: interestMint.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          emodeCategory: null, // outside every e-mode category
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Interest-bearing mint rejected:", error.message);
+      if (!error.message.includes("UnsupportedMintExtension")) {
+        throw new Error(`initBank rejected an interest-bearing mint for the wrong reason: ${error.message}`);
+      }
+    }
+    if (!rejected) {
+      throw new Error("initBank listed an interest-bearing mint");
+    }
+  });
+
+  it("Test Deposit and Repay credit what a transfer fee mint delivers", async () => {
+    // Token-2022 mint withholding 1% of every transfer
+    const feeMint = Keypair.generate();
+    await createToken2022Mint(
+      feeMint,
+      ExtensionType.TransferFeeConfig,
+      createInitializeTransferFeeConfigInstruction(
+        feeMint.publicKey,
+        signer.publicKey,
+        signer.publicKey,
+        100,
+        BigInt(1_000_000 * 10 ** 6),
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    const [feeBankAccount] = PublicKey.findProgramAddressSync(
+      [feeMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const [feeTreasuryAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), feeMint.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initBank(bankConfig(USDC_PRICE_FEED_ID))
+      .accounts({
+        authority: signer.publicKey,
+        mint: feeMint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
+      })
+      .rpc({ commitment: "confirmed" });
+    const bankInfo = await program.account.bank.fetch(feeBankAccount);
+    if (!bankInfo.transferFee) {
+      throw new Error("initBank did not flag the transfer fee mint");
+    }
+
+    // A fresh user holding 10k tokens, minting charges no fee. The treasury gets liquidity to borrow from.
+    const depositor = Keypair.generate();
+    context.setAccount(depositor.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    const depositorTokenAccount = getAssociatedTokenAddressSync(
+      feeMint.publicKey,
+      depositor.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          signer.publicKey,
+          depositorTokenAccount,
+          depositor.publicKey,
+          feeMint.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createMintToInstruction(feeMint.publicKey, depositorTokenAccount, signer.publicKey, 10_000 * 10 ** 6, [], TOKEN_2022_PROGRAM_ID),
+        createMintToInstruction(feeMint.publicKey, feeTreasuryAccount, signer.publicKey, 100_000 * 10 ** 6, [], TOKEN_2022_PROGRAM_ID)
+      )
+    );
+    const [depositorAccount] = PublicKey.findProgramAddressSync(
+      [depositor.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initUser()
+      .accounts({ signer: depositor.publicKey })
+      .signers([depositor])
+      .rpc({ commitment: "confirmed" });
+
+    // 1,000 sent, 10 withheld by the mint, so 990 is credited and first-deposit shares match it 1:1
+    await program.methods
+      .deposit(new BN(1_000 * 10 ** 6))
+      .accounts({
+        signer: depositor.publicKey,
+        mint: feeMint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([depositor])
+      .rpc({ commitment: "confirmed" });
+
+    const credited = new BN(990 * 10 ** 6);
+    const afterDeposit = await program.account.bank.fetch(feeBankAccount);
+    const userAfterDeposit = await program.account.user.fetch(depositorAccount);
+    console.log("👤 User after fee mint deposit:", formatPositions(userAfterDeposit));
+    if (!afterDeposit.totalDeposits.eq(credited)) {
+      throw new Error(`deposit credited ${afterDeposit.totalDeposits.toString()}, expected what arrived after the fee`);
+    }
+    if (!userAfterDeposit.positions[0].depositShares.eq(credited)) {
+      throw new Error("deposit shares were minted for the amount sent rather than the amount received");
+    }
+
+    await program.methods
+      .borrow(new BN(100 * 10 ** 6))
+      .accounts({
+        signer: depositor.publicKey,
+        mint: feeMint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        priceUpdate: usdcUsdPriceFeedAccountPubkey,
+        isolatedBank: null,
+      })
+      .remainingAccounts(positionAccounts(userAfterDeposit))
+      .signers([depositor])
+      .rpc({ commitment: "confirmed" });
+    const beforeRepay = await program.account.bank.fetch(feeBankAccount);
+
+    // 50 sent, 0.5 withheld, so only 49.5 of the 100 borrowed is repaid
+    await program.methods
+      .repay(new BN(50 * 10 ** 6))
+      .accounts({
+        signer: depositor.publicKey,
+        mint: feeMint.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        isolatedBank: null,
+      })
+      .signers([depositor])
+      .rpc({ commitment: "confirmed" });
+
+    const repaid = new BN(49.5 * 10 ** 6);
+    const afterRepay = await program.account.bank.fetch(feeBankAccount);
+    const userAfterRepay = await program.account.user.fetch(depositorAccount);
+    console.log("👤 User after fee mint repay:", formatPositions(userAfterRepay));
+    if (!beforeRepay.totalBorrowed.sub(afterRepay.totalBorrowed).eq(repaid)) {
+      throw new Error("repay reduced debt by the amount sent rather than the amount received");
+    }
+    if (!userAfterRepay.positions[0].borrowShares.eq(new BN(100 * 10 ** 6).sub(repaid))) {
+      throw new Error("repay burned borrow shares for the amount sent rather than the amount received");
+    }
+  });
+
+  it("Test Borrow and Repay in the isolated bank itself", async () => {
+    // An isolated USD pegged bank with a $1,000 debt ceiling
+    const isolatedMint = await createMint(
+      // @ts-ignore
+      banksClient,
+      signer,
+      signer.publicKey,
+      null,
+      6
+    );
+    const [isolatedBankAccount] = PublicKey.findProgramAddressSync(
+      [isolatedMint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initBank({ ...bankConfig(USDC_PRICE_FEED_ID), isolated: true, isolatedDebtCeiling: new BN(1_000 * 10 ** 6) })
+      .accounts({
+        authority: signer.publicKey,
+        mint: isolatedMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        emodeCategory: null, // outside every e-mode category
+      })
+      .rpc({ commitment: "confirmed" });
+
+    // A fresh user enters isolation mode by depositing 1,000 tokens
+    const isolatedUser = Keypair.generate();
+    context.setAccount(isolatedUser.publicKey, {
+      lamports: 10 * 10 ** 9,
+      data: Buffer.alloc(0),
+      owner: SystemProgram.programId,
+      executable: false,
+    });
+    const isolatedTokenAccount = await createAccount(
+      // @ts-ignore
+      banksClient,
+      signer,
+      isolatedMint,
+      isolatedUser.publicKey
+    );
+    await mintTo(
+      // @ts-ignore
+      banksClient,
+      signer,
+      isolatedMint,
+      isolatedTokenAccount,
+      signer,
+      1_000 * 10 ** 6
+    );
+    const [isolatedUserAccount] = PublicKey.findProgramAddressSync(
+      [isolatedUser.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initUser()
+      .accounts({ signer: isolatedUser.publicKey })
+      .signers([isolatedUser])
+      .rpc({ commitment: "confirmed" });
+    await program.methods
+      .deposit(new BN(1_000 * 10 ** 6))
+      .accounts({
+        signer: isolatedUser.publicKey,
+        mint: isolatedMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([isolatedUser])
+      .rpc({ commitment: "confirmed" });
+
+    // Passing the borrowed bank again as the isolated bank would leave two copies of one account
+    const userInfo = await program.account.user.fetch(isolatedUserAccount);
+    const borrowFromIsolatedBank = (amount: BN, isolatedBank: PublicKey | null) =>
+      program.methods
+        .borrow(amount)
+        .accounts({
+          signer: isolatedUser.publicKey,
+          mint: isolatedMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          priceUpdate: usdcUsdPriceFeedAccountPubkey,
+          isolatedBank,
+        })
+        .remainingAccounts(positionAccounts(userInfo))
+        .signers([isolatedUser])
+        .rpc({ commitment: "confirmed" });
+    let rejected = false;
+    try {
+      await borrowFromIsolatedBank(new BN(100 * 10 ** 6), isolatedBankAccount);
+    } catch (error) {
+      rejected = true;
+      console.log("✅ Borrow passing the borrowed bank as its isolated bank rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("borrow accepted the borrowed bank as a separate isolated bank account");
+    }
+
+    // Borrowing 100 charges the ceiling through the borrowed bank and still books the debt
+    await borrowFromIsolatedBank(new BN(100 * 10 ** 6), null);
+    const afterBorrow = await program.account.bank.fetch(isolatedBankAccount);
+    if (!afterBorrow.totalBorrowed.eq(new BN(100 * 10 ** 6))) {
+      throw new Error(`isolated bank booked ${afterBorrow.totalBorrowed.toString()} borrowed, expected 100`);
+    }
+    if (!afterBorrow.isolatedDebt.eq(new BN(100 * 10 ** 6))) {
+      throw new Error(`isolated bank charged ${afterBorrow.isolatedDebt.toString()} against its ceiling, expected $100`);
+    }
+
+    // Repaying half frees half of the isolated debt and reduces the debt booked by the bank
+    await program.methods
+      .repay(new BN(50 * 10 ** 6))
+      .accounts({
+        signer: isolatedUser.publicKey,
+        mint: isolatedMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        isolatedBank: null,
+      })
+      .signers([isolatedUser])
+      .rpc({ commitment: "confirmed" });
+    const afterRepay = await program.account.bank.fetch(isolatedBankAccount);
+    if (!afterBorrow.totalBorrowed.sub(afterRepay.totalBorrowed).eq(new BN(50 * 10 ** 6))) {
+      throw new Error("repay in the isolated bank did not reduce its borrowed total");
+    }
+    if (!afterRepay.isolatedDebt.eq(new BN(50 * 10 ** 6))) {
+      throw new Error(`repay left ${afterRepay.isolatedDebt.toString()} isolated debt, expected $50`);
+    }
+  });
+
   it("Test Repay SOL", async () => {
     try {
       const repayAmount = new BN(1 * 10 ** 9); // 1 SOL
@@ -415,6 +1464,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
           signer: signer.publicKey,
           mint: mintSOL,
           tokenProgram: TOKEN_PROGRAM_ID,
+          isolatedBank: null,
         })
         .rpc({ commitment: "confirmed" });
 
@@ -423,10 +1473,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
       // Verify user account state
       const userInfo = await program.account.user.fetch(userAccount);
-      console.log("👤 User after SOL repay:", {
-        borrowedSol: userInfo.borrowedSol.toString(),
-        borrowedSolShares: userInfo.borrowedSolShares.toString()
-      });
+      console.log("👤 User after SOL repay:", formatPositions(userInfo));
 
       // Verify bank state
       const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -442,6 +1489,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Withdraw USDC", async () => {
     const withdrawAmount = new BN(1_000 * 10 ** 6); // 1k USDC
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawUSDC = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -449,6 +1497,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
         mint: mintUSDC,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw USDC:", withdrawUSDC);
@@ -456,10 +1505,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after USDC withdraw:", {
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedUsdcShares: userInfo.depositedUsdcShares.toString()
-    });
+    console.log("User after USDC withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(usdcBankAccount);
@@ -479,6 +1525,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
   it("Test Withdraw SOL", async () => {
     const withdrawAmount = new BN(1 * 10 ** 9); // 1 SOL
+    const userBefore = await program.account.user.fetch(userAccount);
     const withdrawSOL = await program.methods
       .withdraw(withdrawAmount)
       .accounts({
@@ -486,6 +1533,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
         mint: mintSOL,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .remainingAccounts(positionAccounts(userBefore))
       .rpc({ commitment: "confirmed" });
 
     console.log("✅ Withdraw SOL:", withdrawSOL);
@@ -493,10 +1541,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
 
     // Verify user account state
     const userInfo = await program.account.user.fetch(userAccount);
-    console.log("User after SOL withdraw:", {
-      depositedSol: userInfo.depositedSol.toString(),
-      depositedSolShares: userInfo.depositedSolShares.toString()
-    });
+    console.log("User after SOL withdraw:", formatPositions(userInfo));
 
     // Verify bank state
     const bankInfo = await program.account.bank.fetch(solBankAccount);
@@ -521,10 +1566,7 @@ describe("Lending Smart Contract Tests - Enhanced Version", async () => {
     const userInfo = await program.account.user.fetch(userAccount);
     console.log("Final User State:", {
       owner: userInfo.owner.toBase58(),
-      depositedUsdc: userInfo.depositedUsdc.toString(),
-      depositedSol: userInfo.depositedSol.toString(),
-      borrowedSol: userInfo.borrowedSol.toString(),
-      borrowedUsdc: userInfo.borrowedUsdc.toString(),
+      positions: formatPositions(userInfo),
       lastUpdated: userInfo.lastUpdated.toString()
     });
 
@@ -10,6 +10,7 @@ import {
 } from "@solana/spl-token";
 import { PublicKey, Keypair, Connection, SystemProgram } from "@solana/web3.js";
 import { assert } from "chai";
+import { programDataAddress } from "../bankrun-utils/upgradeableProgram";
 
 describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
   // Configure the client to use devnet
@@ -34,6 +35,28 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
   const SOL_USD_PRICE_FEED = new PublicKey(
     "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE"
   );
+  const SOL_USD_FEED_ID = "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+  const USDC_USD_FEED_ID = "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a";
+  const MAX_PRICE_AGE = new BN(100); // seconds
+  // 5% liquidation bonus, 50% close factor, 5% borrow rate at 80% utilization rising to 50% at full on top of the per-test threshold and LTV
+  const bankConfig = (liquidationThreshold: BN, maxLtv: BN, feedId: string) => ({
+    liquidationThreshold,
+    maxLtv,
+    liquidationBonus: new BN(5),
+    liquidationCloseFactor: new BN(50),
+    interestRate: new BN(500),
+    maxInterestRate: new BN(5000),
+    optimalUtilization: new BN(80),
+    isolated: false,
+    isolatedDebtCeiling: new BN(0),
+    emodeCategory: 0,
+    oracleFeedId: Array.from(Buffer.from(feedId, "hex")),
+    maxPriceAge: MAX_PRICE_AGE,
+  });
+
+  // Deposit shares the user holds in a bank, zero when there is no position
+  const depositShares = (userData: any, bank: PublicKey) =>
+    (userData.positions.find((position: any) => position.bank.equals(bank))?.depositShares ?? new BN(0)).toString();
 
   console.log("\n🚀 Starting On-Chain Tests on Devnet");
   console.log("📍 Program ID:", program.programId.toBase58());
@@ -44,7 +67,7 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
   it("Initialize User Account", async () => {
     console.log("\n=== Test 1: Initialize User Account ===");
 
-    // Create USDC mint (we'll use this as the usdc_address parameter)
+    // Create USDC mint, banks are created for it in the next test
     console.log("📦 Creating USDC mint...");
     usdcMint = await createMint(
       connection,
@@ -71,15 +94,13 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
         const userAccountData = await program.account.user.fetch(userAccount);
         console.log("📊 Existing User Account Data:", {
           owner: userAccountData.owner.toBase58(),
-          usdcAddress: userAccountData.usdcAddress.toBase58(),
-          depositedUsdc: userAccountData.depositedUsdc.toString(),
-          depositedSol: userAccountData.depositedSol.toString(),
+          positions: userAccountData.positions.length,
         });
         return;
       }
 
       const tx = await program.methods
-        .initUser(usdcMint)
+        .initUser()
         .accounts({
           signer: payer.publicKey,
           userAccount: userAccount,
@@ -101,9 +122,7 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       const userAccountData = await program.account.user.fetch(userAccount);
       console.log("📊 User Account Data:", {
         owner: userAccountData.owner.toBase58(),
-        usdcAddress: userAccountData.usdcAddress.toBase58(),
-        depositedUsdc: userAccountData.depositedUsdc.toString(),
-        depositedSol: userAccountData.depositedSol.toString(),
+        positions: userAccountData.positions.length,
       });
     } catch (error) {
       console.error("❌ Error:", error);
@@ -111,6 +130,29 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
     }
   });
 
+  it("Initialize Protocol", async () => {
+    const [protocol] = PublicKey.findProgramAddressSync(
+      [Buffer.from("protocol")],
+      program.programId
+    );
+    if (await connection.getAccountInfo(protocol)) {
+      console.log("ℹ️  Protocol already initialized, skipping initialization");
+      return;
+    }
+
+    // Only the program's upgrade authority, the deploying wallet, can take the protocol
+    const tx = await program.methods
+      .initProtocol()
+      .accounts({
+        signer: payer.publicKey,
+        programData: programDataAddress(program.programId),
+      })
+      .rpc();
+
+    console.log("✅ Protocol initialized!");
+    console.log("🔗 Transaction:", tx);
+  });
+
   it("Initialize USDC Bank", async () => {
     console.log("\n=== Test 2: Initialize USDC Bank ===");
 
@@ -133,13 +175,14 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       const maxLtv = new BN(75); // 75%
 
       const tx = await program.methods
-        .initBank(liquidationThreshold, maxLtv)
+        .initBank(bankConfig(liquidationThreshold, maxLtv, USDC_USD_FEED_ID))
         .accounts({
-          signer: payer.publicKey,
+          authority: payer.publicKey,
           mint: usdcMint,
           bank: usdcBankAccount,
           bankTokenAccount: usdcTreasuryAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
+          emodeCategory: null, // outside every e-mode category
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -198,13 +241,14 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       const maxLtv = new BN(75);
 
       const tx = await program.methods
-        .initBank(liquidationThreshold, maxLtv)
+        .initBank(bankConfig(liquidationThreshold, maxLtv, SOL_USD_FEED_ID))
         .accounts({
-          signer: payer.publicKey,
+          authority: payer.publicKey,
           mint: solMint,
           bank: solBankAccount,
           bankTokenAccount: solTreasuryAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
+          emodeCategory: null, // outside every e-mode category
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -278,7 +322,7 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       await connection.confirmTransaction(tx, "confirmed");
 
       const userData = await program.account.user.fetch(userAccount);
-      console.log("📊 User Deposited USDC:", userData.depositedUsdc.toString());
+      console.log("📊 User USDC Deposit Shares:", depositShares(userData, usdcBankAccount));
     } catch (error) {
       console.error("❌ Error:", error);
       throw error;
@@ -340,7 +384,7 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       await connection.confirmTransaction(tx, "confirmed");
 
       const userData = await program.account.user.fetch(userAccount);
-      console.log("📊 User Deposited SOL:", userData.depositedSol.toString());
+      console.log("📊 User SOL Deposit Shares:", depositShares(userData, solBankAccount));
     } catch (error) {
       console.error("❌ Error:", error);
       throw error;
@@ -378,7 +422,7 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
       await connection.confirmTransaction(tx, "confirmed");
 
       const userData = await program.account.user.fetch(userAccount);
-      console.log("📊 User Deposited USDC after withdrawal:", userData.depositedUsdc.toString());
+      console.log("📊 User USDC Deposit Shares after withdrawal:", depositShares(userData, usdcBankAccount));
     } catch (error) {
       console.error("❌ Error:", error);
       throw error;
@@ -394,10 +438,11 @@ describe("Lending Protocol - On-Chain Tests (Devnet)", () => {
 
     console.log("\n👤 User Account:");
     console.log("  - Address:", userAccount.toBase58());
-    console.log("  - Deposited USDC:", userData.depositedUsdc.toString());
-    console.log("  - Deposited SOL:", userData.depositedSol.toString());
-    console.log("  - Borrowed USDC:", userData.borrowedUsdc.toString());
-    console.log("  - Borrowed SOL:", userData.borrowedSol.toString());
+    for (const position of userData.positions) {
+      console.log("  - Bank:", position.bank.toBase58());
+      console.log("    Deposit Shares:", position.depositShares.toString());
+      console.log("    Borrow Shares:", position.borrowShares.toString());
+    }
 
     console.log("\n🏦 USDC Bank:");
     console.log("  - Address:", usdcBankAccount.toBase58());
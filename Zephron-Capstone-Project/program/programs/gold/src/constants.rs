@@ -7,6 +7,16 @@ pub const SEED_MINT_ACCOUNT: &[u8] = b"mint";
 pub const SEED_LENDING_POOL: &[u8] = b"lending_pool";
 pub const SEED_LENDER_POSITION: &[u8] = b"lender_position";
 pub const SEED_BORROWER_POSITION: &[u8] = b"borrower_position";
+pub const SEED_RECEIPT_MINT: &[u8] = b"receipt_mint";
+pub const SEED_OBLIGATION: &[u8] = b"obligation";
+pub const SEED_WITHDRAWAL_QUEUE: &[u8] = b"withdrawal_queue";
+pub const SEED_WITHDRAWAL_REQUEST: &[u8] = b"withdrawal_request";
+pub const SEED_TERM_LOAN: &[u8] = b"term_loan";
+pub const SEED_TERM_LOAN_ESCROW: &[u8] = b"term_loan_escrow";
+pub const SEED_REWARD_VAULT: &[u8] = b"reward_vault";
+pub const SEED_RATE_HISTORY: &[u8] = b"rate_history";
+pub const SEED_INSURANCE_FUND: &[u8] = b"insurance_fund";
+pub const SEED_INSURANCE_VAULT: &[u8] = b"insurance_vault";
 
 #[constant]
 pub const GOLD_FEED_ID: &str = "0x765d2ba906dbc32ca17cc11f5310a89e9ee1f6420508c63861f2f8ba4ee34bb2";
@@ -25,6 +35,16 @@ pub const MINT_DECIMALS: u8 = 9;
 
 // Lending protocol constants
 pub const SLOTS_PER_YEAR: u64 = 630_720_000; // Approximate slots per year (assuming ~400ms per slot)
+pub const SECONDS_PER_YEAR: u64 = 31_536_000; // 365 days, used when the pool accrues on unix_timestamp
 pub const BASIS_POINTS: u64 = 10_000; // 100% in basis points
 pub const MAX_UTILIZATION_RATE: u64 = 9_000; // 90% max utilization
-pub const COLLATERAL_FACTOR: u64 = 7_500; // 75% collateral factor (can borrow up to 75% of supplied)
\ No newline at end of file
+pub const FLASH_LOAN_FEE: u64 = 9; // 0.09% flash loan fee (basis points), paid to lenders
+pub const INDEX_PRECISION: u128 = 1_000_000_000_000; // supply index scale, 1e12 = exchange rate of 1 zGOLD : 1 GOLD
+pub const TERM_RATE_PREMIUM: u64 = 200; // 2% (basis points) added to the floating borrow rate when a term loan is fixed
+pub const MAX_TERM_DURATION: i64 = 31_536_000; // 365 days, longest term loan maturity (seconds)
+pub const TERM_GRACE_PERIOD: i64 = 259_200; // 3 days after maturity before a term loan can be liquidated (seconds)
+pub const EARLY_REPAYMENT_FEE: u64 = 50; // 0.5% of principal (basis points) charged when repaying before maturity
+pub const MAX_REWARD_MINTS: usize = 4; // concurrent liquidity mining reward mints per lending pool
+pub const RATE_HISTORY_LENGTH: usize = 128; // pool snapshots kept in the rate history ring buffer
+pub const CROSS_MARGIN_CLOSE_FACTOR: u64 = 50; // a single cross margin liquidation repays at most 50% of the lending debt
+pub const INSURANCE_LIQUIDATION_FEE: u64 = 100; // 1% of debt repaid in a liquidation (basis points), paid by the liquidator into the insurance fund
@@ -12,4 +12,50 @@ pub enum CustomError {
     InsufficientFunds,
     #[msg("Insufficient Borrow Capacity")]
     InsufficientBorrowCapacity,
+    #[msg("Invalid Lending Pool Parameters")]
+    InvalidLendingPoolParameters,
+    #[msg("Amount Too Small")]
+    AmountTooSmall,
+    #[msg("Lending Pool Supply Cap Exceeded")]
+    SupplyCapExceeded,
+    #[msg("Lending Pool Borrow Cap Exceeded")]
+    BorrowCapExceeded,
+    #[msg("Lending Pool Maximum Utilization Exceeded")]
+    MaxUtilizationExceeded,
+    #[msg("Insufficient Protocol Reserves")]
+    InsufficientReserves,
+    #[msg("Flash Loan Cannot Be Invoked Through CPI")]
+    FlashLoanCpiNotAllowed,
+    #[msg("Flash Loan Missing Matching Repay Instruction")]
+    FlashLoanRepayMissing,
+    #[msg("Flash Loan Repay Does Not Match Borrow Instruction")]
+    FlashLoanInvalidRepay,
+    #[msg("Lending Pool Must Be Refreshed In The Current Slot")]
+    LendingPoolStale,
+    #[msg("Obligation Must Be Refreshed In The Current Slot")]
+    ObligationStale,
+    #[msg("Insufficient Lending Pool Liquidity")]
+    InsufficientLiquidity,
+    #[msg("Invalid Term Loan Duration")]
+    InvalidTermLoanDuration,
+    #[msg("Term Loan Is Not Past Its Grace Period")]
+    TermLoanNotLiquidatable,
+    #[msg("Invalid Reward Slot")]
+    InvalidRewardSlot,
+    #[msg("Reward Slot Already In Use")]
+    RewardSlotInUse,
+    #[msg("Cross Margin Is Not Enabled")]
+    CrossMarginNotEnabled,
+    #[msg("Cross Margin Obligation And Lending Pool Accounts Required")]
+    CrossMarginAccountsMissing,
+    #[msg("zGOLD Account Must Delegate To The Lending Pool For Cross Margin")]
+    CrossMarginDelegateMissing,
+    #[msg("Position Has No Bad Debt")]
+    NoBadDebt,
+    #[msg("Withdrawal Request Owner, Token Account And Queue Accounts Required")]
+    WithdrawalRequestAccountsMissing,
+    #[msg("Collateral Must Be Liquidated Before Bad Debt Is Covered")]
+    CollateralNotLiquidated,
+    #[msg("Lending Pool Is Not In The Legacy Layout")]
+    NotLegacyLendingPool,
 }
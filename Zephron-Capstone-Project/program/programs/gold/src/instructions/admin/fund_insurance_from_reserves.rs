@@ -0,0 +1,103 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        record_rate_history, update_lending_pool, withdrawable_reserves,
+    },
+    Config, InsuranceFund, LendingPool, RateHistory, SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND,
+    SEED_LENDING_POOL, SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked};
+
+#[derive(Accounts)]
+pub struct FundInsuranceFromReserves<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Move accrued protocol reserves from the pool vault into the insurance fund
+pub fn process_fund_insurance_from_reserves(
+    ctx: Context<FundInsuranceFromReserves>,
+    amount: u64,
+) -> Result<()> {
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+
+    // Same bound as withdraw_reserves, GOLD supplied by lenders never moves into the insurance fund
+    let withdrawable = withdrawable_reserves(lending_pool, ctx.accounts.lending_pool_vault.amount);
+    msg!("Protocol Reserves: {:.9}, Withdrawable: {:.9}, Requested: {:.9}",
+         lending_pool.protocol_reserves as f64 / 1e9,
+         withdrawable as f64 / 1e9,
+         amount as f64 / 1e9);
+    require!(amount <= withdrawable, CustomError::InsufficientReserves);
+    lending_pool.protocol_reserves -= amount;
+    ctx.accounts.insurance_fund.total_deposited += amount;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.lending_pool_vault.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.insurance_vault.to_account_info(),
+                authority: ctx.accounts.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint_account.decimals,
+    )?;
+
+    msg!("Insurance Fund Deposited: {:.9}, Total: {:.9}",
+         amount as f64 / 1e9,
+         ctx.accounts.insurance_fund.total_deposited as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
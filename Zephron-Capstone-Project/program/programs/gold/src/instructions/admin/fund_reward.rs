@@ -0,0 +1,97 @@
+use crate::{
+    error::CustomError, instructions::lending::utils::accrue_pool_rewards, Config, LendingPool,
+    MAX_REWARD_MINTS, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_REWARD_VAULT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+#[derive(Accounts)]
+pub struct FundReward<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_REWARD_VAULT, lending_pool.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = lending_pool,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = authority,
+        token::token_program = reward_token_program
+    )]
+    pub authority_reward_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+// Top up a reward vault and set the per second emission rates until end_timestamp
+pub fn process_fund_reward(
+    ctx: Context<FundReward>,
+    slot: u8,
+    amount: u64,
+    supply_emission_rate: u64,
+    borrow_emission_rate: u64,
+    end_timestamp: i64,
+) -> Result<()> {
+    let slot = slot as usize;
+    require!(slot < MAX_REWARD_MINTS, CustomError::InvalidRewardSlot);
+    require!(
+        ctx.accounts.lending_pool.rewards[slot].reward_mint == ctx.accounts.reward_mint.key(),
+        CustomError::InvalidRewardSlot
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(end_timestamp >= now, CustomError::InvalidLendingPoolParameters);
+
+    // Emissions up to now are settled at the old rates before the new rates apply
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    accrue_pool_rewards(lending_pool)?;
+
+    let reward = &mut lending_pool.rewards[slot];
+    reward.supply_emission_rate = supply_emission_rate;
+    reward.borrow_emission_rate = borrow_emission_rate;
+    reward.end_timestamp = end_timestamp;
+    reward.last_update_timestamp = now;
+
+    if amount > 0 {
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.reward_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.authority_reward_account.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+    }
+
+    msg!("Fund Reward Slot {} - Amount: {}:{:#?}", slot, amount, ctx.accounts.lending_pool.rewards[slot]);
+    Ok(())
+}
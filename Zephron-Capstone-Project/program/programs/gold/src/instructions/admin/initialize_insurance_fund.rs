@@ -0,0 +1,59 @@
+use crate::{
+    Config, InsuranceFund, SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND, SEED_INSURANCE_VAULT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceFund::INIT_SPACE,
+        seeds = [SEED_INSURANCE_FUND],
+        bump,
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SEED_INSURANCE_VAULT, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = insurance_fund,
+        token::token_program = token_program
+    )]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Insurance fund backstops bad debt in both the CDP vaults and the lending pool
+pub fn process_initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+    *ctx.accounts.insurance_fund = InsuranceFund {
+        insurance_vault: ctx.accounts.insurance_vault.key(),
+        total_deposited: 0,
+        total_covered: 0,
+        total_socialized: 0,
+        bump: ctx.bumps.insurance_fund,
+        bump_insurance_vault: ctx.bumps.insurance_vault,
+    };
+
+    msg!("Initialize Insurance Fund:{:#?}", ctx.accounts.insurance_fund);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::{
+    error::CustomError, ClockSource, Config, LendingPool, PoolReward, BASIS_POINTS,
+    EARLY_REPAYMENT_FEE, FLASH_LOAN_FEE, INDEX_PRECISION, MAX_REWARD_MINTS, MAX_TERM_DURATION,
+    MINT_DECIMALS, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_RECEIPT_MINT, TERM_GRACE_PERIOD,
+    TERM_RATE_PREMIUM,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct InitializeLendingPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingPool::INIT_SPACE,
+        seeds = [SEED_LENDING_POOL],
+        bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+        token::token_program = token_program
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SEED_RECEIPT_MINT],
+        bump,
+        mint::decimals = MINT_DECIMALS,
+        mint::authority = lending_pool,
+        mint::freeze_authority = lending_pool,
+        mint::token_program = token_program
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Lending pool is created once by the config authority, user instructions require it to exist
+pub fn process_initialize_lending_pool(
+    ctx: Context<InitializeLendingPool>,
+    collateral_factor: u64,
+    reserve_factor: u64,
+) -> Result<()> {
+    require!(
+        collateral_factor > 0 && collateral_factor < BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+    require!(
+        reserve_factor < BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+
+    let clock = Clock::get()?;
+    *ctx.accounts.lending_pool = LendingPool {
+        total_supplied: 0,
+        total_borrowed: 0,
+        last_update_slot: clock.slot,
+        last_update_timestamp: clock.unix_timestamp,
+        clock_source: ClockSource::UnixTimestamp,
+        last_refresh_slot: clock.slot,
+        require_refresh: false,
+        supply_rate: 0,
+        borrow_rate: 0,
+        utilization_rate: 0,
+        collateral_factor,
+        supply_cap: u64::MAX,
+        borrow_cap: u64::MAX,
+        max_utilization: BASIS_POINTS,
+        reserve_factor,
+        protocol_reserves: 0,
+        flash_loan_fee: FLASH_LOAN_FEE,
+        supply_index: INDEX_PRECISION,
+        receipt_mint: ctx.accounts.receipt_mint.key(),
+        queued_receipt: 0,
+        withdrawal_queue_head: 0,
+        withdrawal_queue_tail: 0,
+        term_rate_premium: TERM_RATE_PREMIUM,
+        max_term_duration: MAX_TERM_DURATION,
+        term_grace_period: TERM_GRACE_PERIOD,
+        early_repayment_fee: EARLY_REPAYMENT_FEE,
+        term_borrowed: 0,
+        reward_supply_basis: 0,
+        reward_borrow_basis: 0,
+        rewards: [PoolReward::default(); MAX_REWARD_MINTS],
+        bump: ctx.bumps.lending_pool,
+        bump_receipt_mint: ctx.bumps.receipt_mint,
+    };
+    msg!("Initialized Lending Pool:{:#?}", ctx.accounts.lending_pool);
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::{
+    Config, LendingPool, RateHistory, RATE_HISTORY_LENGTH, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL,
+    SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitializeRateHistory<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RateHistory>(),
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Rate history is created once per pool, every instruction that accrues the pool then records to it
+pub fn process_initialize_rate_history(ctx: Context<InitializeRateHistory>) -> Result<()> {
+    let mut rate_history = ctx.accounts.rate_history.load_init()?;
+    rate_history.lending_pool = ctx.accounts.lending_pool.key();
+    rate_history.head = 0;
+    rate_history.count = 0;
+    rate_history.bump = ctx.bumps.rate_history;
+
+    msg!("Initialize Rate History - Lending Pool: {}, Capacity: {}",
+         ctx.accounts.lending_pool.key(),
+         RATE_HISTORY_LENGTH);
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::{
+    error::CustomError, Config, LendingPool, MAX_REWARD_MINTS, SEED_CONFIG_ACCOUNT,
+    SEED_LENDING_POOL, SEED_REWARD_VAULT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct InitializeReward<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SEED_REWARD_VAULT, lending_pool.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = lending_pool,
+        token::token_program = reward_token_program
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+// Register a liquidity mining reward mint in an empty slot, emissions start once it is funded
+pub fn process_initialize_reward(ctx: Context<InitializeReward>, slot: u8) -> Result<()> {
+    let slot = slot as usize;
+    require!(slot < MAX_REWARD_MINTS, CustomError::InvalidRewardSlot);
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    require!(
+        lending_pool.rewards[slot].reward_mint == Pubkey::default(),
+        CustomError::RewardSlotInUse
+    );
+
+    let reward = &mut lending_pool.rewards[slot];
+    reward.reward_mint = ctx.accounts.reward_mint.key();
+    reward.supply_emission_rate = 0;
+    reward.borrow_emission_rate = 0;
+    reward.supply_index = 0;
+    reward.borrow_index = 0;
+    reward.end_timestamp = 0;
+    reward.last_update_timestamp = Clock::get()?.unix_timestamp;
+
+    msg!("Initialize Reward Slot {}:{:#?}", slot, lending_pool.rewards[slot]);
+    Ok(())
+}
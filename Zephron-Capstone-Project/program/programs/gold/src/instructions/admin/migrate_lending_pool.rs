@@ -0,0 +1,149 @@
+use crate::{
+    error::CustomError, ClockSource, Config, LegacyLendingPool, LendingPool, PoolReward,
+    BASIS_POINTS, EARLY_REPAYMENT_FEE, FLASH_LOAN_FEE, INDEX_PRECISION, MAX_REWARD_MINTS,
+    MAX_TERM_DURATION, MINT_DECIMALS, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_RECEIPT_MINT,
+    TERM_GRACE_PERIOD, TERM_RATE_PREMIUM,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct MigrateLendingPool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    /// CHECK: legacy layout pool, its owner, discriminator and size are checked before it is rewritten
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump,
+    )]
+    pub lending_pool: UncheckedAccount<'info>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    // Legacy deposits created the vault on first use, it is created here if the pool never had one
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+        token::token_program = token_program
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SEED_RECEIPT_MINT],
+        bump,
+        mint::decimals = MINT_DECIMALS,
+        mint::authority = lending_pool,
+        mint::freeze_authority = lending_pool,
+        mint::token_program = token_program
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Rewrite a pool created by the legacy deposit path in the current layout. Its totals, rates and
+// accrual slot carry over, so migrate_positions can then fold legacy positions into obligations.
+pub fn process_migrate_lending_pool(
+    ctx: Context<MigrateLendingPool>,
+    collateral_factor: u64,
+    reserve_factor: u64,
+) -> Result<()> {
+    require!(
+        collateral_factor > 0 && collateral_factor < BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+    require!(
+        reserve_factor < BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+
+    let pool_info = ctx.accounts.lending_pool.to_account_info();
+    let legacy = {
+        let data = pool_info.try_borrow_data()?;
+        require!(
+            pool_info.owner == &crate::ID
+                && data.len() == 8 + LegacyLendingPool::INIT_SPACE
+                && data[..8] == *LendingPool::DISCRIMINATOR,
+            CustomError::NotLegacyLendingPool
+        );
+        LegacyLendingPool::deserialize(&mut &data[8..])?
+    };
+    msg!("Legacy Lending Pool:{:#?}", legacy);
+
+    // Grow the account to the current layout, the authority pays the extra rent
+    let space = 8 + LendingPool::INIT_SPACE;
+    let rent = Rent::get()?.minimum_balance(space).saturating_sub(pool_info.lamports());
+    if rent > 0 {
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: pool_info.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    pool_info.realloc(space, true)?;
+
+    // Legacy interest accrued on slots, the pool keeps the slot clock until the authority switches it
+    let clock = Clock::get()?;
+    let lending_pool = LendingPool {
+        total_supplied: legacy.total_supplied,
+        total_borrowed: legacy.total_borrowed,
+        last_update_slot: legacy.last_update_slot,
+        last_update_timestamp: clock.unix_timestamp,
+        clock_source: ClockSource::Slot,
+        last_refresh_slot: clock.slot,
+        require_refresh: false,
+        supply_rate: legacy.supply_rate,
+        borrow_rate: legacy.borrow_rate,
+        utilization_rate: legacy.utilization_rate,
+        collateral_factor,
+        supply_cap: u64::MAX,
+        borrow_cap: u64::MAX,
+        max_utilization: BASIS_POINTS,
+        reserve_factor,
+        protocol_reserves: 0,
+        flash_loan_fee: FLASH_LOAN_FEE,
+        supply_index: INDEX_PRECISION,
+        receipt_mint: ctx.accounts.receipt_mint.key(),
+        queued_receipt: 0,
+        withdrawal_queue_head: 0,
+        withdrawal_queue_tail: 0,
+        term_rate_premium: TERM_RATE_PREMIUM,
+        max_term_duration: MAX_TERM_DURATION,
+        term_grace_period: TERM_GRACE_PERIOD,
+        early_repayment_fee: EARLY_REPAYMENT_FEE,
+        term_borrowed: 0,
+        reward_supply_basis: 0,
+        reward_borrow_basis: 0,
+        rewards: [PoolReward::default(); MAX_REWARD_MINTS],
+        bump: ctx.bumps.lending_pool,
+        bump_receipt_mint: ctx.bumps.receipt_mint,
+    };
+    lending_pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Migrated Lending Pool:{:#?}", lending_pool);
+    Ok(())
+}
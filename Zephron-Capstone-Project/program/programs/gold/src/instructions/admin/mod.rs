@@ -2,3 +2,27 @@ pub use initialize_config::*;
 pub mod update_config;
 pub use update_config::*;
 pub mod initialize_config;
+pub use initialize_lending_pool::*;
+pub mod initialize_lending_pool;
+pub use migrate_lending_pool::*;
+pub mod migrate_lending_pool;
+pub use update_lending_pool_caps::*;
+pub mod update_lending_pool_caps;
+pub use withdraw_reserves::*;
+pub mod withdraw_reserves;
+pub use update_lending_pool_clock_source::*;
+pub mod update_lending_pool_clock_source;
+pub use update_lending_pool_require_refresh::*;
+pub mod update_lending_pool_require_refresh;
+pub use update_term_loan_params::*;
+pub mod update_term_loan_params;
+pub use initialize_reward::*;
+pub mod initialize_reward;
+pub use fund_reward::*;
+pub mod fund_reward;
+pub use initialize_rate_history::*;
+pub mod initialize_rate_history;
+pub use initialize_insurance_fund::*;
+pub mod initialize_insurance_fund;
+pub use fund_insurance_from_reserves::*;
+pub mod fund_insurance_from_reserves;
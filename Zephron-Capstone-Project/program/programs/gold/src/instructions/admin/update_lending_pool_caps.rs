@@ -0,0 +1,42 @@
+use crate::{error::CustomError, Config, LendingPool, BASIS_POINTS, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateLendingPoolCaps<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+}
+
+// Caps limit pool risk while a market is young, u64::MAX caps and 100% utilization disable them
+pub fn process_update_lending_pool_caps(
+    ctx: Context<UpdateLendingPoolCaps>,
+    supply_cap: u64,
+    borrow_cap: u64,
+    max_utilization: u64,
+) -> Result<()> {
+    require!(
+        max_utilization > 0 && max_utilization <= BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    lending_pool.supply_cap = supply_cap;
+    lending_pool.borrow_cap = borrow_cap;
+    lending_pool.max_utilization = max_utilization;
+
+    msg!("Update Lending Pool Caps:{:#?}", ctx.accounts.lending_pool);
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::{
+    instructions::lending::utils::{record_rate_history, update_lending_pool},
+    ClockSource, Config, LendingPool, RateHistory, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL,
+    SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateLendingPoolClockSource<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+}
+
+// Interest up to now is accrued on the old clock before switching, so no period is counted twice
+pub fn process_update_lending_pool_clock_source(
+    ctx: Context<UpdateLendingPoolClockSource>,
+    clock_source: ClockSource,
+) -> Result<()> {
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+
+    let clock = Clock::get()?;
+    lending_pool.clock_source = clock_source;
+    lending_pool.last_update_slot = clock.slot;
+    lending_pool.last_update_timestamp = clock.unix_timestamp;
+
+    msg!("Update Lending Pool Clock Source:{:#?}", ctx.accounts.lending_pool);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
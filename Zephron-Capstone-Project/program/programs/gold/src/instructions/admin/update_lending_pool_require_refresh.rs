@@ -0,0 +1,33 @@
+use crate::{Config, LendingPool, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateLendingPoolRequireRefresh<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+}
+
+// When enabled, borrow and withdraw must follow refresh_pool / refresh_position in the same slot
+pub fn process_update_lending_pool_require_refresh(
+    ctx: Context<UpdateLendingPoolRequireRefresh>,
+    require_refresh: bool,
+) -> Result<()> {
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    lending_pool.require_refresh = require_refresh;
+
+    msg!("Update Lending Pool Require Refresh:{:#?}", ctx.accounts.lending_pool);
+    Ok(())
+}
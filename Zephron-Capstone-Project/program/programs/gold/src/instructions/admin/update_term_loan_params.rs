@@ -0,0 +1,48 @@
+use crate::{error::CustomError, Config, LendingPool, BASIS_POINTS, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateTermLoanParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority
+    )]
+    pub config_account: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+}
+
+// Only affects new term loans, rate and maturity of existing loans are locked at origination
+pub fn process_update_term_loan_params(
+    ctx: Context<UpdateTermLoanParams>,
+    term_rate_premium: u64,
+    max_term_duration: i64,
+    term_grace_period: i64,
+    early_repayment_fee: u64,
+) -> Result<()> {
+    require!(
+        term_rate_premium <= BASIS_POINTS && early_repayment_fee <= BASIS_POINTS,
+        CustomError::InvalidLendingPoolParameters
+    );
+    require!(
+        max_term_duration > 0 && term_grace_period >= 0,
+        CustomError::InvalidLendingPoolParameters
+    );
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    lending_pool.term_rate_premium = term_rate_premium;
+    lending_pool.max_term_duration = max_term_duration;
+    lending_pool.term_grace_period = term_grace_period;
+    lending_pool.early_repayment_fee = early_repayment_fee;
+
+    msg!("Update Term Loan Params:{:#?}", ctx.accounts.lending_pool);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        record_rate_history, update_lending_pool, withdrawable_reserves,
+    },
+    Config, LendingPool, RateHistory, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked};
+
+#[derive(Accounts)]
+pub struct WithdrawReserves<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = authority,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::token_program = token_program
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Send accrued protocol reserves from the pool vault to a treasury token account
+pub fn process_withdraw_reserves(ctx: Context<WithdrawReserves>, amount: u64) -> Result<()> {
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+
+    // Reserves are booked as interest accrues, so only the part actually sitting in the vault
+    // beyond everything supplied by lenders can be withdrawn
+    let withdrawable = withdrawable_reserves(lending_pool, ctx.accounts.lending_pool_vault.amount);
+    msg!("Protocol Reserves: {:.9}, Withdrawable: {:.9}, Requested: {:.9}",
+         lending_pool.protocol_reserves as f64 / 1e9,
+         withdrawable as f64 / 1e9,
+         amount as f64 / 1e9);
+    require!(amount <= withdrawable, CustomError::InsufficientReserves);
+    lending_pool.protocol_reserves -= amount;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.lending_pool_vault.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.treasury_token_account.to_account_info(),
+                authority: ctx.accounts.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint_account.decimals,
+    )?;
+
+    msg!("Withdrew Reserves: {:.9} to {}", amount as f64 / 1e9, ctx.accounts.treasury_token_account.key());
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::{
-    check_health_factor, deposit_sol_internal, mint_tokens_internal, Collateral, Config,
+    deposit_collateral_and_mint_internal, Collateral, Config, LendingPool, Obligation,
     SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_SOL_ACCOUNT,
 };
 use anchor_lang::prelude::*;
@@ -46,6 +46,9 @@ pub struct DepositCollateralAndMintTokens<'info> {
         associated_token::token_program = token_program
     )]
     pub token_account: InterfaceAccount<'info, TokenAccount>,
+    // Required when the collateral account is cross margined with a lending obligation
+    pub lending_pool: Option<Box<Account<'info, LendingPool>>>,
+    pub obligation: Option<Box<Account<'info, Obligation>>>,
     pub token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -57,39 +60,21 @@ pub fn process_deposit_collateral_and_mint_tokens(
     amount_collateral: u64,
     amount_to_mint: u64,
 ) -> Result<()> {
-    let collateral_account = &mut ctx.accounts.collateral_account;
-    collateral_account.lamport_balance = ctx.accounts.sol_account.lamports() + amount_collateral;
-    collateral_account.amount_minted += amount_to_mint;
-
-    if !collateral_account.is_initialized {
-        collateral_account.is_initialized = true;
-        collateral_account.depositor = ctx.accounts.depositor.key();
-        collateral_account.sol_account = ctx.accounts.sol_account.key();
-        collateral_account.token_account = ctx.accounts.token_account.key();
-        collateral_account.bump = ctx.bumps.collateral_account;
-        collateral_account.bump_sol_account = ctx.bumps.sol_account;
-    }
-
-    check_health_factor(
-        &ctx.accounts.collateral_account,
-        &ctx.accounts.config_account,
-        &ctx.accounts.gold_price_update,
-        &ctx.accounts.sol_price_update,
-    )?;
-
-    deposit_sol_internal(
+    deposit_collateral_and_mint_internal(
         &ctx.accounts.depositor,
+        &ctx.accounts.config_account,
+        &mut ctx.accounts.collateral_account,
         &ctx.accounts.sol_account,
-        &ctx.accounts.system_program,
-        amount_collateral,
-    )?;
-
-    mint_tokens_internal(
         &ctx.accounts.mint_account,
         &ctx.accounts.token_account,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.accounts.lending_pool.as_deref(),
+        ctx.accounts.obligation.as_deref(),
         &ctx.accounts.token_program,
-        ctx.accounts.config_account.bump_mint_account,
+        &ctx.accounts.system_program,
+        (ctx.bumps.collateral_account, ctx.bumps.sol_account),
+        amount_collateral,
         amount_to_mint,
-    )?;
-    Ok(())
+    )
 }
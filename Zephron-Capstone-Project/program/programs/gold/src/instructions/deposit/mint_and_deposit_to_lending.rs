@@ -0,0 +1,139 @@
+use crate::{
+    deposit_collateral_and_mint_internal, Collateral, Config, LendingPool, Obligation,
+    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_SOL_ACCOUNT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, Token2022, TokenAccount},
+};
+use lending_protocol::{
+    cpi::accounts::Deposit as LendingDeposit,
+    program::LendingProtocol,
+    state::{Bank as LendingBank, User as LendingUser},
+};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct MintAndDepositToLending<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + Collateral::INIT_SPACE,
+        seeds = [SEED_COLLATERAL_ACCOUNT, depositor.key().as_ref()],
+        bump,
+    )]
+    pub collateral_account: Box<Account<'info, Collateral>>,
+    #[account(
+        mut,
+        seeds = [SEED_SOL_ACCOUNT, depositor.key().as_ref()],
+        bump,
+    )]
+    pub sol_account: SystemAccount<'info>,
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+    pub gold_price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub sol_price_update: Box<Account<'info, PriceUpdateV2>>,
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = mint_account,
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    // Required when the collateral account is cross margined with a lending obligation
+    pub lending_pool: Option<Box<Account<'info, LendingPool>>>,
+    pub obligation: Option<Box<Account<'info, Obligation>>>,
+
+    // lending_protocol accounts, the GOLD bank and the depositor's lending User (created with init_user)
+    pub lending_program: Program<'info, LendingProtocol>,
+    #[account(
+        mut,
+        seeds = [mint_account.key().as_ref()],
+        bump,
+        seeds::program = lending_program.key(),
+    )]
+    pub lending_bank: Box<Account<'info, LendingBank>>,
+    #[account(
+        mut,
+        seeds = [b"treasury", mint_account.key().as_ref()],
+        bump,
+        seeds::program = lending_program.key(),
+    )]
+    pub lending_bank_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [depositor.key().as_ref()],
+        bump,
+        seeds::program = lending_program.key(),
+    )]
+    pub lending_user_account: Box<Account<'info, LendingUser>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Mint GOLD against SOL collateral like deposit_collateral_and_mint, then deposit all of it into the
+// lending_protocol GOLD bank through CPI. Returns the lending deposit shares, also set as return data.
+pub fn process_mint_and_deposit_to_lending(
+    ctx: Context<MintAndDepositToLending>,
+    amount_collateral: u64,
+    amount_to_mint: u64,
+) -> Result<u64> {
+    msg!("Instruction: MintAndDepositToLending");
+
+    deposit_collateral_and_mint_internal(
+        &ctx.accounts.depositor,
+        &ctx.accounts.config_account,
+        &mut ctx.accounts.collateral_account,
+        &ctx.accounts.sol_account,
+        &ctx.accounts.mint_account,
+        &ctx.accounts.token_account,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+        ctx.accounts.lending_pool.as_deref(),
+        ctx.accounts.obligation.as_deref(),
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+        (ctx.bumps.collateral_account, ctx.bumps.sol_account),
+        amount_collateral,
+        amount_to_mint,
+    )?;
+
+    // The depositor signed this transaction, so their signature carries into the lending deposit
+    let shares = lending_protocol::cpi::deposit(
+        CpiContext::new(
+            ctx.accounts.lending_program.to_account_info(),
+            LendingDeposit {
+                signer: ctx.accounts.depositor.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                bank: ctx.accounts.lending_bank.to_account_info(),
+                bank_token_account: ctx.accounts.lending_bank_token_account.to_account_info(),
+                user_account: ctx.accounts.lending_user_account.to_account_info(),
+                user_token_account: ctx.accounts.token_account.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+        ),
+        amount_to_mint,
+    )?
+    .get();
+
+    msg!("Minted and deposited {:.9} GOLD into lending bank, shares: {}",
+         amount_to_mint as f64 / 1e9,
+         shares);
+
+    Ok(shares)
+}
@@ -1,4 +1,6 @@
 pub use deposit_collateral_and_mint_tokens::*;
 pub mod deposit_collateral_and_mint_tokens;
+pub use mint_and_deposit_to_lending::*;
+pub mod mint_and_deposit_to_lending;
 pub use utils::*;
 pub mod utils;
@@ -1,10 +1,67 @@
-use crate::SEED_MINT_ACCOUNT;
+use crate::{
+    check_collateral_health, Collateral, Config, LendingPool, Obligation, SEED_MINT_ACCOUNT,
+};
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     token_2022::{mint_to, MintTo},
     token_interface::{Mint, Token2022, TokenAccount},
 };
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+// Deposit SOL collateral and mint GOLD against it, shared by every instruction that mints from a CDP.
+// The position, cross margin included, must stay healthy with the new collateral and debt.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_collateral_and_mint_internal<'info>(
+    depositor: &Signer<'info>,
+    config_account: &Account<'info, Config>,
+    collateral_account: &mut Account<'info, Collateral>,
+    sol_account: &SystemAccount<'info>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    gold_price_update: &Account<'info, PriceUpdateV2>,
+    sol_price_update: &Account<'info, PriceUpdateV2>,
+    lending_pool: Option<&Account<'info, LendingPool>>,
+    obligation: Option<&Account<'info, Obligation>>,
+    token_program: &Program<'info, Token2022>,
+    system_program: &Program<'info, System>,
+    bumps: (u8, u8),
+    amount_collateral: u64,
+    amount_to_mint: u64,
+) -> Result<()> {
+    collateral_account.lamport_balance = sol_account.lamports() + amount_collateral;
+    collateral_account.amount_minted += amount_to_mint;
+
+    if !collateral_account.is_initialized {
+        let (bump, bump_sol_account) = bumps;
+        collateral_account.is_initialized = true;
+        collateral_account.depositor = depositor.key();
+        collateral_account.sol_account = sol_account.key();
+        collateral_account.token_account = token_account.key();
+        collateral_account.bump = bump;
+        collateral_account.bump_sol_account = bump_sol_account;
+    }
+
+    // Cross margin SOL may also back lending debt, the combined position must stay healthy
+    check_collateral_health(
+        collateral_account,
+        lending_pool,
+        obligation,
+        config_account,
+        gold_price_update,
+        sol_price_update,
+    )?;
+
+    deposit_sol_internal(depositor, sol_account, system_program, amount_collateral)?;
+
+    mint_tokens_internal(
+        mint_account,
+        token_account,
+        token_program,
+        config_account.bump_mint_account,
+        amount_to_mint,
+    )
+}
 
 pub fn mint_tokens_internal<'info>(
     mint_account: &InterfaceAccount<'info, Mint>,
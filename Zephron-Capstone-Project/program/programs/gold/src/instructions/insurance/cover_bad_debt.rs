@@ -0,0 +1,273 @@
+use crate::{
+    error::CustomError,
+    get_collateral_value_in_gold,
+    instructions::insurance::utils::{
+        burn_delegated_receipt, burn_from_insurance_vault, burn_from_lending_pool_vault,
+        transfer_from_insurance_vault,
+    },
+    instructions::lending::utils::{
+        accrue_obligation_interest, apply_repayment, record_rate_history,
+        refresh_obligation_collateral, socialize_loss, sync_rewards, thaw_receipt_account,
+        update_lending_pool, update_pool_rates,
+    },
+    Collateral, Config, InsuranceFund, LendingPool, Obligation, RateHistory,
+    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND, SEED_LENDING_POOL,
+    SEED_OBLIGATION, SEED_RATE_HISTORY,
+};
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct CoverBadDebt<'info> {
+    pub caller: Signer<'info>,
+
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Box<Account<'info, LendingPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = owner,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Required when the obligation is cross margined, its CDP SOL must have been liquidated too
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, owner.key().as_ref()],
+        bump = collateral_account.bump,
+    )]
+    pub collateral_account: Option<Box<Account<'info, Collateral>>>,
+    pub sol_account: Option<SystemAccount<'info>>,
+    pub gold_price_update: Option<Box<Account<'info, PriceUpdateV2>>>,
+    pub sol_price_update: Option<Box<Account<'info, PriceUpdateV2>>>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Cover the debt an obligation's collateral can no longer back. Its zGOLD is seized and written off
+// against the debt, then the insurance fund pays and whatever it cannot cover is written off against
+// lenders through the supply index
+pub fn process_cover_bad_debt(ctx: Context<CoverBadDebt>) -> Result<()> {
+    msg!("Instruction: CoverBadDebt");
+
+    // Cross margin debt is also backed by the owner's CDP SOL, which must be liquidated as well
+    if ctx.accounts.obligation.cross_margin {
+        let (
+            Some(collateral_account),
+            Some(sol_account),
+            Some(gold_price_update),
+            Some(sol_price_update),
+        ) = (
+            ctx.accounts.collateral_account.as_mut(),
+            &ctx.accounts.sol_account,
+            &ctx.accounts.gold_price_update,
+            &ctx.accounts.sol_price_update,
+        ) else {
+            return err!(CustomError::CrossMarginAccountsMissing);
+        };
+        require_keys_eq!(collateral_account.sol_account, sol_account.key());
+
+        collateral_account.lamport_balance = sol_account.lamports();
+        let sol_value =
+            get_collateral_value_in_gold(collateral_account, gold_price_update, sol_price_update)?;
+        msg!("Cross Margin SOL Value: {:.9}", sol_value as f64 / 1e9);
+        require!(sol_value == 0, CustomError::CollateralNotLiquidated);
+    }
+    let receipt_balance = ctx.accounts.owner_receipt_account.amount;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    update_lending_pool(lending_pool)?;
+    accrue_obligation_interest(obligation, lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    msg!("Obligation - Debt: {:.9}, Collateral Value: {:.9}",
+         total_debt as f64 / 1e9,
+         obligation.collateral_value as f64 / 1e9);
+    require!(total_debt > obligation.collateral_value, CustomError::NoBadDebt);
+
+    // Borrowing delegates the zGOLD to the pool, all of it is seized and repays the debt as the
+    // owner's own supply: principal is burned from the vault, interest stays in it
+    if receipt_balance > 0 {
+        let owner_receipt_account = &ctx.accounts.owner_receipt_account;
+        let delegated = if owner_receipt_account.delegate == COption::Some(lending_pool.key()) {
+            owner_receipt_account.delegated_amount.min(receipt_balance)
+        } else {
+            0
+        };
+        require!(delegated == receipt_balance, CustomError::CollateralNotLiquidated);
+
+        let seized_value = obligation.collateral_value;
+        let (_, principal_seized) = apply_repayment(obligation, lending_pool, seized_value)?;
+        obligation.amount_supplied = 0;
+        lending_pool.total_supplied -= seized_value;
+        update_pool_rates(lending_pool)?;
+        msg!("zGOLD Seized: {:.9}, Value: {:.9}",
+             receipt_balance as f64 / 1e9,
+             seized_value as f64 / 1e9);
+
+        burn_delegated_receipt(
+            lending_pool,
+            owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.token_program,
+            receipt_balance,
+        )?;
+        if principal_seized > 0 {
+            burn_from_lending_pool_vault(
+                lending_pool,
+                &ctx.accounts.lending_pool_vault,
+                &ctx.accounts.mint_account,
+                &ctx.accounts.token_program,
+                principal_seized,
+            )?;
+        }
+        ctx.accounts.lending_pool_vault.reload()?;
+    }
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    refresh_obligation_collateral(obligation, lending_pool, 0);
+
+    let shortfall = obligation.amount_borrowed + obligation.borrow_interest;
+    let covered = shortfall.min(ctx.accounts.insurance_vault.amount);
+    let written_off = shortfall - covered;
+    msg!("Bad Debt: {:.9}, Covered: {:.9}, Socialized: {:.9}",
+         shortfall as f64 / 1e9,
+         covered as f64 / 1e9,
+         written_off as f64 / 1e9);
+
+    // Insurance fund repays like any other payer, interest to the vault and principal burned
+    let (interest_payment, principal_payment) = apply_repayment(obligation, lending_pool, covered)?;
+
+    // Remaining shortfall is forgiven, interest first then principal
+    let interest_off = written_off.min(obligation.borrow_interest);
+    let principal_off = written_off - interest_off;
+    obligation.borrow_interest -= interest_off;
+    obligation.amount_borrowed -= principal_off;
+    lending_pool.total_borrowed -= principal_off;
+
+    // Forgiven principal was minted and is never coming back, burn the same GOLD out of the
+    // pool vault so the loss lands on lenders and the stablecoin supply stays backed
+    let principal_burn = principal_off.min(
+        ctx.accounts
+            .lending_pool_vault
+            .amount
+            .saturating_sub(lending_pool.protocol_reserves),
+    );
+    socialize_loss(lending_pool, written_off);
+    update_pool_rates(lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, 0);
+    sync_rewards(lending_pool, obligation, 0)?;
+
+    if interest_payment > 0 {
+        transfer_from_insurance_vault(
+            &ctx.accounts.insurance_fund,
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.token_program,
+            interest_payment,
+        )?;
+    }
+    if principal_payment > 0 {
+        burn_from_insurance_vault(
+            &ctx.accounts.insurance_fund,
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.token_program,
+            principal_payment,
+        )?;
+    }
+    if principal_burn > 0 {
+        burn_from_lending_pool_vault(
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.token_program,
+            principal_burn,
+        )?;
+    }
+
+    // The debt is gone, release the emptied zGOLD account
+    if ctx.accounts.owner_receipt_account.is_frozen() {
+        thaw_receipt_account(
+            &ctx.accounts.owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    let insurance_fund = &mut ctx.accounts.insurance_fund;
+    insurance_fund.total_covered += interest_payment + principal_payment;
+    insurance_fund.total_socialized += written_off;
+
+    msg!("=== BAD DEBT COVERED ===");
+    msg!("Insurance Fund - Total Covered: {:.9}, Total Socialized: {:.9}",
+         insurance_fund.total_covered as f64 / 1e9,
+         insurance_fund.total_socialized as f64 / 1e9);
+    msg!("Total Pool Supplied: {:.9}, Total Pool Borrowed: {:.9}",
+         ctx.accounts.lending_pool.total_supplied as f64 / 1e9,
+         ctx.accounts.lending_pool.total_borrowed as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
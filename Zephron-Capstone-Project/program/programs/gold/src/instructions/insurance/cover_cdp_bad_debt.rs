@@ -0,0 +1,274 @@
+use crate::{
+    burn_tokens_internal,
+    error::CustomError,
+    get_collateral_value_in_gold,
+    instructions::insurance::utils::{
+        burn_delegated_receipt, burn_from_insurance_vault, burn_from_lending_pool_vault,
+    },
+    instructions::lending::utils::{
+        available_liquidity, receipt_to_tokens, record_rate_history, refresh_obligation_collateral,
+        socialize_loss, sync_rewards, tokens_to_receipt_round_up, update_lending_pool,
+        update_pool_rates,
+    },
+    withdraw_sol_internal, Collateral, Config, InsuranceFund, LendingPool, Obligation, RateHistory,
+    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND, SEED_LENDING_POOL,
+    SEED_RATE_HISTORY,
+};
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct CoverCdpBadDebt<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    // GOLD the caller burns to buy the vault's remaining SOL before the shortfall is covered
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = caller,
+        token::token_program = token_program
+    )]
+    pub caller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub gold_price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub sol_price_update: Box<Account<'info, PriceUpdateV2>>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, collateral_account.depositor.as_ref()],
+        bump = collateral_account.bump,
+        has_one = sol_account
+    )]
+    pub collateral_account: Box<Account<'info, Collateral>>,
+
+    #[account(mut)]
+    pub sol_account: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Box<Account<'info, LendingPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Required when the collateral account is cross margined, its delegated zGOLD is seized too
+    #[account(mut)]
+    pub obligation: Option<Box<Account<'info, Obligation>>>,
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub owner_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// Retire GOLD minted by a CDP vault whose SOL no longer covers it. The vault's SOL is sold to the
+// caller at liquidation terms and cross margined zGOLD is redeemed first, then the remainder is
+// burned from the insurance fund and finally from the lending pool vault as a loss socialized across lenders
+pub fn process_cover_cdp_bad_debt(ctx: Context<CoverCdpBadDebt>) -> Result<()> {
+    msg!("Instruction: CoverCdpBadDebt");
+
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.lamport_balance = ctx.accounts.sol_account.lamports();
+
+    let collateral_value = get_collateral_value_in_gold(
+        collateral_account,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+    )?;
+    msg!("CDP - Minted: {:.9}, Collateral Value: {:.9}",
+         collateral_account.amount_minted as f64 / 1e9,
+         collateral_value as f64 / 1e9);
+    require!(
+        collateral_value < collateral_account.amount_minted,
+        CustomError::NoBadDebt
+    );
+
+    // The caller takes all of the SOL for its value less the liquidation bonus, burning that GOLD against the vault
+    let sol_seized = collateral_account.lamport_balance;
+    let sol_payment = collateral_value * 100 / (100 + ctx.accounts.config_account.liquidation_bonus);
+    if sol_seized > 0 {
+        if sol_payment > 0 {
+            burn_tokens_internal(
+                &ctx.accounts.mint_account,
+                &ctx.accounts.caller_token_account,
+                &ctx.accounts.caller,
+                &ctx.accounts.token_program,
+                sol_payment,
+            )?;
+        }
+        withdraw_sol_internal(
+            &ctx.accounts.sol_account,
+            &ctx.accounts.caller.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.collateral_account.depositor,
+            ctx.accounts.collateral_account.bump_sol_account,
+            sol_seized,
+        )?;
+
+        let collateral_account = &mut ctx.accounts.collateral_account;
+        collateral_account.amount_minted -= sol_payment;
+        collateral_account.lamport_balance = 0;
+    }
+    msg!("SOL Seized: {:.9}, GOLD Burned: {:.9}", sol_seized as f64 / 1e9, sol_payment as f64 / 1e9);
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+
+    // Cross margined zGOLD is delegated to the pool, it is redeemed against free liquidity and the
+    // GOLD burned from the pool vault retires the same amount of the vault's minted GOLD
+    let mut receipt_redeemed = 0;
+    if ctx.accounts.collateral_account.cross_margin {
+        let (Some(obligation), Some(receipt_mint), Some(owner_receipt_account)) = (
+            ctx.accounts.obligation.as_mut(),
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.owner_receipt_account,
+        ) else {
+            return err!(CustomError::CrossMarginAccountsMissing);
+        };
+        let depositor = ctx.accounts.collateral_account.depositor;
+        require_keys_eq!(obligation.owner, depositor);
+        require_keys_eq!(obligation.lending_pool, lending_pool.key());
+        require_keys_eq!(receipt_mint.key(), lending_pool.receipt_mint);
+        require_keys_eq!(owner_receipt_account.owner, depositor);
+        require_keys_eq!(owner_receipt_account.mint, receipt_mint.key());
+
+        let delegated = if owner_receipt_account.delegate == COption::Some(lending_pool.key()) {
+            owner_receipt_account.delegated_amount.min(owner_receipt_account.amount)
+        } else {
+            0
+        };
+        let receipt_value = receipt_to_tokens(delegated, lending_pool);
+        let redeem_value = receipt_value
+            .min(ctx.accounts.collateral_account.amount_minted)
+            .min(available_liquidity(lending_pool, ctx.accounts.lending_pool_vault.amount));
+        let receipt_to_burn = if redeem_value == receipt_value {
+            delegated
+        } else {
+            tokens_to_receipt_round_up(redeem_value, lending_pool).min(delegated)
+        };
+
+        if receipt_to_burn > 0 {
+            burn_delegated_receipt(
+                lending_pool,
+                owner_receipt_account,
+                receipt_mint,
+                &ctx.accounts.token_program,
+                receipt_to_burn,
+            )?;
+        }
+        if redeem_value > 0 {
+            burn_from_lending_pool_vault(
+                lending_pool,
+                &ctx.accounts.lending_pool_vault,
+                &ctx.accounts.mint_account,
+                &ctx.accounts.token_program,
+                redeem_value,
+            )?;
+        }
+
+        // Redeemed zGOLD comes out of supply interest first, then principal
+        let principal_redeemed = redeem_value.saturating_sub(obligation.supply_interest);
+        obligation.amount_supplied = obligation.amount_supplied.saturating_sub(principal_redeemed);
+        lending_pool.total_supplied -= redeem_value;
+        let receipt_remaining = owner_receipt_account.amount - receipt_to_burn;
+        refresh_obligation_collateral(obligation, lending_pool, receipt_remaining);
+        sync_rewards(lending_pool, obligation, receipt_remaining)?;
+
+        ctx.accounts.collateral_account.amount_minted -= redeem_value;
+        receipt_redeemed = redeem_value;
+    }
+    msg!("zGOLD Redeemed: {:.9}", receipt_redeemed as f64 / 1e9);
+
+    // Only what the seized collateral could not retire is covered
+    let shortfall = ctx.accounts.collateral_account.amount_minted;
+    let covered = shortfall.min(ctx.accounts.insurance_vault.amount);
+
+    // Lenders absorb what the fund cannot, bounded by the GOLD actually free in the pool vault
+    ctx.accounts.lending_pool_vault.reload()?;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let socialized = (shortfall - covered)
+        .min(available_liquidity(lending_pool, ctx.accounts.lending_pool_vault.amount));
+    socialize_loss(lending_pool, socialized);
+    update_pool_rates(lending_pool)?;
+
+    msg!("Bad Debt: {:.9}, Covered: {:.9}, Socialized: {:.9}",
+         shortfall as f64 / 1e9,
+         covered as f64 / 1e9,
+         socialized as f64 / 1e9);
+
+    if covered > 0 {
+        burn_from_insurance_vault(
+            &ctx.accounts.insurance_fund,
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.token_program,
+            covered,
+        )?;
+    }
+    if socialized > 0 {
+        burn_from_lending_pool_vault(
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.token_program,
+            socialized,
+        )?;
+    }
+
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.amount_minted -= covered + socialized;
+
+    let insurance_fund = &mut ctx.accounts.insurance_fund;
+    insurance_fund.total_covered += covered;
+    insurance_fund.total_socialized += socialized;
+
+    msg!("=== CDP BAD DEBT COVERED ===");
+    msg!("Remaining Minted: {:.9}", collateral_account.amount_minted as f64 / 1e9);
+    msg!("Insurance Fund - Total Covered: {:.9}, Total Socialized: {:.9}",
+         insurance_fund.total_covered as f64 / 1e9,
+         insurance_fund.total_socialized as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
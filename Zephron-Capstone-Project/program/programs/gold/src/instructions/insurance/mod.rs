@@ -0,0 +1,6 @@
+pub use cover_bad_debt::*;
+pub mod cover_bad_debt;
+pub use cover_cdp_bad_debt::*;
+pub mod cover_cdp_bad_debt;
+pub use utils::*;
+pub mod utils;
@@ -0,0 +1,149 @@
+use crate::{
+    instructions::lending::utils::{freeze_receipt_account, thaw_receipt_account},
+    InsuranceFund, LendingPool, BASIS_POINTS, INSURANCE_LIQUIDATION_FEE, SEED_INSURANCE_FUND,
+    SEED_LENDING_POOL,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+};
+
+// Liquidators pay a share of the repaid debt in GOLD into the insurance fund
+pub fn pay_insurance_fee<'info>(
+    insurance_fund: &mut Account<'info, InsuranceFund>,
+    insurance_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    liquidator_token_account: &InterfaceAccount<'info, TokenAccount>,
+    liquidator: &Signer<'info>,
+    token_program: &Program<'info, Token2022>,
+    amount_repaid: u64,
+) -> Result<u64> {
+    let fee = (amount_repaid as u128 * INSURANCE_LIQUIDATION_FEE as u128)
+        .div_ceil(BASIS_POINTS as u128) as u64;
+    if fee == 0 {
+        return Ok(0);
+    }
+
+    transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: liquidator_token_account.to_account_info(),
+                mint: mint_account.to_account_info(),
+                to: insurance_vault.to_account_info(),
+                authority: liquidator.to_account_info(),
+            },
+        ),
+        fee,
+        mint_account.decimals,
+    )?;
+    insurance_fund.total_deposited += fee;
+
+    msg!("Insurance Fee: {:.9}", fee as f64 / 1e9);
+    Ok(fee)
+}
+
+// Move GOLD out of the insurance vault, signed by the insurance fund PDA
+pub fn transfer_from_insurance_vault<'info>(
+    insurance_fund: &Account<'info, InsuranceFund>,
+    insurance_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Program<'info, Token2022>,
+    amount: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_INSURANCE_FUND, &[insurance_fund.bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: insurance_vault.to_account_info(),
+                mint: mint_account.to_account_info(),
+                to: destination.to_account_info(),
+                authority: insurance_fund.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint_account.decimals,
+    )
+}
+
+// Burn GOLD from the insurance vault, retiring minted debt that has no collateral behind it
+pub fn burn_from_insurance_vault<'info>(
+    insurance_fund: &Account<'info, InsuranceFund>,
+    insurance_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    token_program: &Program<'info, Token2022>,
+    amount: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_INSURANCE_FUND, &[insurance_fund.bump]]];
+    burn(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Burn {
+                mint: mint_account.to_account_info(),
+                from: insurance_vault.to_account_info(),
+                authority: insurance_fund.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+// Burn lenders' GOLD from the pool vault when uncovered bad debt is socialized
+pub fn burn_from_lending_pool_vault<'info>(
+    lending_pool: &Account<'info, LendingPool>,
+    lending_pool_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    token_program: &Program<'info, Token2022>,
+    amount: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+    burn(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Burn {
+                mint: mint_account.to_account_info(),
+                from: lending_pool_vault.to_account_info(),
+                authority: lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+// Burn zGOLD the owner delegated to the lending pool for cross margin, thawing a frozen account around the burn
+pub fn burn_delegated_receipt<'info>(
+    lending_pool: &Account<'info, LendingPool>,
+    owner_receipt_account: &InterfaceAccount<'info, TokenAccount>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Program<'info, Token2022>,
+    amount: u64,
+) -> Result<()> {
+    let is_frozen = owner_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(owner_receipt_account, receipt_mint, lending_pool, token_program)?;
+    }
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+    burn(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Burn {
+                mint: receipt_mint.to_account_info(),
+                from: owner_receipt_account.to_account_info(),
+                authority: lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    if is_frozen {
+        freeze_receipt_account(owner_receipt_account, receipt_mint, lending_pool, token_program)?;
+    }
+    Ok(())
+}
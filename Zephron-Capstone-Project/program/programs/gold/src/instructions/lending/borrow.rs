@@ -1,11 +1,15 @@
 use crate::{
-    BorrowerPosition, Config, LendingPool, SEED_BORROWER_POSITION, SEED_CONFIG_ACCOUNT,
-    SEED_LENDING_POOL,
+    instructions::lending::utils::{
+        check_refreshed, freeze_receipt_account, initialize_obligation, record_rate_history,
+        refresh_obligation_collateral, sync_rewards, thaw_receipt_account,
+    },
+    Config, LendingPool, Obligation, RateHistory, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL,
+    SEED_OBLIGATION, SEED_RATE_HISTORY,
 };
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{mint_to, Mint, MintTo, Token2022, TokenAccount},
+    token_interface::{approve, mint_to, Approve, Mint, MintTo, Token2022, TokenAccount},
 };
 
 #[derive(Accounts)]
@@ -21,22 +25,28 @@ pub struct BorrowTokens<'info> {
     pub config_account: Box<Account<'info, Config>>,
 
     #[account(
-        init_if_needed,
-        payer = borrower,
-        space = 8 + LendingPool::INIT_SPACE,
+        mut,
         seeds = [SEED_LENDING_POOL],
-        bump,
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
     )]
     pub lending_pool: Account<'info, LendingPool>,
 
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
     #[account(
         init_if_needed,
         payer = borrower,
-        space = 8 + BorrowerPosition::INIT_SPACE,
-        seeds = [SEED_BORROWER_POSITION, borrower.key().as_ref()],
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), borrower.key().as_ref()],
         bump,
     )]
-    pub borrower_position: Account<'info, BorrowerPosition>,
+    pub obligation: Account<'info, Obligation>,
 
     #[account(mut)]
     pub mint_account: InterfaceAccount<'info, Mint>,
@@ -50,6 +60,16 @@ pub struct BorrowTokens<'info> {
     )]
     pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
     pub token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -59,61 +79,47 @@ pub fn process_borrow_tokens(ctx: Context<BorrowTokens>, amount: u64) -> Result<
     msg!("Instruction: BorrowTokens");
     
     let lending_pool = &mut ctx.accounts.lending_pool;
-    let borrower_position = &mut ctx.accounts.borrower_position;
-    let clock = Clock::get()?;
+    let obligation = &mut ctx.accounts.obligation;
 
     msg!("Borrow Amount: {:.9}", amount as f64 / 1e9);
 
-    // Initialize lending pool if needed
-    if lending_pool.last_update_slot == 0 {
-        lending_pool.total_supplied = 0;
-        lending_pool.total_borrowed = 0;
-        lending_pool.last_update_slot = clock.slot;
-        lending_pool.supply_rate = 0;
-        lending_pool.borrow_rate = 0;
-        lending_pool.utilization_rate = 0;
-        lending_pool.bump = ctx.bumps.lending_pool;
-    }
+    // Initialize obligation if needed
+    initialize_obligation(obligation, ctx.accounts.borrower.key(), lending_pool, ctx.bumps.obligation)?;
 
-    // Initialize borrower position if needed
-    if borrower_position.last_update_slot == 0 {
-        borrower_position.borrower = ctx.accounts.borrower.key();
-        borrower_position.amount_borrowed = 0;
-        borrower_position.amount_supplied = 0;
-        borrower_position.accumulated_interest = 0;
-        borrower_position.last_update_slot = clock.slot;
-        borrower_position.bump = ctx.bumps.borrower_position;
-    }
+    // Pools that require refresh only accept state refreshed earlier in this slot
+    check_refreshed(lending_pool, obligation)?;
 
     // Update pool interest rates
     crate::instructions::lending::utils::update_lending_pool(lending_pool)?;
 
-    // Update borrower position interest
-    let previous_interest = borrower_position.accumulated_interest;
-    let interest = crate::instructions::lending::utils::calculate_borrower_interest(
-        borrower_position,
+    // Update obligation borrow interest
+    let previous_interest = obligation.borrow_interest;
+    crate::instructions::lending::utils::accrue_obligation_interest(obligation, lending_pool)?;
+
+    // Collateral is the current value of the zGOLD held by the borrower
+    refresh_obligation_collateral(
+        obligation,
         lending_pool,
-    )?;
-    borrower_position.accumulated_interest = interest;
-    borrower_position.last_update_slot = clock.slot;
+        ctx.accounts.borrower_receipt_account.amount,
+    );
     
     msg!("Borrower Interest Accrued: {:.9} (Previous: {:.9})",
-         interest as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9,
          previous_interest as f64 / 1e9);
-    msg!("Borrower Position - Supplied: {:.9}, Borrowed: {:.9}, Interest: {:.9}",
-         borrower_position.amount_supplied as f64 / 1e9,
-         borrower_position.amount_borrowed as f64 / 1e9,
-         borrower_position.accumulated_interest as f64 / 1e9);
+    msg!("Obligation - Collateral: {:.9}, Borrowed: {:.9}, Interest: {:.9}",
+         obligation.collateral_value as f64 / 1e9,
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9);
 
     // Check borrow capacity
-    let total_borrowable = (borrower_position.amount_supplied * crate::COLLATERAL_FACTOR) / crate::BASIS_POINTS;
-    let total_debt = borrower_position.amount_borrowed + borrower_position.accumulated_interest;
+    let total_borrowable = (obligation.collateral_value * lending_pool.collateral_factor) / crate::BASIS_POINTS;
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
     msg!("Borrow Capacity Check - Total Borrowable: {:.9}, Current Debt: {:.9}, Requested: {:.9}",
          total_borrowable as f64 / 1e9,
          total_debt as f64 / 1e9,
          amount as f64 / 1e9);
     
-    crate::instructions::lending::utils::check_borrow_capacity(borrower_position, amount)?;
+    crate::instructions::lending::utils::check_borrow_capacity(obligation, lending_pool, amount)?;
 
     // Check pool has enough liquidity
     let available_liquidity = lending_pool.total_supplied.saturating_sub(lending_pool.total_borrowed);
@@ -127,20 +133,44 @@ pub fn process_borrow_tokens(ctx: Context<BorrowTokens>, amount: u64) -> Result<
         crate::error::CustomError::InsufficientFunds
     );
 
+    // Enforce the pool borrow cap and hard maximum utilization
+    let utilization_after = if lending_pool.total_supplied > 0 {
+        ((lending_pool.total_borrowed + amount) as u128 * crate::BASIS_POINTS as u128
+            / lending_pool.total_supplied as u128) as u64
+    } else {
+        0
+    };
+    msg!("Borrow Cap Check - Borrow Cap: {:.9}, Utilization After: {} bp, Max Utilization: {} bp",
+         lending_pool.borrow_cap as f64 / 1e9,
+         utilization_after,
+         lending_pool.max_utilization);
+
+    require!(
+        lending_pool.total_borrowed + amount <= lending_pool.borrow_cap,
+        crate::error::CustomError::BorrowCapExceeded
+    );
+    require!(
+        utilization_after <= lending_pool.max_utilization,
+        crate::error::CustomError::MaxUtilizationExceeded
+    );
+
     // Update amounts
-    let previous_borrowed = borrower_position.amount_borrowed;
-    borrower_position.amount_borrowed += amount;
+    let previous_borrowed = obligation.amount_borrowed;
+    obligation.amount_borrowed += amount;
     lending_pool.total_borrowed += amount;
+    sync_rewards(lending_pool, obligation, ctx.accounts.borrower_receipt_account.amount)?;
 
-    msg!("Borrower Position Updated - Previous Borrowed: {:.9}, New Borrowed: {:.9}",
+    msg!("Obligation Updated - Previous Borrowed: {:.9}, New Borrowed: {:.9}",
          previous_borrowed as f64 / 1e9,
-         borrower_position.amount_borrowed as f64 / 1e9);
+         obligation.amount_borrowed as f64 / 1e9);
 
     // Update utilization and rates
     let (supply_rate, borrow_rate) =
         crate::instructions::lending::utils::calculate_interest_rates(
             lending_pool.total_supplied,
             lending_pool.total_borrowed,
+            lending_pool.term_borrowed,
+            lending_pool.reserve_factor,
         )?;
     lending_pool.supply_rate = supply_rate;
     lending_pool.borrow_rate = borrow_rate;
@@ -175,13 +205,54 @@ pub fn process_borrow_tokens(ctx: Context<BorrowTokens>, amount: u64) -> Result<
         amount,
     )?;
 
+    // Delegate zGOLD collateral to the pool so cover_bad_debt can seize it if the debt outgrows it,
+    // approve is rejected on frozen accounts
+    let mut is_frozen = ctx.accounts.borrower_receipt_account.is_frozen();
+    if ctx.accounts.borrower_receipt_account.delegate != COption::Some(ctx.accounts.lending_pool.key()) {
+        msg!("Delegating zGOLD collateral to the pool...");
+        if is_frozen {
+            thaw_receipt_account(
+                &ctx.accounts.borrower_receipt_account,
+                &ctx.accounts.receipt_mint,
+                &ctx.accounts.lending_pool,
+                &ctx.accounts.token_program,
+            )?;
+            is_frozen = false;
+        }
+        approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.borrower_receipt_account.to_account_info(),
+                    delegate: ctx.accounts.lending_pool.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            u64::MAX,
+        )?;
+    }
+
+    // Lock zGOLD collateral until the debt is repaid
+    if !is_frozen {
+        msg!("Freezing zGOLD collateral...");
+        freeze_receipt_account(
+            &ctx.accounts.borrower_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    let lending_pool = &ctx.accounts.lending_pool;
+    let obligation = &ctx.accounts.obligation;
     msg!("=== BORROW COMPLETE ===");
     msg!("Borrowed: {:.9} tokens", amount as f64 / 1e9);
     msg!("Total Pool Borrowed: {:.9}", lending_pool.total_borrowed as f64 / 1e9);
-    msg!("Borrower Total Borrowed: {:.9}", borrower_position.amount_borrowed as f64 / 1e9);
+    msg!("Borrower Total Borrowed: {:.9}", obligation.amount_borrowed as f64 / 1e9);
     msg!("Borrower Total Debt (Principal + Interest): {:.9}",
-         (borrower_position.amount_borrowed + borrower_position.accumulated_interest) as f64 / 1e9);
+         (obligation.amount_borrowed + obligation.borrow_interest) as f64 / 1e9);
 
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
     Ok(())
 }
 
@@ -0,0 +1,195 @@
+use crate::{
+    check_cross_margin_health_factor,
+    error::CustomError,
+    instructions::lending::utils::{
+        accrue_obligation_interest, check_refreshed, freeze_receipt_account, record_rate_history,
+        refresh_obligation_collateral, sync_rewards, update_lending_pool, update_pool_rates,
+    },
+    Collateral, Config, LendingPool, Obligation, RateHistory, BASIS_POINTS,
+    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_MINT_ACCOUNT,
+    SEED_OBLIGATION, SEED_RATE_HISTORY,
+};
+use anchor_lang::{prelude::*, solana_program::program_option::COption};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, Mint, MintTo, Token2022, TokenAccount},
+};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct BorrowTokensCrossMargin<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub gold_price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub sol_price_update: Box<Account<'info, PriceUpdateV2>>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Box<Account<'info, LendingPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), borrower.key().as_ref()],
+        bump = obligation.bump,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, borrower.key().as_ref()],
+        bump = collateral_account.bump,
+        has_one = sol_account,
+    )]
+    pub collateral_account: Box<Account<'info, Collateral>>,
+
+    pub sol_account: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = mint_account,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Borrow GOLD against the combined CDP SOL collateral and zGOLD supply of a cross margin obligation
+pub fn process_borrow_tokens_cross_margin(
+    ctx: Context<BorrowTokensCrossMargin>,
+    amount: u64,
+) -> Result<()> {
+    msg!("Instruction: BorrowTokensCrossMargin");
+    msg!("Borrow Amount: {:.9}", amount as f64 / 1e9);
+
+    require!(amount > 0, CustomError::AmountTooSmall);
+    require!(
+        ctx.accounts.obligation.cross_margin && ctx.accounts.collateral_account.cross_margin,
+        CustomError::CrossMarginNotEnabled
+    );
+
+    // Liquidations seize zGOLD through the pool delegate, it must still be in place
+    let receipt_account = &ctx.accounts.borrower_receipt_account;
+    require!(
+        receipt_account.delegate == COption::Some(ctx.accounts.lending_pool.key())
+            && receipt_account.delegated_amount >= receipt_account.amount,
+        CustomError::CrossMarginDelegateMissing
+    );
+
+    let receipt_balance = receipt_account.amount;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    // Pools that require refresh only accept state refreshed earlier in this slot
+    check_refreshed(lending_pool, obligation)?;
+
+    update_lending_pool(lending_pool)?;
+    accrue_obligation_interest(obligation, lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+    ctx.accounts.collateral_account.lamport_balance = ctx.accounts.sol_account.lamports();
+
+    // Same pool liquidity, borrow cap and utilization limits as a floating borrow
+    require!(
+        lending_pool.total_supplied >= lending_pool.total_borrowed + amount,
+        CustomError::InsufficientFunds
+    );
+    require!(
+        lending_pool.total_borrowed + amount <= lending_pool.borrow_cap,
+        CustomError::BorrowCapExceeded
+    );
+    let utilization_after = ((lending_pool.total_borrowed + amount) as u128
+        * BASIS_POINTS as u128
+        / lending_pool.total_supplied as u128) as u64;
+    require!(
+        utilization_after <= lending_pool.max_utilization,
+        CustomError::MaxUtilizationExceeded
+    );
+
+    obligation.amount_borrowed += amount;
+    lending_pool.total_borrowed += amount;
+    update_pool_rates(lending_pool)?;
+    sync_rewards(lending_pool, obligation, receipt_balance)?;
+
+    // SOL collateral and weighted zGOLD must cover CDP minted GOLD plus lending debt
+    check_cross_margin_health_factor(
+        &ctx.accounts.collateral_account,
+        &ctx.accounts.obligation,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.config_account,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+    )?;
+
+    msg!("Minting tokens to borrower...");
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        SEED_MINT_ACCOUNT,
+        &[ctx.accounts.config_account.bump_mint_account],
+    ]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.borrower_token_account.to_account_info(),
+                authority: ctx.accounts.mint_account.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    // Lock zGOLD collateral until the debt is repaid, which also locks the pool delegate
+    if !ctx.accounts.borrower_receipt_account.is_frozen() {
+        msg!("Freezing zGOLD collateral...");
+        freeze_receipt_account(
+            &ctx.accounts.borrower_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("=== CROSS MARGIN BORROW COMPLETE ===");
+    msg!("Borrower Total Debt (Principal + Interest): {:.9}",
+         (ctx.accounts.obligation.amount_borrowed + ctx.accounts.obligation.borrow_interest) as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        accrue_obligation_interest, check_refreshed, freeze_receipt_account, receipt_to_tokens,
+        record_rate_history, refresh_obligation_collateral, sync_rewards, thaw_receipt_account,
+        tokens_to_receipt_round_up, update_lending_pool, update_pool_rates,
+    },
+    Config, LendingPool, Obligation, RateHistory, TermLoan, BASIS_POINTS, SEED_CONFIG_ACCOUNT,
+    SEED_LENDING_POOL, SEED_MINT_ACCOUNT, SEED_OBLIGATION, SEED_RATE_HISTORY, SEED_TERM_LOAN,
+    SEED_TERM_LOAN_ESCROW,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, Token2022, TokenAccount, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+#[instruction(loan_id: u64)]
+pub struct BorrowTermLoan<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), borrower.key().as_ref()],
+        bump = obligation.bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + TermLoan::INIT_SPACE,
+        seeds = [
+            SEED_TERM_LOAN,
+            lending_pool.key().as_ref(),
+            borrower.key().as_ref(),
+            loan_id.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub term_loan: Account<'info, TermLoan>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = borrower,
+        associated_token::mint = mint_account,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = borrower,
+        seeds = [SEED_TERM_LOAN_ESCROW, term_loan.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+        token::token_program = token_program
+    )]
+    pub term_loan_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Fixed-rate loan with a maturity date, collateralized by zGOLD escrowed for the life of the loan
+pub fn process_borrow_term_loan(
+    ctx: Context<BorrowTermLoan>,
+    loan_id: u64,
+    amount: u64,
+    duration: i64,
+) -> Result<()> {
+    msg!("Instruction: BorrowTermLoan");
+    msg!("Term Loan #{} - Amount: {:.9}, Duration: {} s", loan_id, amount as f64 / 1e9, duration);
+
+    let clock = Clock::get()?;
+    let receipt_balance = ctx.accounts.borrower_receipt_account.amount;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    require!(
+        duration > 0 && duration <= lending_pool.max_term_duration,
+        CustomError::InvalidTermLoanDuration
+    );
+    require!(amount > 0, CustomError::AmountTooSmall);
+
+    // Pools that require refresh only accept state refreshed earlier in this slot
+    check_refreshed(lending_pool, obligation)?;
+
+    update_lending_pool(lending_pool)?;
+    accrue_obligation_interest(obligation, lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+
+    // zGOLD escrowed so that the loan sits at the pool collateral factor
+    let required_value = (amount as u128 * BASIS_POINTS as u128)
+        .div_ceil(lending_pool.collateral_factor as u128) as u64;
+    let receipt_to_escrow = tokens_to_receipt_round_up(required_value, lending_pool);
+    let floating_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    require!(
+        receipt_to_escrow <= receipt_balance,
+        CustomError::InsufficientBorrowCapacity
+    );
+
+    // Remaining zGOLD must still back the floating rate debt
+    let remaining_value = receipt_to_tokens(receipt_balance - receipt_to_escrow, lending_pool);
+    msg!("Collateral Check - Escrow: {:.9} zGOLD (Value: {:.9}), Remaining Value: {:.9}, Floating Debt: {:.9}",
+         receipt_to_escrow as f64 / 1e9,
+         required_value as f64 / 1e9,
+         remaining_value as f64 / 1e9,
+         floating_debt as f64 / 1e9);
+    require!(
+        (remaining_value * lending_pool.collateral_factor) / BASIS_POINTS >= floating_debt,
+        CustomError::InsufficientBorrowCapacity
+    );
+
+    // Same pool liquidity, borrow cap and utilization limits as a floating borrow
+    require!(
+        lending_pool.total_supplied >= lending_pool.total_borrowed + amount,
+        CustomError::InsufficientFunds
+    );
+    require!(
+        lending_pool.total_borrowed + amount <= lending_pool.borrow_cap,
+        CustomError::BorrowCapExceeded
+    );
+    let utilization_after = ((lending_pool.total_borrowed + amount) as u128
+        * BASIS_POINTS as u128
+        / lending_pool.total_supplied as u128) as u64;
+    require!(
+        utilization_after <= lending_pool.max_utilization,
+        CustomError::MaxUtilizationExceeded
+    );
+
+    // Rate is locked at the current floating borrow rate plus the term premium
+    let fixed_rate = lending_pool.borrow_rate + lending_pool.term_rate_premium;
+
+    lending_pool.total_borrowed += amount;
+    lending_pool.term_borrowed += amount;
+    update_pool_rates(lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance - receipt_to_escrow);
+    sync_rewards(lending_pool, obligation, receipt_balance - receipt_to_escrow)?;
+
+    let term_loan = &mut ctx.accounts.term_loan;
+    term_loan.owner = ctx.accounts.borrower.key();
+    term_loan.lending_pool = lending_pool.key();
+    term_loan.loan_id = loan_id;
+    term_loan.principal = amount;
+    term_loan.fixed_rate = fixed_rate;
+    term_loan.start_timestamp = clock.unix_timestamp;
+    term_loan.maturity_timestamp = clock.unix_timestamp + duration;
+    term_loan.collateral_receipt = receipt_to_escrow;
+    term_loan.bump = ctx.bumps.term_loan;
+
+    msg!("Term Loan - Fixed Rate: {} bp, Maturity: {}, Collateral: {:.9} zGOLD",
+         term_loan.fixed_rate,
+         term_loan.maturity_timestamp,
+         term_loan.collateral_receipt as f64 / 1e9);
+
+    // Escrow zGOLD, thawing the account if it was frozen for floating debt
+    let is_frozen = ctx.accounts.borrower_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(
+            &ctx.accounts.borrower_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.borrower_receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                to: ctx.accounts.term_loan_escrow.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        receipt_to_escrow,
+        ctx.accounts.receipt_mint.decimals,
+    )?;
+
+    if is_frozen {
+        freeze_receipt_account(
+            &ctx.accounts.borrower_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("Minting tokens to borrower...");
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        SEED_MINT_ACCOUNT,
+        &[ctx.accounts.config_account.bump_mint_account],
+    ]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.borrower_token_account.to_account_info(),
+                authority: ctx.accounts.mint_account.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    msg!("=== TERM BORROW COMPLETE ===");
+    msg!("Total Pool Borrowed: {:.9}", ctx.accounts.lending_pool.total_borrowed as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
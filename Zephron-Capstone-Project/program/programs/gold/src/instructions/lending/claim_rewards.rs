@@ -0,0 +1,114 @@
+use crate::{
+    error::CustomError, instructions::lending::utils::sync_rewards, LendingPool, Obligation,
+    MAX_REWARD_MINTS, SEED_LENDING_POOL, SEED_OBLIGATION, SEED_REWARD_VAULT,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        transfer_checked, Mint, Token2022, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = owner,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_REWARD_VAULT, lending_pool.key().as_ref(), reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = lending_pool,
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program
+    )]
+    pub owner_reward_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Pay out liquidity mining rewards earned on supplied zGOLD and borrowed GOLD for one reward mint
+pub fn process_claim_rewards(ctx: Context<ClaimRewards>, slot: u8) -> Result<()> {
+    msg!("Instruction: ClaimRewards");
+
+    let slot = slot as usize;
+    require!(slot < MAX_REWARD_MINTS, CustomError::InvalidRewardSlot);
+    require!(
+        ctx.accounts.lending_pool.rewards[slot].reward_mint == ctx.accounts.reward_mint.key(),
+        CustomError::InvalidRewardSlot
+    );
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    sync_rewards(lending_pool, obligation, ctx.accounts.owner_receipt_account.amount)?;
+
+    // An underfunded vault pays what it holds, the remainder stays claimable
+    let claim_amount = obligation.rewards[slot]
+        .unclaimed
+        .min(ctx.accounts.reward_vault.amount);
+    obligation.rewards[slot].unclaimed -= claim_amount;
+
+    msg!("Reward Slot {} - Claimed: {}, Still Unclaimed: {}",
+         slot,
+         claim_amount,
+         obligation.rewards[slot].unclaimed);
+
+    if claim_amount > 0 {
+        let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.reward_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.owner_reward_account.to_account_info(),
+                    authority: ctx.accounts.lending_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            claim_amount,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,11 +1,18 @@
 use crate::{
-    BorrowerPosition, LenderPosition, LendingPool, SEED_BORROWER_POSITION,
-    SEED_LENDER_POSITION, SEED_LENDING_POOL,
+    error::CustomError,
+    instructions::lending::utils::{
+        freeze_receipt_account, initialize_obligation, record_rate_history,
+        refresh_obligation_collateral, sync_rewards, thaw_receipt_account, tokens_to_receipt,
+        update_pool_rates,
+    },
+    LendingPool, Obligation, RateHistory, SEED_LENDING_POOL, SEED_OBLIGATION, SEED_RATE_HISTORY,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked},
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, Token2022, TokenAccount, TransferChecked,
+    },
 };
 
 #[derive(Accounts)]
@@ -14,31 +21,28 @@ pub struct DepositTokens<'info> {
     pub lender: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = lender,
-        space = 8 + LendingPool::INIT_SPACE,
+        mut,
         seeds = [SEED_LENDING_POOL],
-        bump,
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
     )]
     pub lending_pool: Account<'info, LendingPool>,
 
     #[account(
-        init_if_needed,
-        payer = lender,
-        space = 8 + LenderPosition::INIT_SPACE,
-        seeds = [SEED_LENDER_POSITION, lender.key().as_ref()],
-        bump,
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
     )]
-    pub lender_position: Account<'info, LenderPosition>,
+    pub rate_history: AccountLoader<'info, RateHistory>,
 
     #[account(
         init_if_needed,
         payer = lender,
-        space = 8 + BorrowerPosition::INIT_SPACE,
-        seeds = [SEED_BORROWER_POSITION, lender.key().as_ref()],
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), lender.key().as_ref()],
         bump,
     )]
-    pub borrower_position: Account<'info, BorrowerPosition>,
+    pub obligation: Account<'info, Obligation>,
 
     #[account(mut)]
     pub mint_account: InterfaceAccount<'info, Mint>,
@@ -46,9 +50,20 @@ pub struct DepositTokens<'info> {
     #[account(mut)]
     pub lender_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
     #[account(
         init_if_needed,
         payer = lender,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program
+    )]
+    pub lender_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
         seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
         bump,
         token::mint = mint_account,
@@ -63,30 +78,16 @@ pub struct DepositTokens<'info> {
 
 pub fn process_deposit_tokens(ctx: Context<DepositTokens>, amount: u64) -> Result<()> {
     msg!("Instruction: DepositTokens");
-    
+
     let lending_pool = &mut ctx.accounts.lending_pool;
-    let lender_position = &mut ctx.accounts.lender_position;
-    let borrower_position = &mut ctx.accounts.borrower_position;
-    let clock = Clock::get()?;
+    let obligation = &mut ctx.accounts.obligation;
 
     msg!("Deposit Amount: {:.9}", amount as f64 / 1e9);
 
-    // Initialize lending pool if needed
-    if lending_pool.last_update_slot == 0 {
-        msg!("Initializing lending pool...");
-        lending_pool.total_supplied = 0;
-        lending_pool.total_borrowed = 0;
-        lending_pool.last_update_slot = clock.slot;
-        lending_pool.supply_rate = 0;
-        lending_pool.borrow_rate = 0;
-        lending_pool.utilization_rate = 0;
-        lending_pool.bump = ctx.bumps.lending_pool;
-    }
-
     // Update pool interest rates
     crate::instructions::lending::utils::update_lending_pool(lending_pool)?;
-    
-    msg!("Pool State - Total Supplied: {:.9}, Total Borrowed: {:.9}", 
+
+    msg!("Pool State - Total Supplied: {:.9}, Total Borrowed: {:.9}",
          lending_pool.total_supplied as f64 / 1e9,
          lending_pool.total_borrowed as f64 / 1e9);
     msg!("Pool Rates - Supply: {} bp, Borrow: {} bp, Utilization: {} bp",
@@ -94,55 +95,39 @@ pub fn process_deposit_tokens(ctx: Context<DepositTokens>, amount: u64) -> Resul
          lending_pool.borrow_rate,
          lending_pool.utilization_rate);
 
-    // Initialize lender position if needed
-    if lender_position.last_update_slot == 0 {
-        msg!("Initializing lender position...");
-        lender_position.lender = ctx.accounts.lender.key();
-        lender_position.amount_supplied = 0;
-        lender_position.accumulated_interest = 0;
-        lender_position.last_update_slot = clock.slot;
-        lender_position.bump = ctx.bumps.lender_position;
-    } else {
-        // Calculate and add accumulated interest
-        let previous_interest = lender_position.accumulated_interest;
-        let interest = crate::instructions::lending::utils::calculate_lender_interest(
-            lender_position,
-            lending_pool,
-        )?;
-        lender_position.accumulated_interest = interest;
-        lender_position.last_update_slot = clock.slot;
-        msg!("Lender Interest Accrued: {:.9} (Previous: {:.9})",
-             interest as f64 / 1e9,
-             previous_interest as f64 / 1e9);
-    }
+    // Initialize obligation if needed, then accrue borrow interest
+    initialize_obligation(obligation, ctx.accounts.lender.key(), lending_pool, ctx.bumps.obligation)?;
+    crate::instructions::lending::utils::accrue_obligation_interest(obligation, lending_pool)?;
 
-    // Initialize borrower position if needed (for potential borrowing)
-    if borrower_position.last_update_slot == 0 {
-        borrower_position.borrower = ctx.accounts.lender.key();
-        borrower_position.amount_borrowed = 0;
-        borrower_position.amount_supplied = 0;
-        borrower_position.accumulated_interest = 0;
-        borrower_position.last_update_slot = clock.slot;
-        borrower_position.bump = ctx.bumps.borrower_position;
-    } else {
-        // Update borrower position interest
-        let interest = crate::instructions::lending::utils::calculate_borrower_interest(
-            borrower_position,
-            lending_pool,
-        )?;
-        borrower_position.accumulated_interest = interest;
-        borrower_position.last_update_slot = clock.slot;
-    }
+    // Enforce the pool supply cap
+    msg!("Supply Cap Check - Total Supplied: {:.9}, Supply Cap: {:.9}",
+         lending_pool.total_supplied as f64 / 1e9,
+         lending_pool.supply_cap as f64 / 1e9);
+    require!(
+        lending_pool.total_supplied.saturating_add(amount) <= lending_pool.supply_cap,
+        CustomError::SupplyCapExceeded
+    );
+
+    // zGOLD minted at the current exchange rate, lender interest accrues through the supply index
+    let receipt_amount = tokens_to_receipt(amount, lending_pool);
+    require!(receipt_amount > 0, CustomError::AmountTooSmall);
+    let receipt_balance = ctx.accounts.lender_receipt_account.amount + receipt_amount;
+
+    msg!("Supply Index: {:.12}, Receipt Minted: {:.9} zGOLD",
+         lending_pool.supply_index as f64 / 1e12,
+         receipt_amount as f64 / 1e9);
 
     // Update amounts
-    let previous_supplied = lender_position.amount_supplied;
-    lender_position.amount_supplied += amount;
-    borrower_position.amount_supplied += amount;
+    let previous_supplied = obligation.amount_supplied;
+    obligation.amount_supplied += amount;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
     lending_pool.total_supplied += amount;
+    update_pool_rates(lending_pool)?;
+    sync_rewards(lending_pool, obligation, receipt_balance)?;
 
-    msg!("Lender Position - Previous Supplied: {:.9}, New Supplied: {:.9}",
+    msg!("Obligation - Previous Supplied: {:.9}, New Supplied: {:.9}",
          previous_supplied as f64 / 1e9,
-         lender_position.amount_supplied as f64 / 1e9);
+         obligation.amount_supplied as f64 / 1e9);
 
     // Transfer tokens from lender to pool vault
     msg!("Transferring tokens to pool vault...");
@@ -160,12 +145,50 @@ pub fn process_deposit_tokens(ctx: Context<DepositTokens>, amount: u64) -> Resul
         ctx.accounts.mint_account.decimals,
     )?;
 
+    // Receipt account stays frozen while the lender has debt, thaw it around the mint
+    let is_frozen = ctx.accounts.lender_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("Minting zGOLD receipt to lender...");
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                to: ctx.accounts.lender_receipt_account.to_account_info(),
+                authority: ctx.accounts.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        receipt_amount,
+    )?;
+
+    if is_frozen {
+        freeze_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
     msg!("=== DEPOSIT COMPLETE ===");
     msg!("Deposited: {:.9} tokens", amount as f64 / 1e9);
-    msg!("Total Pool Supplied: {:.9}", lending_pool.total_supplied as f64 / 1e9);
-    msg!("Lender Total Supplied: {:.9}", lender_position.amount_supplied as f64 / 1e9);
-    msg!("Lender Accumulated Interest: {:.9}", lender_position.accumulated_interest as f64 / 1e9);
-
+    msg!("Total Pool Supplied: {:.9}", ctx.accounts.lending_pool.total_supplied as f64 / 1e9);
+    msg!("Lender Total Supplied: {:.9}", ctx.accounts.obligation.amount_supplied as f64 / 1e9);
+    msg!("Lender Supply Interest: {:.9}", ctx.accounts.obligation.supply_interest as f64 / 1e9);
+    msg!("Lender zGOLD Balance: {:.9} (Value: {:.9} GOLD)",
+         receipt_balance as f64 / 1e9,
+         ctx.accounts.obligation.collateral_value as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
     Ok(())
 }
-
@@ -0,0 +1,107 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        fill_queued_withdrawal, record_rate_history, update_lending_pool,
+    },
+    Config, LendingPool, RateHistory, WithdrawalRequest, SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL,
+    SEED_RATE_HISTORY, SEED_WITHDRAWAL_QUEUE, SEED_WITHDRAWAL_REQUEST,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct FillWithdrawalRequest<'info> {
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    // Only the request at the head of the queue can be filled
+    #[account(
+        mut,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_head.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+        has_one = owner,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub withdrawal_queue: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Permissionless crank, fills the head of the withdrawal queue from liquidity freed by repayments
+pub fn process_fill_withdrawal_request(ctx: Context<FillWithdrawalRequest>) -> Result<()> {
+    msg!("Instruction: FillWithdrawalRequest");
+
+    update_lending_pool(&mut ctx.accounts.lending_pool)?;
+
+    let filled = fill_queued_withdrawal(
+        &mut ctx.accounts.lending_pool,
+        &mut ctx.accounts.withdrawal_request,
+        &ctx.accounts.owner,
+        &ctx.accounts.owner_token_account,
+        &ctx.accounts.mint_account,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.withdrawal_queue,
+        &ctx.accounts.lending_pool_vault,
+        &ctx.accounts.token_program,
+    )?;
+    require!(filled, CustomError::InsufficientLiquidity);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::{error::CustomError, LendingPool, SEED_LENDING_POOL};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+    Discriminator,
+};
+use anchor_spl::token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked};
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::token_program = token_program
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: instructions sysvar, used to find the matching flash_repay
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn process_flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+    msg!("Instruction: FlashBorrow");
+    msg!("Flash Borrow Amount: {:.9}", amount as f64 / 1e9);
+
+    // Introspection only sees top level instructions, so the borrow must not come through CPI
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+        CustomError::FlashLoanCpiNotAllowed
+    );
+
+    // Find a flash_repay for this borrow later in the same transaction
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let current_index = load_current_index_checked(&instructions_sysvar)?;
+    let mut repay_found = false;
+    let mut index = current_index as usize + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, &instructions_sysvar) {
+        let repay_discriminator = crate::instruction::FlashRepay::DISCRIMINATOR;
+        if instruction.program_id == crate::ID && instruction.data.starts_with(repay_discriminator) {
+            let repay = crate::instruction::FlashRepay::try_from_slice(
+                &instruction.data[repay_discriminator.len()..],
+            )?;
+            if repay.borrow_instruction_index as u16 == current_index && repay.amount == amount {
+                repay_found = true;
+                break;
+            }
+        }
+        index += 1;
+    }
+    require!(repay_found, CustomError::FlashLoanRepayMissing);
+
+    msg!("Vault Liquidity: {:.9}", ctx.accounts.lending_pool_vault.amount as f64 / 1e9);
+    require!(
+        amount > 0 && amount <= ctx.accounts.lending_pool_vault.amount,
+        CustomError::InsufficientFunds
+    );
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.lending_pool_vault.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.borrower_token_account.to_account_info(),
+                authority: ctx.accounts.lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.mint_account.decimals,
+    )?;
+
+    msg!("=== FLASH BORROW COMPLETE ===");
+    msg!("Repay Instruction Index: {}", index);
+    Ok(())
+}
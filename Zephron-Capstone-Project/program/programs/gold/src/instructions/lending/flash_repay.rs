@@ -0,0 +1,104 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{accrue_to_lenders, update_lending_pool, update_pool_rates},
+    LendingPool, BASIS_POINTS, SEED_LENDING_POOL,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    Discriminator,
+};
+use anchor_spl::token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked};
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::token_program = token_program
+    )]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: instructions sysvar, used to verify the matching flash_borrow
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn process_flash_repay(
+    ctx: Context<FlashRepay>,
+    amount: u64,
+    borrow_instruction_index: u8,
+) -> Result<()> {
+    msg!("Instruction: FlashRepay");
+
+    // The referenced instruction must be an earlier flash_borrow of the same amount
+    let instructions_sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let current_index = load_current_index_checked(&instructions_sysvar)?;
+    require!(
+        (borrow_instruction_index as u16) < current_index,
+        CustomError::FlashLoanInvalidRepay
+    );
+    let borrow_instruction =
+        load_instruction_at_checked(borrow_instruction_index as usize, &instructions_sysvar)?;
+    let borrow_discriminator = crate::instruction::FlashBorrow::DISCRIMINATOR;
+    require!(
+        borrow_instruction.program_id == crate::ID
+            && borrow_instruction.data.starts_with(borrow_discriminator),
+        CustomError::FlashLoanInvalidRepay
+    );
+    let borrow = crate::instruction::FlashBorrow::try_from_slice(
+        &borrow_instruction.data[borrow_discriminator.len()..],
+    )?;
+    require!(borrow.amount == amount, CustomError::FlashLoanInvalidRepay);
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let fee = (amount as u128 * lending_pool.flash_loan_fee as u128).div_ceil(BASIS_POINTS as u128) as u64;
+    msg!("Flash Repay Amount: {:.9}, Fee: {:.9}", amount as f64 / 1e9, fee as f64 / 1e9);
+
+    // Accrue interest up to now first, so supply interest is not earned on the fee for time before it was paid
+    update_lending_pool(lending_pool)?;
+
+    // Flash loan fee accrues to lenders through the supply index
+    accrue_to_lenders(lending_pool, fee);
+    update_pool_rates(lending_pool)?;
+
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.borrower_token_account.to_account_info(),
+                mint: ctx.accounts.mint_account.to_account_info(),
+                to: ctx.accounts.lending_pool_vault.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        amount + fee,
+        ctx.accounts.mint_account.decimals,
+    )?;
+
+    msg!("=== FLASH REPAY COMPLETE ===");
+    msg!("Supply Index: {:.12}", ctx.accounts.lending_pool.supply_index as f64 / 1e12);
+    Ok(())
+}
@@ -0,0 +1,367 @@
+use crate::{
+    calculate_cross_margin_health_factor, check_health_factor,
+    error::CustomError,
+    get_lamports_from_gold,
+    instructions::insurance::utils::pay_insurance_fee,
+    instructions::lending::utils::{
+        accrue_obligation_interest, apply_repayment, fill_queued_withdrawal,
+        freeze_receipt_account, record_rate_history, refresh_obligation_collateral, sync_rewards,
+        thaw_receipt_account, tokens_to_receipt_round_up, update_lending_pool,
+    },
+    withdraw_sol_internal, Collateral, Config, InsuranceFund, LendingPool, MarginSide, Obligation,
+    RateHistory, WithdrawalRequest, CROSS_MARGIN_CLOSE_FACTOR, SEED_COLLATERAL_ACCOUNT,
+    SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND, SEED_LENDING_POOL, SEED_OBLIGATION,
+    SEED_RATE_HISTORY, SEED_WITHDRAWAL_QUEUE, SEED_WITHDRAWAL_REQUEST,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+    },
+};
+use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct LiquidateCrossMargin<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    pub owner: SystemAccount<'info>,
+
+    pub gold_price_update: Box<Account<'info, PriceUpdateV2>>,
+    pub sol_price_update: Box<Account<'info, PriceUpdateV2>>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Box<Account<'info, LendingPool>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = owner,
+    )]
+    pub obligation: Box<Account<'info, Obligation>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, owner.key().as_ref()],
+        bump = collateral_account.bump,
+        has_one = sol_account,
+    )]
+    pub collateral_account: Box<Account<'info, Collateral>>,
+
+    #[account(mut)]
+    pub sol_account: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = liquidator,
+        token::token_program = token_program
+    )]
+    pub liquidator_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program
+    )]
+    pub liquidator_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Head of the withdrawal queue, filled from the liquidity this liquidation frees when passed
+    #[account(
+        mut,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_head.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Option<Account<'info, WithdrawalRequest>>,
+    #[account(mut)]
+    pub withdrawal_request_owner: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub withdrawal_request_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub withdrawal_queue: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Repay lending debt of an unhealthy cross margin position and seize SOL or zGOLD worth the repayment plus the bonus
+pub fn process_liquidate_cross_margin(
+    ctx: Context<LiquidateCrossMargin>,
+    amount: u64,
+    side: MarginSide,
+) -> Result<()> {
+    msg!("Instruction: LiquidateCrossMargin");
+    msg!("Repay Amount: {:.9}, Seize From: {:?}", amount as f64 / 1e9, side);
+
+    require!(ctx.accounts.obligation.cross_margin, CustomError::CrossMarginNotEnabled);
+
+    let receipt_balance = ctx.accounts.owner_receipt_account.amount;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    update_lending_pool(lending_pool)?;
+    accrue_obligation_interest(obligation, lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+    ctx.accounts.collateral_account.lamport_balance = ctx.accounts.sol_account.lamports();
+
+    let health_factor = calculate_cross_margin_health_factor(
+        &ctx.accounts.collateral_account,
+        &ctx.accounts.obligation,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+    )?;
+    require!(
+        health_factor < ctx.accounts.config_account.min_health_factor,
+        CustomError::AboveMinimumHealthFactor
+    );
+
+    // A single liquidation repays at most the close factor share of the debt
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    let max_repay = (total_debt * CROSS_MARGIN_CLOSE_FACTOR).div_ceil(100);
+    msg!("Close Factor {}%, Max Repay: {:.9}", CROSS_MARGIN_CLOSE_FACTOR, max_repay as f64 / 1e9);
+    let (interest_payment, principal_payment) =
+        apply_repayment(obligation, lending_pool, amount.min(max_repay))?;
+    let repay_amount = interest_payment + principal_payment;
+    require!(repay_amount > 0, CustomError::AmountTooSmall);
+
+    let liquidation_bonus = repay_amount * ctx.accounts.config_account.liquidation_bonus / 100;
+    let seize_value = repay_amount + liquidation_bonus;
+
+    msg!("*** CROSS MARGIN LIQUIDATION ***");
+    msg!("Bonus {}%", ctx.accounts.config_account.liquidation_bonus);
+    msg!("Repaid: {:.9} (Interest: {:.9}, Principal: {:.9}), Seize Value: {:.9}",
+         repay_amount as f64 / 1e9,
+         interest_payment as f64 / 1e9,
+         principal_payment as f64 / 1e9,
+         seize_value as f64 / 1e9);
+
+    // Interest is paid into the pool vault, principal is burned
+    if interest_payment > 0 {
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.lending_pool_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            interest_payment,
+            ctx.accounts.mint_account.decimals,
+        )?;
+    }
+
+    if principal_payment > 0 {
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    from: ctx.accounts.liquidator_token_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            principal_payment,
+        )?;
+    }
+
+    pay_insurance_fee(
+        &mut ctx.accounts.insurance_fund,
+        &ctx.accounts.insurance_vault,
+        &ctx.accounts.mint_account,
+        &ctx.accounts.liquidator_token_account,
+        &ctx.accounts.liquidator,
+        &ctx.accounts.token_program,
+        repay_amount,
+    )?;
+
+    let mut receipt_remaining = receipt_balance;
+    match side {
+        MarginSide::Cdp => {
+            let lamports = get_lamports_from_gold(
+                &seize_value,
+                &ctx.accounts.gold_price_update,
+                &ctx.accounts.sol_price_update,
+            )?
+            .min(ctx.accounts.sol_account.lamports());
+            msg!("SOL Seized: {:.9}", lamports as f64 / 1e9);
+
+            withdraw_sol_internal(
+                &ctx.accounts.sol_account,
+                &ctx.accounts.liquidator.to_account_info(),
+                &ctx.accounts.system_program,
+                &ctx.accounts.owner.key(),
+                ctx.accounts.collateral_account.bump_sol_account,
+                lamports,
+            )?;
+            ctx.accounts.collateral_account.lamport_balance = ctx.accounts.sol_account.lamports();
+
+            // The SOL also backs GOLD minted from the CDP, which must stay healthy on its own
+            check_health_factor(
+                &ctx.accounts.collateral_account,
+                &ctx.accounts.config_account,
+                &ctx.accounts.gold_price_update,
+                &ctx.accounts.sol_price_update,
+            )?;
+        }
+        MarginSide::Lending => {
+            let receipt_to_seize = tokens_to_receipt_round_up(seize_value, &ctx.accounts.lending_pool)
+                .min(receipt_balance);
+            receipt_remaining -= receipt_to_seize;
+            msg!("zGOLD Seized: {:.9}", receipt_to_seize as f64 / 1e9);
+
+            // The pool moves the zGOLD as the delegate approved by set_cross_margin
+            let is_frozen = ctx.accounts.owner_receipt_account.is_frozen();
+            if is_frozen {
+                thaw_receipt_account(
+                    &ctx.accounts.owner_receipt_account,
+                    &ctx.accounts.receipt_mint,
+                    &ctx.accounts.lending_pool,
+                    &ctx.accounts.token_program,
+                )?;
+            }
+
+            let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.owner_receipt_account.to_account_info(),
+                        mint: ctx.accounts.receipt_mint.to_account_info(),
+                        to: ctx.accounts.liquidator_receipt_account.to_account_info(),
+                        authority: ctx.accounts.lending_pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                receipt_to_seize,
+                ctx.accounts.receipt_mint.decimals,
+            )?;
+
+            let obligation = &ctx.accounts.obligation;
+            if is_frozen && obligation.amount_borrowed + obligation.borrow_interest > 0 {
+                freeze_receipt_account(
+                    &ctx.accounts.owner_receipt_account,
+                    &ctx.accounts.receipt_mint,
+                    &ctx.accounts.lending_pool,
+                    &ctx.accounts.token_program,
+                )?;
+            }
+        }
+    }
+
+    // Queued withdrawals are filled from the interest this liquidation paid in
+    if let Some(withdrawal_request) = ctx.accounts.withdrawal_request.as_mut() {
+        let (Some(request_owner), Some(request_token_account), Some(withdrawal_queue)) = (
+            &ctx.accounts.withdrawal_request_owner,
+            &ctx.accounts.withdrawal_request_token_account,
+            &ctx.accounts.withdrawal_queue,
+        ) else {
+            return err!(CustomError::WithdrawalRequestAccountsMissing);
+        };
+        require_keys_eq!(withdrawal_request.owner, request_owner.key());
+        require_keys_eq!(request_token_account.owner, request_owner.key());
+        require_keys_eq!(request_token_account.mint, ctx.accounts.mint_account.key());
+
+        ctx.accounts.lending_pool_vault.reload()?;
+        fill_queued_withdrawal(
+            &mut ctx.accounts.lending_pool,
+            withdrawal_request,
+            request_owner,
+            request_token_account,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.receipt_mint,
+            withdrawal_queue,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_remaining);
+    sync_rewards(lending_pool, obligation, receipt_remaining)?;
+
+    // Optional, logs new health factor
+    calculate_cross_margin_health_factor(
+        &ctx.accounts.collateral_account,
+        &ctx.accounts.obligation,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.gold_price_update,
+        &ctx.accounts.sol_price_update,
+    )?;
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
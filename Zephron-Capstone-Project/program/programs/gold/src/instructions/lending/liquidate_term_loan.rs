@@ -0,0 +1,237 @@
+use crate::{
+    error::CustomError,
+    instructions::insurance::utils::pay_insurance_fee,
+    instructions::lending::utils::{
+        book_term_loan_interest, close_receipt_escrow, record_rate_history,
+        release_escrowed_receipt, term_loan_interest, tokens_to_receipt_round_up,
+        update_lending_pool, update_pool_rates,
+    },
+    Config, InsuranceFund, LendingPool, RateHistory, TermLoan, LIQUIDATION_BONUS,
+    SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND, SEED_LENDING_POOL, SEED_RATE_HISTORY, SEED_TERM_LOAN,
+    SEED_TERM_LOAN_ESCROW,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct LiquidateTermLoan<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            SEED_TERM_LOAN,
+            lending_pool.key().as_ref(),
+            owner.key().as_ref(),
+            term_loan.loan_id.to_le_bytes().as_ref()
+        ],
+        bump = term_loan.bump,
+        has_one = owner,
+    )]
+    pub term_loan: Account<'info, TermLoan>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = liquidator,
+        token::token_program = token_program
+    )]
+    pub liquidator_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = liquidator,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program
+    )]
+    pub liquidator_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TERM_LOAN_ESCROW, term_loan.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub term_loan_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Once a term loan is past maturity plus the grace period, anyone can repay it for the escrowed zGOLD plus a bonus
+pub fn process_liquidate_term_loan(ctx: Context<LiquidateTermLoan>) -> Result<()> {
+    msg!("Instruction: LiquidateTermLoan");
+
+    let now = Clock::get()?.unix_timestamp;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let term_loan = &ctx.accounts.term_loan;
+
+    msg!("Term Loan #{} - Maturity: {}, Grace Period: {} s, Now: {}",
+         term_loan.loan_id,
+         term_loan.maturity_timestamp,
+         lending_pool.term_grace_period,
+         now);
+    require!(
+        now > term_loan.maturity_timestamp + lending_pool.term_grace_period,
+        CustomError::TermLoanNotLiquidatable
+    );
+
+    update_lending_pool(lending_pool)?;
+
+    let interest = term_loan_interest(term_loan, now);
+    let total_debt = term_loan.principal + interest;
+
+    // Liquidator receives zGOLD worth the debt plus the liquidation bonus, the rest goes back to the owner
+    let seize_value = total_debt + (total_debt * LIQUIDATION_BONUS) / 100;
+    let receipt_to_liquidator =
+        tokens_to_receipt_round_up(seize_value, lending_pool).min(term_loan.collateral_receipt);
+    let receipt_to_owner = term_loan.collateral_receipt - receipt_to_liquidator;
+
+    msg!("Term Loan Debt - Principal: {:.9}, Interest: {:.9}, Total: {:.9}",
+         term_loan.principal as f64 / 1e9,
+         interest as f64 / 1e9,
+         total_debt as f64 / 1e9);
+    msg!("Collateral Split - Liquidator: {:.9} zGOLD, Owner: {:.9} zGOLD",
+         receipt_to_liquidator as f64 / 1e9,
+         receipt_to_owner as f64 / 1e9);
+
+    book_term_loan_interest(lending_pool, interest);
+    lending_pool.total_borrowed -= term_loan.principal;
+    lending_pool.term_borrowed -= term_loan.principal;
+    update_pool_rates(lending_pool)?;
+
+    // Interest is paid into the pool vault, principal is burned
+    if interest > 0 {
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.liquidator_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.lending_pool_vault.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            interest,
+            ctx.accounts.mint_account.decimals,
+        )?;
+    }
+
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.mint_account.to_account_info(),
+                from: ctx.accounts.liquidator_token_account.to_account_info(),
+                authority: ctx.accounts.liquidator.to_account_info(),
+            },
+        ),
+        ctx.accounts.term_loan.principal,
+    )?;
+
+    pay_insurance_fee(
+        &mut ctx.accounts.insurance_fund,
+        &ctx.accounts.insurance_vault,
+        &ctx.accounts.mint_account,
+        &ctx.accounts.liquidator_token_account,
+        &ctx.accounts.liquidator,
+        &ctx.accounts.token_program,
+        total_debt,
+    )?;
+
+    release_escrowed_receipt(
+        &ctx.accounts.term_loan_escrow,
+        &ctx.accounts.liquidator_receipt_account,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.token_program,
+        receipt_to_liquidator,
+    )?;
+    release_escrowed_receipt(
+        &ctx.accounts.term_loan_escrow,
+        &ctx.accounts.owner_receipt_account,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.token_program,
+        receipt_to_owner,
+    )?;
+    close_receipt_escrow(
+        &ctx.accounts.term_loan_escrow,
+        ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.token_program,
+    )?;
+
+    msg!("=== TERM LOAN LIQUIDATED ===");
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
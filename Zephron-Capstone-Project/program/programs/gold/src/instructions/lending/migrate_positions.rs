@@ -0,0 +1,186 @@
+use crate::{
+    instructions::lending::utils::{
+        accrued_interest, freeze_receipt_account, record_rate_history,
+        refresh_obligation_collateral, sync_rewards, thaw_receipt_account, tokens_to_receipt,
+        update_lending_pool, update_pool_rates,
+    },
+    BorrowerPosition, LenderPosition, LendingPool, Obligation, RateHistory,
+    SEED_BORROWER_POSITION, SEED_LENDER_POSITION, SEED_LENDING_POOL, SEED_OBLIGATION,
+    SEED_RATE_HISTORY, SLOTS_PER_YEAR,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{mint_to, Mint, MintTo, Token2022, TokenAccount},
+};
+
+#[derive(Accounts)]
+pub struct MigratePositions<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SEED_LENDER_POSITION, owner.key().as_ref()],
+        bump = lender_position.bump,
+        constraint = lender_position.lender == owner.key(),
+    )]
+    pub lender_position: Option<Account<'info, LenderPosition>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SEED_BORROWER_POSITION, owner.key().as_ref()],
+        bump = borrower_position.bump,
+        constraint = borrower_position.borrower == owner.key(),
+    )]
+    pub borrower_position: Account<'info, BorrowerPosition>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Fold the legacy lender and borrower positions of a wallet into a single obligation. A legacy
+// pool is first rewritten in the current layout by migrate_lending_pool.
+pub fn process_migrate_positions(ctx: Context<MigratePositions>) -> Result<()> {
+    msg!("Instruction: MigratePositions");
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+
+    // Legacy positions only tracked slots, bring lender and borrower interest up to date on the slot clock
+    let clock = Clock::get()?;
+    let borrower_position = &ctx.accounts.borrower_position;
+    let (amount_supplied, supply_interest) = match &ctx.accounts.lender_position {
+        Some(lender_position) => {
+            let slots_elapsed = clock.slot.saturating_sub(lender_position.last_update_slot);
+            let interest = lender_position.accumulated_interest
+                + accrued_interest(
+                    lender_position.amount_supplied,
+                    lending_pool.supply_rate,
+                    slots_elapsed,
+                    SLOTS_PER_YEAR,
+                ) as u64;
+            (lender_position.amount_supplied, interest)
+        }
+        None => (0, 0),
+    };
+
+    // Legacy supply is paid out in zGOLD at the current exchange rate. The principal is already in
+    // total_supplied from the legacy deposit, the interest it earned was never booked.
+    let receipt_amount = tokens_to_receipt(amount_supplied + supply_interest, lending_pool);
+    let receipt_balance = ctx.accounts.owner_receipt_account.amount + receipt_amount;
+    lending_pool.total_supplied += supply_interest;
+    update_pool_rates(lending_pool)?;
+
+    msg!("Legacy Supply: {:.9}, Interest: {:.9}, Receipt Minted: {:.9} zGOLD",
+         amount_supplied as f64 / 1e9,
+         supply_interest as f64 / 1e9,
+         receipt_amount as f64 / 1e9);
+
+    let obligation = &mut ctx.accounts.obligation;
+    obligation.owner = ctx.accounts.owner.key();
+    obligation.lending_pool = lending_pool.key();
+    obligation.amount_supplied = amount_supplied;
+    obligation.amount_borrowed = borrower_position.amount_borrowed;
+    obligation.borrow_interest = borrower_position.accumulated_interest;
+    obligation.bump = ctx.bumps.obligation;
+    obligation.is_initialized = true;
+
+    let slots_elapsed = clock.slot.saturating_sub(borrower_position.last_update_slot);
+    obligation.borrow_interest += accrued_interest(
+        obligation.amount_borrowed,
+        lending_pool.borrow_rate,
+        slots_elapsed,
+        SLOTS_PER_YEAR,
+    ) as u64;
+    obligation.last_update_slot = clock.slot;
+    obligation.last_update_timestamp = clock.unix_timestamp;
+    obligation.last_refresh_slot = 0;
+
+    // Supply interest and collateral are derived from the zGOLD balance
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+    sync_rewards(lending_pool, obligation, receipt_balance)?;
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+
+    if receipt_amount > 0 {
+        // A receipt account frozen by an earlier loan is thawed around the mint
+        let is_frozen = ctx.accounts.owner_receipt_account.is_frozen();
+        if is_frozen {
+            thaw_receipt_account(
+                &ctx.accounts.owner_receipt_account,
+                &ctx.accounts.receipt_mint,
+                &ctx.accounts.lending_pool,
+                &ctx.accounts.token_program,
+            )?;
+        }
+
+        let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[ctx.accounts.lending_pool.bump]]];
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.receipt_mint.to_account_info(),
+                    to: ctx.accounts.owner_receipt_account.to_account_info(),
+                    authority: ctx.accounts.lending_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            receipt_amount,
+        )?;
+        ctx.accounts.owner_receipt_account.reload()?;
+    }
+
+    // Migrated debt locks the zGOLD backing it, as a borrow would
+    if total_debt > 0 && !ctx.accounts.owner_receipt_account.is_frozen() {
+        freeze_receipt_account(
+            &ctx.accounts.owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("Migrated Obligation:{:#?}", ctx.accounts.obligation);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -1,11 +1,41 @@
 pub mod deposit;
 pub mod borrow;
 pub mod repay;
+pub mod repay_for;
 pub mod withdraw;
+pub mod request_withdrawal;
+pub mod fill_withdrawal_request;
+pub mod borrow_term_loan;
+pub mod repay_term_loan;
+pub mod liquidate_term_loan;
+pub mod flash_borrow;
+pub mod flash_repay;
+pub mod migrate_positions;
+pub mod refresh_pool;
+pub mod refresh_position;
+pub mod claim_rewards;
+pub mod set_cross_margin;
+pub mod borrow_cross_margin;
+pub mod liquidate_cross_margin;
 pub mod utils;
 
 pub use deposit::*;
 pub use borrow::*;
 pub use repay::*;
+pub use repay_for::*;
 pub use withdraw::*;
+pub use request_withdrawal::*;
+pub use fill_withdrawal_request::*;
+pub use borrow_term_loan::*;
+pub use repay_term_loan::*;
+pub use liquidate_term_loan::*;
+pub use flash_borrow::*;
+pub use flash_repay::*;
+pub use migrate_positions::*;
+pub use refresh_pool::*;
+pub use refresh_position::*;
+pub use claim_rewards::*;
+pub use set_cross_margin::*;
+pub use borrow_cross_margin::*;
+pub use liquidate_cross_margin::*;
 
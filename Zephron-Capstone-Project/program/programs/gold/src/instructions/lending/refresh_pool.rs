@@ -0,0 +1,43 @@
+use crate::{
+    instructions::lending::utils::{record_rate_history, update_lending_pool},
+    LendingPool, RateHistory, SEED_LENDING_POOL, SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RefreshPool<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+}
+
+// Permissionless crank, accrues pool interest and recomputes utilization and rates
+pub fn process_refresh_pool(ctx: Context<RefreshPool>) -> Result<()> {
+    msg!("Instruction: RefreshPool");
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    update_lending_pool(lending_pool)?;
+    lending_pool.last_refresh_slot = Clock::get()?.slot;
+
+    msg!("Pool State - Total Supplied: {:.9}, Total Borrowed: {:.9}, Supply Index: {:.12}",
+         lending_pool.total_supplied as f64 / 1e9,
+         lending_pool.total_borrowed as f64 / 1e9,
+         lending_pool.supply_index as f64 / 1e12);
+    msg!("Pool Rates - Supply: {} bp, Borrow: {} bp, Utilization: {} bp",
+         lending_pool.supply_rate,
+         lending_pool.borrow_rate,
+         lending_pool.utilization_rate);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::{
+    instructions::lending::utils::{
+        accrue_obligation_interest, record_rate_history, refresh_obligation_collateral,
+        sync_rewards, update_lending_pool,
+    },
+    LendingPool, Obligation, RateHistory, SEED_LENDING_POOL, SEED_OBLIGATION, SEED_RATE_HISTORY,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct RefreshPosition<'info> {
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = lending_pool,
+        has_one = owner,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Permissionless crank, accrues pool and obligation interest and revalues zGOLD collateral
+pub fn process_refresh_position(ctx: Context<RefreshPosition>) -> Result<()> {
+    msg!("Instruction: RefreshPosition");
+
+    let current_slot = Clock::get()?.slot;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    update_lending_pool(lending_pool)?;
+    lending_pool.last_refresh_slot = current_slot;
+
+    accrue_obligation_interest(obligation, lending_pool)?;
+    refresh_obligation_collateral(
+        obligation,
+        lending_pool,
+        ctx.accounts.owner_receipt_account.amount,
+    );
+    sync_rewards(lending_pool, obligation, ctx.accounts.owner_receipt_account.amount)?;
+    obligation.last_refresh_slot = current_slot;
+
+    msg!("Obligation Supply - Supplied: {:.9}, Interest: {:.9}, Collateral Value: {:.9}",
+         obligation.amount_supplied as f64 / 1e9,
+         obligation.supply_interest as f64 / 1e9,
+         obligation.collateral_value as f64 / 1e9);
+    msg!("Obligation Debt - Principal: {:.9}, Interest: {:.9}",
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
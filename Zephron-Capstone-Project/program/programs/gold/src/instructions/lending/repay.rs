@@ -1,11 +1,19 @@
 use crate::{
-    BorrowerPosition, Config, LendingPool, SEED_BORROWER_POSITION, SEED_CONFIG_ACCOUNT,
-    SEED_LENDING_POOL,
+    error::CustomError,
+    instructions::lending::utils::{
+        apply_repayment, fill_queued_withdrawal, initialize_obligation, record_rate_history,
+        sync_rewards, thaw_receipt_account,
+    },
+    Config, LendingPool, Obligation, RateHistory, WithdrawalRequest, SEED_CONFIG_ACCOUNT,
+    SEED_LENDING_POOL, SEED_OBLIGATION, SEED_RATE_HISTORY, SEED_WITHDRAWAL_QUEUE,
+    SEED_WITHDRAWAL_REQUEST,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{burn, Burn, Mint, Token2022, TokenAccount},
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+    },
 };
 
 #[derive(Accounts)]
@@ -21,22 +29,28 @@ pub struct RepayTokens<'info> {
     pub config_account: Box<Account<'info, Config>>,
 
     #[account(
-        init_if_needed,
-        payer = borrower,
-        space = 8 + LendingPool::INIT_SPACE,
+        mut,
         seeds = [SEED_LENDING_POOL],
-        bump,
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
     )]
     pub lending_pool: Account<'info, LendingPool>,
 
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
     #[account(
         init_if_needed,
         payer = borrower,
-        space = 8 + BorrowerPosition::INIT_SPACE,
-        seeds = [SEED_BORROWER_POSITION, borrower.key().as_ref()],
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), borrower.key().as_ref()],
         bump,
     )]
-    pub borrower_position: Account<'info, BorrowerPosition>,
+    pub obligation: Account<'info, Obligation>,
 
     #[account(mut)]
     pub mint_account: InterfaceAccount<'info, Mint>,
@@ -44,6 +58,50 @@ pub struct RepayTokens<'info> {
     #[account(mut)]
     pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Head of the withdrawal queue, filled from the liquidity this repayment frees when passed
+    #[account(
+        mut,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_head.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Option<Account<'info, WithdrawalRequest>>,
+    #[account(mut)]
+    pub withdrawal_request_owner: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub withdrawal_request_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub withdrawal_queue: Option<InterfaceAccount<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -53,118 +111,66 @@ pub fn process_repay_tokens(ctx: Context<RepayTokens>, amount: u64) -> Result<()
     msg!("Instruction: RepayTokens");
     
     let lending_pool = &mut ctx.accounts.lending_pool;
-    let borrower_position = &mut ctx.accounts.borrower_position;
-    let clock = Clock::get()?;
+    let obligation = &mut ctx.accounts.obligation;
 
     msg!("Repay Amount: {:.9}", amount as f64 / 1e9);
 
-    // Initialize lending pool if needed
-    if lending_pool.last_update_slot == 0 {
-        lending_pool.total_supplied = 0;
-        lending_pool.total_borrowed = 0;
-        lending_pool.last_update_slot = clock.slot;
-        lending_pool.supply_rate = 0;
-        lending_pool.borrow_rate = 0;
-        lending_pool.utilization_rate = 0;
-        lending_pool.bump = ctx.bumps.lending_pool;
+    // Initialize obligation if needed
+    initialize_obligation(obligation, ctx.accounts.borrower.key(), lending_pool, ctx.bumps.obligation)?;
+
+    // Accrue interest and apply the repayment to the obligation debt
+    let (interest_payment, principal_payment) = apply_repayment(obligation, lending_pool, amount)?;
+    sync_rewards(lending_pool, obligation, ctx.accounts.borrower_receipt_account.amount)?;
+    let repay_amount = interest_payment + principal_payment;
+
+    // Interest is paid into the pool vault, funding lender yield and protocol reserves
+    if interest_payment > 0 {
+        msg!("Transferring interest to pool vault...");
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.borrower_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.lending_pool_vault.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            interest_payment,
+            ctx.accounts.mint_account.decimals,
+        )?;
     }
 
-    // Initialize borrower position if needed
-    if borrower_position.last_update_slot == 0 {
-        borrower_position.borrower = ctx.accounts.borrower.key();
-        borrower_position.amount_borrowed = 0;
-        borrower_position.amount_supplied = 0;
-        borrower_position.accumulated_interest = 0;
-        borrower_position.last_update_slot = clock.slot;
-        borrower_position.bump = ctx.bumps.borrower_position;
+    // Principal was minted on borrow, burn it back
+    if principal_payment > 0 {
+        msg!("Burning principal from borrower...");
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    from: ctx.accounts.borrower_token_account.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            principal_payment,
+        )?;
     }
 
-    // Update pool interest rates
-    crate::instructions::lending::utils::update_lending_pool(lending_pool)?;
-
-    // Update borrower position interest
-    let interest = crate::instructions::lending::utils::calculate_borrower_interest(
-        borrower_position,
-        lending_pool,
-    )?;
-    borrower_position.accumulated_interest = interest;
-    borrower_position.last_update_slot = clock.slot;
-
-    // Calculate total debt
-    let total_debt = borrower_position.amount_borrowed + borrower_position.accumulated_interest;
-    msg!("Borrower Debt - Principal: {:.9}, Interest: {:.9}, Total: {:.9}",
-         borrower_position.amount_borrowed as f64 / 1e9,
-         borrower_position.accumulated_interest as f64 / 1e9,
-         total_debt as f64 / 1e9);
-    
-    let repay_amount = if amount > total_debt {
-        total_debt
-    } else {
-        amount
-    };
-    
-    msg!("Repay Amount (capped at total debt): {:.9}", repay_amount as f64 / 1e9);
-
-    // First pay accumulated interest, then principal
-    let interest_payment = if repay_amount > borrower_position.accumulated_interest {
-        borrower_position.accumulated_interest
-    } else {
-        repay_amount
-    };
-    let principal_payment = repay_amount - interest_payment;
-    
-    msg!("Repayment Breakdown - Interest: {:.9}, Principal: {:.9}",
-         interest_payment as f64 / 1e9,
-         principal_payment as f64 / 1e9);
-
-    // Update amounts
-    let previous_borrowed = borrower_position.amount_borrowed;
-    let previous_interest = borrower_position.accumulated_interest;
-    borrower_position.accumulated_interest -= interest_payment;
-    borrower_position.amount_borrowed -= principal_payment;
-    lending_pool.total_borrowed -= principal_payment;
-
-    msg!("Borrower Position Updated - Previous Borrowed: {:.9}, New Borrowed: {:.9}",
-         previous_borrowed as f64 / 1e9,
-         borrower_position.amount_borrowed as f64 / 1e9);
-    msg!("Borrower Interest Updated - Previous: {:.9}, New: {:.9}",
-         previous_interest as f64 / 1e9,
-         borrower_position.accumulated_interest as f64 / 1e9);
-
-    // Update utilization and rates
-    let (supply_rate, borrow_rate) =
-        crate::instructions::lending::utils::calculate_interest_rates(
-            lending_pool.total_supplied,
-            lending_pool.total_borrowed,
+    // Release zGOLD collateral once the debt is fully repaid
+    let obligation = &ctx.accounts.obligation;
+    let remaining_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    if remaining_debt == 0 && ctx.accounts.borrower_receipt_account.is_frozen() {
+        msg!("Thawing zGOLD collateral...");
+        thaw_receipt_account(
+            &ctx.accounts.borrower_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
         )?;
-    lending_pool.supply_rate = supply_rate;
-    lending_pool.borrow_rate = borrow_rate;
-    lending_pool.utilization_rate = if lending_pool.total_supplied > 0 {
-        (lending_pool.total_borrowed * crate::BASIS_POINTS) / lending_pool.total_supplied
-    } else {
-        0
-    };
-    
-    msg!("Updated Pool Rates - Supply: {} bp, Borrow: {} bp, Utilization: {} bp",
-         lending_pool.supply_rate,
-         lending_pool.borrow_rate,
-         lending_pool.utilization_rate);
-
-    // Burn tokens from borrower
-    msg!("Burning tokens from borrower...");
-    burn(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.mint_account.to_account_info(),
-                from: ctx.accounts.borrower_token_account.to_account_info(),
-                authority: ctx.accounts.borrower.to_account_info(),
-            },
-        ),
-        repay_amount,
-    )?;
-
-    let remaining_debt = borrower_position.amount_borrowed + borrower_position.accumulated_interest;
+    }
+
+    let lending_pool = &ctx.accounts.lending_pool;
     msg!("=== REPAY COMPLETE ===");
     msg!("Repaid: {:.9} tokens (Interest: {:.9}, Principal: {:.9})",
          repay_amount as f64 / 1e9,
@@ -172,10 +178,38 @@ pub fn process_repay_tokens(ctx: Context<RepayTokens>, amount: u64) -> Result<()
          principal_payment as f64 / 1e9);
     msg!("Remaining Debt: {:.9} (Principal: {:.9}, Interest: {:.9})",
          remaining_debt as f64 / 1e9,
-         borrower_position.amount_borrowed as f64 / 1e9,
-         borrower_position.accumulated_interest as f64 / 1e9);
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9);
     msg!("Total Pool Borrowed: {:.9}", lending_pool.total_borrowed as f64 / 1e9);
 
+    // Queued withdrawals are filled as repayments free liquidity
+    if let Some(withdrawal_request) = ctx.accounts.withdrawal_request.as_mut() {
+        let (Some(request_owner), Some(request_token_account), Some(withdrawal_queue)) = (
+            &ctx.accounts.withdrawal_request_owner,
+            &ctx.accounts.withdrawal_request_token_account,
+            &ctx.accounts.withdrawal_queue,
+        ) else {
+            return err!(CustomError::WithdrawalRequestAccountsMissing);
+        };
+        require_keys_eq!(withdrawal_request.owner, request_owner.key());
+        require_keys_eq!(request_token_account.owner, request_owner.key());
+        require_keys_eq!(request_token_account.mint, ctx.accounts.mint_account.key());
+
+        ctx.accounts.lending_pool_vault.reload()?;
+        fill_queued_withdrawal(
+            &mut ctx.accounts.lending_pool,
+            withdrawal_request,
+            request_owner,
+            request_token_account,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.receipt_mint,
+            withdrawal_queue,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
     Ok(())
 }
 
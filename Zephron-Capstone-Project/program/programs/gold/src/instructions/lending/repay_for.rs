@@ -0,0 +1,214 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        apply_repayment, fill_queued_withdrawal, record_rate_history, sync_rewards,
+        thaw_receipt_account,
+    },
+    Config, LendingPool, Obligation, RateHistory, WithdrawalRequest, SEED_CONFIG_ACCOUNT,
+    SEED_LENDING_POOL, SEED_OBLIGATION, SEED_RATE_HISTORY, SEED_WITHDRAWAL_QUEUE,
+    SEED_WITHDRAWAL_REQUEST,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+};
+
+#[derive(Accounts)]
+pub struct RepayTokensFor<'info> {
+    pub payer: Signer<'info>,
+
+    pub owner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = owner,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint_account,
+        token::authority = payer,
+        token::token_program = token_program
+    )]
+    pub payer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Head of the withdrawal queue, filled from the liquidity this repayment frees when passed
+    #[account(
+        mut,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_head.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Option<Account<'info, WithdrawalRequest>>,
+    #[account(mut)]
+    pub withdrawal_request_owner: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub withdrawal_request_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub withdrawal_queue: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Repay another wallet's obligation, the payer's GOLD covers the debt of the owner
+pub fn process_repay_tokens_for(ctx: Context<RepayTokensFor>, amount: u64) -> Result<()> {
+    msg!("Instruction: RepayTokensFor");
+
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    msg!("Repay Amount: {:.9}, Payer: {}, Owner: {}",
+         amount as f64 / 1e9,
+         ctx.accounts.payer.key(),
+         ctx.accounts.owner.key());
+
+    // Accrue interest and apply the repayment to the obligation debt
+    let (interest_payment, principal_payment) = apply_repayment(obligation, lending_pool, amount)?;
+    sync_rewards(lending_pool, obligation, ctx.accounts.owner_receipt_account.amount)?;
+    let repay_amount = interest_payment + principal_payment;
+
+    // Interest is paid into the pool vault, funding lender yield and protocol reserves
+    if interest_payment > 0 {
+        msg!("Transferring interest from payer to pool vault...");
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.payer_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.lending_pool_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            interest_payment,
+            ctx.accounts.mint_account.decimals,
+        )?;
+    }
+
+    // Principal was minted on borrow, burn it back from the payer
+    if principal_payment > 0 {
+        msg!("Burning principal from payer...");
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    from: ctx.accounts.payer_token_account.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            principal_payment,
+        )?;
+    }
+
+    // Release the owner's zGOLD collateral once the debt is fully repaid
+    let obligation = &ctx.accounts.obligation;
+    let remaining_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    if remaining_debt == 0 && ctx.accounts.owner_receipt_account.is_frozen() {
+        msg!("Thawing zGOLD collateral...");
+        thaw_receipt_account(
+            &ctx.accounts.owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    let lending_pool = &ctx.accounts.lending_pool;
+    msg!("=== REPAY FOR COMPLETE ===");
+    msg!("Repaid: {:.9} tokens (Interest: {:.9}, Principal: {:.9})",
+         repay_amount as f64 / 1e9,
+         interest_payment as f64 / 1e9,
+         principal_payment as f64 / 1e9);
+    msg!("Remaining Debt: {:.9} (Principal: {:.9}, Interest: {:.9})",
+         remaining_debt as f64 / 1e9,
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9);
+    msg!("Total Pool Borrowed: {:.9}", lending_pool.total_borrowed as f64 / 1e9);
+
+    // Queued withdrawals are filled as repayments free liquidity
+    if let Some(withdrawal_request) = ctx.accounts.withdrawal_request.as_mut() {
+        let (Some(request_owner), Some(request_token_account), Some(withdrawal_queue)) = (
+            &ctx.accounts.withdrawal_request_owner,
+            &ctx.accounts.withdrawal_request_token_account,
+            &ctx.accounts.withdrawal_queue,
+        ) else {
+            return err!(CustomError::WithdrawalRequestAccountsMissing);
+        };
+        require_keys_eq!(withdrawal_request.owner, request_owner.key());
+        require_keys_eq!(request_token_account.owner, request_owner.key());
+        require_keys_eq!(request_token_account.mint, ctx.accounts.mint_account.key());
+
+        ctx.accounts.lending_pool_vault.reload()?;
+        fill_queued_withdrawal(
+            &mut ctx.accounts.lending_pool,
+            withdrawal_request,
+            request_owner,
+            request_token_account,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.receipt_mint,
+            withdrawal_queue,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
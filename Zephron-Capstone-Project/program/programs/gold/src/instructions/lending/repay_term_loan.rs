@@ -0,0 +1,234 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        accrue_to_lenders, book_term_loan_interest, close_receipt_escrow, fill_queued_withdrawal,
+        record_rate_history, release_escrowed_receipt, term_loan_interest, update_lending_pool,
+        update_pool_rates,
+    },
+    Config, LendingPool, RateHistory, TermLoan, WithdrawalRequest, BASIS_POINTS,
+    SEED_CONFIG_ACCOUNT, SEED_LENDING_POOL, SEED_RATE_HISTORY, SEED_TERM_LOAN,
+    SEED_TERM_LOAN_ESCROW, SEED_WITHDRAWAL_QUEUE, SEED_WITHDRAWAL_REQUEST,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct RepayTermLoan<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CONFIG_ACCOUNT],
+        bump = config_account.bump,
+        has_one = mint_account
+    )]
+    pub config_account: Box<Account<'info, Config>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        close = borrower,
+        seeds = [
+            SEED_TERM_LOAN,
+            lending_pool.key().as_ref(),
+            borrower.key().as_ref(),
+            term_loan.loan_id.to_le_bytes().as_ref()
+        ],
+        bump = term_loan.bump,
+    )]
+    pub term_loan: Account<'info, TermLoan>,
+
+    #[account(mut)]
+    pub mint_account: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = borrower,
+        associated_token::token_program = token_program
+    )]
+    pub borrower_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_TERM_LOAN_ESCROW, term_loan.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub term_loan_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
+        bump,
+        token::mint = mint_account,
+        token::authority = lending_pool,
+    )]
+    pub lending_pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // Head of the withdrawal queue, filled from the liquidity this repayment frees when passed
+    #[account(
+        mut,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_head.to_le_bytes().as_ref()
+        ],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Option<Account<'info, WithdrawalRequest>>,
+    #[account(mut)]
+    pub withdrawal_request_owner: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub withdrawal_request_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+    )]
+    pub withdrawal_queue: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+// Repay a term loan in full, before maturity an early repayment fee on the principal applies
+pub fn process_repay_term_loan(ctx: Context<RepayTermLoan>) -> Result<()> {
+    msg!("Instruction: RepayTermLoan");
+
+    let now = Clock::get()?.unix_timestamp;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let term_loan = &ctx.accounts.term_loan;
+    update_lending_pool(lending_pool)?;
+
+    let interest = term_loan_interest(term_loan, now);
+    let early_repayment_fee = if now < term_loan.maturity_timestamp {
+        (term_loan.principal as u128 * lending_pool.early_repayment_fee as u128)
+            .div_ceil(BASIS_POINTS as u128) as u64
+    } else {
+        0
+    };
+
+    msg!("Term Loan #{} - Principal: {:.9}, Interest: {:.9}, Early Repayment Fee: {:.9}",
+         term_loan.loan_id,
+         term_loan.principal as f64 / 1e9,
+         interest as f64 / 1e9,
+         early_repayment_fee as f64 / 1e9);
+
+    // Fixed interest is booked as it is paid, the early repayment fee compensates lenders for the
+    // fixed rate they no longer earn
+    book_term_loan_interest(lending_pool, interest);
+    accrue_to_lenders(lending_pool, early_repayment_fee);
+    lending_pool.total_borrowed -= term_loan.principal;
+    lending_pool.term_borrowed -= term_loan.principal;
+    update_pool_rates(lending_pool)?;
+
+    // Interest and fee are paid into the pool vault
+    if interest + early_repayment_fee > 0 {
+        msg!("Transferring interest and fees to pool vault...");
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.borrower_token_account.to_account_info(),
+                    mint: ctx.accounts.mint_account.to_account_info(),
+                    to: ctx.accounts.lending_pool_vault.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            interest + early_repayment_fee,
+            ctx.accounts.mint_account.decimals,
+        )?;
+    }
+
+    // Principal was minted on borrow, burn it back
+    msg!("Burning principal from borrower...");
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.mint_account.to_account_info(),
+                from: ctx.accounts.borrower_token_account.to_account_info(),
+                authority: ctx.accounts.borrower.to_account_info(),
+            },
+        ),
+        ctx.accounts.term_loan.principal,
+    )?;
+
+    // Return the escrowed zGOLD collateral
+    msg!("Releasing {:.9} zGOLD collateral...", ctx.accounts.term_loan.collateral_receipt as f64 / 1e9);
+    release_escrowed_receipt(
+        &ctx.accounts.term_loan_escrow,
+        &ctx.accounts.borrower_receipt_account,
+        &ctx.accounts.receipt_mint,
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.token_program,
+        ctx.accounts.term_loan.collateral_receipt,
+    )?;
+    close_receipt_escrow(
+        &ctx.accounts.term_loan_escrow,
+        ctx.accounts.borrower.to_account_info(),
+        &ctx.accounts.lending_pool,
+        &ctx.accounts.token_program,
+    )?;
+
+    // Queued withdrawals are filled from the interest and fee this repayment paid in
+    if let Some(withdrawal_request) = ctx.accounts.withdrawal_request.as_mut() {
+        let (Some(request_owner), Some(request_token_account), Some(withdrawal_queue)) = (
+            &ctx.accounts.withdrawal_request_owner,
+            &ctx.accounts.withdrawal_request_token_account,
+            &ctx.accounts.withdrawal_queue,
+        ) else {
+            return err!(CustomError::WithdrawalRequestAccountsMissing);
+        };
+        require_keys_eq!(withdrawal_request.owner, request_owner.key());
+        require_keys_eq!(request_token_account.owner, request_owner.key());
+        require_keys_eq!(request_token_account.mint, ctx.accounts.mint_account.key());
+
+        ctx.accounts.lending_pool_vault.reload()?;
+        fill_queued_withdrawal(
+            &mut ctx.accounts.lending_pool,
+            withdrawal_request,
+            request_owner,
+            request_token_account,
+            &ctx.accounts.mint_account,
+            &ctx.accounts.receipt_mint,
+            withdrawal_queue,
+            &ctx.accounts.lending_pool_vault,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("=== TERM REPAY COMPLETE ===");
+    msg!("Total Pool Borrowed: {:.9}", ctx.accounts.lending_pool.total_borrowed as f64 / 1e9);
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::{
+    instructions::lending::utils::{
+        accrue_obligation_interest, check_refreshed, freeze_receipt_account, record_rate_history,
+        refresh_obligation_collateral, sync_rewards, thaw_receipt_account,
+        tokens_to_receipt_round_up, update_lending_pool,
+    },
+    LendingPool, Obligation, RateHistory, WithdrawalRequest, SEED_LENDING_POOL, SEED_OBLIGATION,
+    SEED_RATE_HISTORY, SEED_WITHDRAWAL_QUEUE, SEED_WITHDRAWAL_REQUEST,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
+    )]
+    pub rate_history: AccountLoader<'info, RateHistory>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), lender.key().as_ref()],
+        bump = obligation.bump,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program
+    )]
+    pub lender_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = lender,
+        seeds = [SEED_WITHDRAWAL_QUEUE, receipt_mint.key().as_ref()],
+        bump,
+        token::mint = receipt_mint,
+        token::authority = lending_pool,
+        token::token_program = token_program
+    )]
+    pub withdrawal_queue: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [
+            SEED_WITHDRAWAL_REQUEST,
+            lending_pool.key().as_ref(),
+            lending_pool.withdrawal_queue_tail.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Queue a withdrawal when the pool lacks liquidity, zGOLD is escrowed and keeps earning until filled
+pub fn process_request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
+    msg!("Instruction: RequestWithdrawal");
+    msg!("Request Amount: {:.9}", amount as f64 / 1e9);
+
+    let receipt_balance = ctx.accounts.lender_receipt_account.amount;
+    let lending_pool = &mut ctx.accounts.lending_pool;
+    let obligation = &mut ctx.accounts.obligation;
+
+    // Pools that require refresh only accept state refreshed earlier in this slot
+    check_refreshed(lending_pool, obligation)?;
+
+    update_lending_pool(lending_pool)?;
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
+    accrue_obligation_interest(obligation, lending_pool)?;
+
+    // Same balance rule as a direct withdrawal, zGOLD backing debt cannot be queued
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    let available_balance = obligation.collateral_value.saturating_sub(total_debt);
+    msg!("Available Balance: {:.9}, Debt: {:.9}",
+         available_balance as f64 / 1e9,
+         total_debt as f64 / 1e9);
+
+    require!(
+        amount <= available_balance,
+        crate::error::CustomError::InsufficientFunds
+    );
+
+    let receipt_to_escrow = tokens_to_receipt_round_up(amount, lending_pool).min(receipt_balance);
+    require!(receipt_to_escrow > 0, crate::error::CustomError::AmountTooSmall);
+
+    // Escrowed zGOLD leaves the obligation now, first from interest then from principal
+    let interest_withdrawal = amount.min(obligation.supply_interest);
+    obligation.amount_supplied = obligation
+        .amount_supplied
+        .saturating_sub(amount - interest_withdrawal);
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance - receipt_to_escrow);
+    sync_rewards(lending_pool, obligation, receipt_balance - receipt_to_escrow)?;
+
+    let withdrawal_request = &mut ctx.accounts.withdrawal_request;
+    withdrawal_request.owner = ctx.accounts.lender.key();
+    withdrawal_request.lending_pool = lending_pool.key();
+    withdrawal_request.id = lending_pool.withdrawal_queue_tail;
+    withdrawal_request.receipt_amount = receipt_to_escrow;
+    withdrawal_request.filled_amount = 0;
+    withdrawal_request.bump = ctx.bumps.withdrawal_request;
+
+    lending_pool.withdrawal_queue_tail += 1;
+    lending_pool.queued_receipt += receipt_to_escrow;
+
+    msg!("Withdrawal Request #{} - zGOLD Escrowed: {:.9}, Queue Head: {}, Queue Tail: {}",
+         withdrawal_request.id,
+         receipt_to_escrow as f64 / 1e9,
+         lending_pool.withdrawal_queue_head,
+         lending_pool.withdrawal_queue_tail);
+
+    // Move zGOLD into the queue escrow, thawing the account if it was frozen as loan collateral
+    let is_frozen = ctx.accounts.lender_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("Escrowing zGOLD in withdrawal queue...");
+    transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.lender_receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                to: ctx.accounts.withdrawal_queue.to_account_info(),
+                authority: ctx.accounts.lender.to_account_info(),
+            },
+        ),
+        receipt_to_escrow,
+        ctx.accounts.receipt_mint.decimals,
+    )?;
+
+    if is_frozen && total_debt > 0 {
+        freeze_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
+    Ok(())
+}
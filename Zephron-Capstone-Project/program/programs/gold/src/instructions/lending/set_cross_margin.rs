@@ -0,0 +1,131 @@
+use crate::{
+    error::CustomError,
+    instructions::lending::utils::{
+        calculate_borrower_interest, freeze_receipt_account, refresh_obligation_collateral,
+        thaw_receipt_account,
+    },
+    Collateral, LendingPool, Obligation, BASIS_POINTS, SEED_COLLATERAL_ACCOUNT, SEED_LENDING_POOL,
+    SEED_OBLIGATION,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{approve, revoke, Approve, Mint, Revoke, Token2022, TokenAccount};
+
+#[derive(Accounts)]
+pub struct SetCrossMargin<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_LENDING_POOL],
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
+    )]
+    pub lending_pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), owner.key().as_ref()],
+        bump = obligation.bump,
+        has_one = owner,
+    )]
+    pub obligation: Account<'info, Obligation>,
+
+    #[account(
+        mut,
+        seeds = [SEED_COLLATERAL_ACCOUNT, owner.key().as_ref()],
+        bump = collateral_account.bump,
+    )]
+    pub collateral_account: Account<'info, Collateral>,
+
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+// Opt in or out of cross margin, enabling delegates the owner's zGOLD to the pool so liquidations can seize it
+pub fn process_set_cross_margin(ctx: Context<SetCrossMargin>, enabled: bool) -> Result<()> {
+    msg!("Instruction: SetCrossMargin");
+    msg!("Cross Margin: {} -> {}", ctx.accounts.obligation.cross_margin, enabled);
+
+    // Leaving cross margin requires the zGOLD alone to back the lending debt again
+    if !enabled {
+        let lending_pool = &ctx.accounts.lending_pool;
+        let obligation = &mut ctx.accounts.obligation;
+        refresh_obligation_collateral(
+            obligation,
+            lending_pool,
+            ctx.accounts.owner_receipt_account.amount,
+        );
+
+        let total_borrowable = (obligation.collateral_value * lending_pool.collateral_factor) / BASIS_POINTS;
+        let total_debt = obligation.amount_borrowed + calculate_borrower_interest(obligation, lending_pool)?;
+        msg!("Lending Only Check - Total Borrowable: {:.9}, Debt: {:.9}",
+             total_borrowable as f64 / 1e9,
+             total_debt as f64 / 1e9);
+        require!(
+            total_debt <= total_borrowable,
+            CustomError::InsufficientBorrowCapacity
+        );
+    }
+
+    // Lending debt keeps its zGOLD delegated to the pool so cover_bad_debt can still seize it
+    let obligation = &ctx.accounts.obligation;
+    let has_debt = obligation.amount_borrowed > 0 || obligation.borrow_interest > 0;
+
+    // Approve and revoke are rejected on frozen accounts, thaw around the delegate change
+    let is_frozen = ctx.accounts.owner_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(
+            &ctx.accounts.owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    if enabled {
+        approve(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Approve {
+                    to: ctx.accounts.owner_receipt_account.to_account_info(),
+                    delegate: ctx.accounts.lending_pool.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            u64::MAX,
+        )?;
+    } else if !has_debt {
+        revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Revoke {
+                source: ctx.accounts.owner_receipt_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        ))?;
+    }
+
+    if is_frozen {
+        freeze_receipt_account(
+            &ctx.accounts.owner_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    ctx.accounts.obligation.cross_margin = enabled;
+    ctx.accounts.collateral_account.cross_margin = enabled;
+
+    msg!("Cross Margin Updated - Obligation: {}, Collateral: {}",
+         ctx.accounts.obligation.key(),
+         ctx.accounts.collateral_account.key());
+    Ok(())
+}
@@ -1,13 +1,20 @@
 use crate::{
-    BorrowerPosition, LenderPosition, LendingPool, BASIS_POINTS, COLLATERAL_FACTOR,
-    MAX_UTILIZATION_RATE, SLOTS_PER_YEAR,
+    ClockSource, LendingPool, Obligation, ObligationReward, RateHistory, RateSnapshot, TermLoan,
+    WithdrawalRequest, BASIS_POINTS, INDEX_PRECISION, MAX_REWARD_MINTS, MAX_UTILIZATION_RATE, RATE_HISTORY_LENGTH,
+    SECONDS_PER_YEAR, SEED_LENDING_POOL, SLOTS_PER_YEAR,
 };
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    burn, close_account, freeze_account, thaw_account, transfer_checked, Burn, CloseAccount,
+    FreezeAccount, Mint, ThawAccount, Token2022, TokenAccount, TransferChecked,
+};
 
 /// Calculate interest rates based on utilization
 pub fn calculate_interest_rates(
     total_supplied: u64,
     total_borrowed: u64,
+    term_borrowed: u64,
+    reserve_factor: u64,
 ) -> Result<(u64, u64)> {
     if total_supplied == 0 {
         return Ok((0, 0));
@@ -15,7 +22,7 @@ pub fn calculate_interest_rates(
 
     let utilization_rate = (total_borrowed * BASIS_POINTS) / total_supplied;
 
-    // Simple interest rate model: supply rate = borrow rate * utilization * 0.9
+    // Simple interest rate model: supply rate = borrow rate * utilization * (1 - reserve factor)
     // Borrow rate increases with utilization
     let borrow_rate = if utilization_rate < MAX_UTILIZATION_RATE {
         // Base rate + utilization-based rate
@@ -27,19 +34,67 @@ pub fn calculate_interest_rates(
         2000 // 20% max borrow rate
     };
 
-    // Supply rate is 90% of borrow rate * utilization
-    let supply_rate = (borrow_rate * utilization_rate * 90) / (BASIS_POINTS * 100);
+    // Supply rate is borrow rate * utilization, less the share kept as protocol reserves. Term loans
+    // pay lenders their fixed interest on repayment, so only floating borrows count here.
+    let floating_utilization =
+        (total_borrowed.saturating_sub(term_borrowed) * BASIS_POINTS) / total_supplied;
+    let supply_rate = (borrow_rate * floating_utilization * (BASIS_POINTS - reserve_factor))
+        / (BASIS_POINTS * BASIS_POINTS);
 
     Ok((supply_rate, borrow_rate))
 }
 
+/// Time elapsed since the last update on the pool's clock source, with the matching periods per year
+pub fn accrual_elapsed(
+    clock_source: ClockSource,
+    last_update_slot: u64,
+    last_update_timestamp: i64,
+) -> Result<(u64, u64)> {
+    let clock = Clock::get()?;
+    Ok(match clock_source {
+        ClockSource::Slot => (clock.slot.saturating_sub(last_update_slot), SLOTS_PER_YEAR),
+        ClockSource::UnixTimestamp => (
+            clock.unix_timestamp.saturating_sub(last_update_timestamp).max(0) as u64,
+            SECONDS_PER_YEAR,
+        ),
+    })
+}
+
+/// Interest on an amount at an annual rate (basis points) over the elapsed periods
+pub fn accrued_interest(amount: u64, rate: u64, elapsed: u64, periods_per_year: u64) -> u128 {
+    (amount as u128 * rate as u128 * elapsed as u128)
+        / (BASIS_POINTS as u128 * periods_per_year as u128)
+}
+
 /// Update lending pool with current interest rates
 pub fn update_lending_pool(pool: &mut Account<LendingPool>) -> Result<()> {
     let clock = Clock::get()?;
-    let current_slot = clock.slot;
+    let (elapsed, periods_per_year) =
+        accrual_elapsed(pool.clock_source, pool.last_update_slot, pool.last_update_timestamp)?;
+
+    if elapsed > 0 {
+        // Grow the supply index and total supplied by the supply interest earned since the last update
+        let index_growth = (pool.supply_index * pool.supply_rate as u128 * elapsed as u128)
+            / (BASIS_POINTS as u128 * periods_per_year as u128);
+        let supply_interest =
+            accrued_interest(pool.total_supplied, pool.supply_rate, elapsed, periods_per_year);
+        pool.supply_index += index_growth;
+        pool.total_supplied += supply_interest as u64;
+
+        // Route the reserve factor share of floating borrow interest into protocol reserves,
+        // term loan interest is booked when the loan is repaid
+        let floating_borrowed = pool.total_borrowed.saturating_sub(pool.term_borrowed);
+        let borrow_interest =
+            accrued_interest(floating_borrowed, pool.borrow_rate, elapsed, periods_per_year);
+        let reserve_interest = borrow_interest * pool.reserve_factor as u128 / BASIS_POINTS as u128;
+        pool.protocol_reserves += reserve_interest as u64;
 
-    if pool.last_update_slot < current_slot {
-        let (supply_rate, borrow_rate) = calculate_interest_rates(pool.total_supplied, pool.total_borrowed)?;
+        let (supply_rate, borrow_rate) = calculate_interest_rates(
+            pool.total_supplied,
+            pool.total_borrowed,
+            pool.term_borrowed,
+            pool.reserve_factor,
+        )?;
         pool.supply_rate = supply_rate;
         pool.borrow_rate = borrow_rate;
         pool.utilization_rate = if pool.total_supplied > 0 {
@@ -47,69 +102,327 @@ pub fn update_lending_pool(pool: &mut Account<LendingPool>) -> Result<()> {
         } else {
             0
         };
-        pool.last_update_slot = current_slot;
+        // Both stamps move together so the clock source can be switched without a gap
+        pool.last_update_slot = clock.slot;
+        pool.last_update_timestamp = clock.unix_timestamp;
     }
 
     Ok(())
 }
 
-/// Calculate accumulated interest for a lender
-pub fn calculate_lender_interest(
-    lender_position: &Account<LenderPosition>,
+/// Recompute utilization and rates after total_supplied or total_borrowed changed
+pub fn update_pool_rates(pool: &mut Account<LendingPool>) -> Result<()> {
+    let (supply_rate, borrow_rate) = calculate_interest_rates(
+        pool.total_supplied,
+        pool.total_borrowed,
+        pool.term_borrowed,
+        pool.reserve_factor,
+    )?;
+    pool.supply_rate = supply_rate;
+    pool.borrow_rate = borrow_rate;
+    pool.utilization_rate = if pool.total_supplied > 0 {
+        (pool.total_borrowed * BASIS_POINTS) / pool.total_supplied
+    } else {
+        0
+    };
+    Ok(())
+}
+
+/// Append the pool state to the rate history, an entry holds until the next entry's timestamp
+pub fn record_rate_history(
+    rate_history: &AccountLoader<RateHistory>,
     pool: &Account<LendingPool>,
-) -> Result<u64> {
-    if lender_position.amount_supplied == 0 {
-        return Ok(0);
+) -> Result<()> {
+    let mut history = rate_history.load_mut()?;
+    let snapshot = RateSnapshot {
+        timestamp: pool.last_update_timestamp,
+        utilization_rate: pool.utilization_rate,
+        supply_rate: pool.supply_rate,
+        borrow_rate: pool.borrow_rate,
+        total_supplied: pool.total_supplied,
+        total_borrowed: pool.total_borrowed,
+    };
+
+    // Several updates within one second collapse into the latest state for that second
+    let head = history.head as usize;
+    if history.count > 0 && history.snapshots[head].timestamp == snapshot.timestamp {
+        history.snapshots[head] = snapshot;
+        return Ok(());
     }
 
-    let clock = Clock::get()?;
-    let slots_elapsed = clock.slot.saturating_sub(lender_position.last_update_slot);
+    let next = if history.count == 0 { 0 } else { (head + 1) % RATE_HISTORY_LENGTH };
+    history.snapshots[next] = snapshot;
+    history.head = next as u64;
+    history.count = (history.count + 1).min(RATE_HISTORY_LENGTH as u64);
+    Ok(())
+}
+
+/// Distribute GOLD paid into the vault to lenders by growing the supply index
+pub fn accrue_to_lenders(pool: &mut Account<LendingPool>, amount: u64) {
+    if pool.total_supplied > 0 {
+        pool.supply_index += pool.supply_index * amount as u128 / pool.total_supplied as u128;
+    }
+    pool.total_supplied += amount;
+}
+
+/// Book fixed term loan interest paid into the vault, the reserve factor share goes to protocol
+/// reserves and the rest to lenders
+pub fn book_term_loan_interest(pool: &mut Account<LendingPool>, interest: u64) {
+    let reserve_interest =
+        (interest as u128 * pool.reserve_factor as u128 / BASIS_POINTS as u128) as u64;
+    pool.protocol_reserves += reserve_interest;
+    accrue_to_lenders(pool, interest - reserve_interest);
+}
+
+/// Write bad debt off against lenders by shrinking the supply index and total supplied
+pub fn socialize_loss(pool: &mut Account<LendingPool>, loss: u64) {
+    if loss == 0 || pool.total_supplied == 0 {
+        return;
+    }
+    let loss = loss.min(pool.total_supplied);
+    pool.supply_index -= pool.supply_index * loss as u128 / pool.total_supplied as u128;
+    pool.total_supplied -= loss;
+}
+
+/// Convert GOLD amount to zGOLD receipt amount at the current supply index (rounded down)
+pub fn tokens_to_receipt(amount: u64, pool: &Account<LendingPool>) -> u64 {
+    ((amount as u128 * INDEX_PRECISION) / pool.supply_index) as u64
+}
+
+/// Convert GOLD amount to zGOLD receipt amount at the current supply index (rounded up)
+pub fn tokens_to_receipt_round_up(amount: u64, pool: &Account<LendingPool>) -> u64 {
+    ((amount as u128 * INDEX_PRECISION).div_ceil(pool.supply_index)) as u64
+}
+
+/// Convert zGOLD receipt amount to GOLD amount at the current supply index (rounded down)
+pub fn receipt_to_tokens(receipt_amount: u64, pool: &Account<LendingPool>) -> u64 {
+    ((receipt_amount as u128 * pool.supply_index) / INDEX_PRECISION) as u64
+}
+
+/// GOLD that can leave the pool vault, bounded by the vault balance net of reserves and by unborrowed supply
+pub fn available_liquidity(pool: &Account<LendingPool>, vault_amount: u64) -> u64 {
+    vault_amount
+        .saturating_sub(pool.protocol_reserves)
+        .min(pool.total_supplied.saturating_sub(pool.total_borrowed))
+}
+
+/// Protocol reserves that can leave the pool vault without touching GOLD owed to lenders.
+/// Borrows are minted rather than paid from the vault, so the vault backs all of total_supplied,
+/// and the value of queued withdrawal requests is held back as well.
+pub fn withdrawable_reserves(pool: &Account<LendingPool>, vault_amount: u64) -> u64 {
+    let queued_value = receipt_to_tokens(pool.queued_receipt, pool);
+    let backing = vault_amount
+        .saturating_sub(pool.total_supplied)
+        .saturating_sub(queued_value);
+    pool.protocol_reserves.min(backing)
+}
+
+/// Pay out the withdrawal request at the head of the queue from available liquidity, partially if needed.
+/// A request whose zGOLD rounds to no GOLD (dust left by socialize_loss) is burned and closed.
+/// Returns false when there was no liquidity to fill a request with value.
+#[allow(clippy::too_many_arguments)]
+pub fn fill_queued_withdrawal<'info>(
+    lending_pool: &mut Account<'info, LendingPool>,
+    withdrawal_request: &mut Account<'info, WithdrawalRequest>,
+    owner: &SystemAccount<'info>,
+    owner_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint_account: &InterfaceAccount<'info, Mint>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    withdrawal_queue: &InterfaceAccount<'info, TokenAccount>,
+    lending_pool_vault: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Program<'info, Token2022>,
+) -> Result<bool> {
+    let request_value = receipt_to_tokens(withdrawal_request.receipt_amount, lending_pool);
+    let liquidity = available_liquidity(lending_pool, lending_pool_vault.amount);
+    let fill_amount = request_value.min(liquidity);
+
+    msg!("Withdrawal Request #{} - zGOLD: {:.9}, Value: {:.9}, Liquidity: {:.9}",
+         withdrawal_request.id,
+         withdrawal_request.receipt_amount as f64 / 1e9,
+         request_value as f64 / 1e9,
+         liquidity as f64 / 1e9);
 
-    if slots_elapsed == 0 {
-        return Ok(lender_position.accumulated_interest);
+    if request_value > 0 && fill_amount == 0 {
+        return Ok(false);
     }
 
-    // Calculate interest: principal * rate * time / slots_per_year
-    let interest = (lender_position.amount_supplied as u128
-        * pool.supply_rate as u128
-        * slots_elapsed as u128)
-        / (BASIS_POINTS as u128 * SLOTS_PER_YEAR as u128);
+    // Partial fills burn zGOLD rounded up in favour of the pool
+    let receipt_to_burn = if fill_amount == request_value {
+        withdrawal_request.receipt_amount
+    } else {
+        tokens_to_receipt_round_up(fill_amount, lending_pool).min(withdrawal_request.receipt_amount)
+    };
+
+    withdrawal_request.receipt_amount -= receipt_to_burn;
+    withdrawal_request.filled_amount += fill_amount;
+    lending_pool.queued_receipt -= receipt_to_burn;
+    lending_pool.total_supplied -= fill_amount;
+    update_pool_rates(lending_pool)?;
+
+    let fully_filled = withdrawal_request.receipt_amount == 0;
+    if fully_filled {
+        lending_pool.withdrawal_queue_head += 1;
+    }
+
+    msg!("Filled: {:.9} GOLD, zGOLD Burned: {:.9}, Fully Filled: {}",
+         fill_amount as f64 / 1e9,
+         receipt_to_burn as f64 / 1e9,
+         fully_filled);
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+
+    burn(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Burn {
+                mint: receipt_mint.to_account_info(),
+                from: withdrawal_queue.to_account_info(),
+                authority: lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        receipt_to_burn,
+    )?;
 
-    Ok(lender_position.accumulated_interest + interest as u64)
+    if fill_amount > 0 {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: lending_pool_vault.to_account_info(),
+                    mint: mint_account.to_account_info(),
+                    to: owner_token_account.to_account_info(),
+                    authority: lending_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            fill_amount,
+            mint_account.decimals,
+        )?;
+    }
+
+    // Return the request rent to its owner once it leaves the queue
+    if fully_filled {
+        withdrawal_request.close(owner.to_account_info())?;
+    }
+
+    msg!("Queue Head: {}, Queue Tail: {}",
+         lending_pool.withdrawal_queue_head,
+         lending_pool.withdrawal_queue_tail);
+    Ok(true)
+}
+
+/// Fixed-rate interest owed on a term loan from origination to now, it keeps accruing past maturity
+pub fn term_loan_interest(loan: &Account<TermLoan>, now: i64) -> u64 {
+    let elapsed = now.saturating_sub(loan.start_timestamp).max(0) as u64;
+    accrued_interest(loan.principal, loan.fixed_rate, elapsed, SECONDS_PER_YEAR) as u64
+}
+
+/// Set up a newly created obligation for its owner
+pub fn initialize_obligation(
+    obligation: &mut Account<Obligation>,
+    owner: Pubkey,
+    pool: &Account<LendingPool>,
+    bump: u8,
+) -> Result<()> {
+    if !obligation.is_initialized {
+        msg!("Initializing obligation...");
+        obligation.owner = owner;
+        obligation.lending_pool = pool.key();
+        obligation.amount_supplied = 0;
+        obligation.supply_interest = 0;
+        obligation.collateral_value = 0;
+        obligation.supply_index = pool.supply_index;
+        obligation.amount_borrowed = 0;
+        obligation.borrow_interest = 0;
+        let clock = Clock::get()?;
+        obligation.last_update_slot = clock.slot;
+        obligation.last_update_timestamp = clock.unix_timestamp;
+        obligation.last_refresh_slot = 0;
+        obligation.reward_supply_basis = 0;
+        obligation.reward_borrow_basis = 0;
+        obligation.rewards = [ObligationReward::default(); MAX_REWARD_MINTS];
+        obligation.bump = bump;
+        obligation.cross_margin = false;
+        obligation.is_initialized = true;
+    }
+    Ok(())
 }
 
 /// Calculate accumulated interest for a borrower
 pub fn calculate_borrower_interest(
-    borrower_position: &Account<BorrowerPosition>,
+    obligation: &Account<Obligation>,
     pool: &Account<LendingPool>,
 ) -> Result<u64> {
-    if borrower_position.amount_borrowed == 0 {
+    if obligation.amount_borrowed == 0 {
         return Ok(0);
     }
 
-    let clock = Clock::get()?;
-    let slots_elapsed = clock.slot.saturating_sub(borrower_position.last_update_slot);
+    let (elapsed, periods_per_year) = accrual_elapsed(
+        pool.clock_source,
+        obligation.last_update_slot,
+        obligation.last_update_timestamp,
+    )?;
 
-    if slots_elapsed == 0 {
-        return Ok(borrower_position.accumulated_interest);
+    if elapsed == 0 {
+        return Ok(obligation.borrow_interest);
     }
 
-    // Calculate interest: principal * rate * time / slots_per_year
-    let interest = (borrower_position.amount_borrowed as u128
-        * pool.borrow_rate as u128
-        * slots_elapsed as u128)
-        / (BASIS_POINTS as u128 * SLOTS_PER_YEAR as u128);
+    // Calculate interest: principal * rate * time / periods_per_year
+    let interest =
+        accrued_interest(obligation.amount_borrowed, pool.borrow_rate, elapsed, periods_per_year);
+
+    Ok(obligation.borrow_interest + interest as u64)
+}
+
+/// Accrue borrow interest on an obligation up to the current clock
+pub fn accrue_obligation_interest(
+    obligation: &mut Account<Obligation>,
+    pool: &Account<LendingPool>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    obligation.borrow_interest = calculate_borrower_interest(obligation, pool)?;
+    obligation.last_update_slot = clock.slot;
+    obligation.last_update_timestamp = clock.unix_timestamp;
+    Ok(())
+}
 
-    Ok(borrower_position.accumulated_interest + interest as u64)
+/// When the pool requires it, check that the pool and obligation were refreshed in this slot
+pub fn check_refreshed(pool: &Account<LendingPool>, obligation: &Account<Obligation>) -> Result<()> {
+    if pool.require_refresh {
+        let current_slot = Clock::get()?.slot;
+        require!(
+            pool.last_refresh_slot == current_slot,
+            crate::error::CustomError::LendingPoolStale
+        );
+        require!(
+            obligation.last_refresh_slot == current_slot,
+            crate::error::CustomError::ObligationStale
+        );
+    }
+    Ok(())
+}
+
+/// Refresh obligation collateral and supply interest from the owner's zGOLD balance
+pub fn refresh_obligation_collateral(
+    obligation: &mut Account<Obligation>,
+    pool: &Account<LendingPool>,
+    receipt_balance: u64,
+) {
+    let receipt_value = receipt_to_tokens(receipt_balance, pool);
+    obligation.collateral_value = receipt_value;
+    obligation.supply_interest = receipt_value.saturating_sub(obligation.amount_supplied);
+    obligation.supply_index = pool.supply_index;
 }
 
 /// Check if borrower can borrow the requested amount
 pub fn check_borrow_capacity(
-    borrower_position: &Account<BorrowerPosition>,
+    obligation: &Account<Obligation>,
+    pool: &Account<LendingPool>,
     requested_amount: u64,
 ) -> Result<()> {
-    let total_borrowable = (borrower_position.amount_supplied * COLLATERAL_FACTOR) / BASIS_POINTS;
-    let total_debt = borrower_position.amount_borrowed + borrower_position.accumulated_interest;
+    let total_borrowable = (obligation.collateral_value * pool.collateral_factor) / BASIS_POINTS;
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
 
     require!(
         total_debt + requested_amount <= total_borrowable,
@@ -119,3 +432,229 @@ pub fn check_borrow_capacity(
     Ok(())
 }
 
+/// Accrue interest and apply a repayment to an obligation, paying accumulated interest first
+/// Returns the (interest, principal) portions, capped at the outstanding debt
+pub fn apply_repayment(
+    obligation: &mut Account<Obligation>,
+    pool: &mut Account<LendingPool>,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    // Update pool interest rates
+    update_lending_pool(pool)?;
+
+    // Update obligation borrow interest
+    accrue_obligation_interest(obligation, pool)?;
+
+    // Calculate total debt
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    msg!("Borrower Debt - Principal: {:.9}, Interest: {:.9}, Total: {:.9}",
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9,
+         total_debt as f64 / 1e9);
+
+    let repay_amount = if amount > total_debt {
+        total_debt
+    } else {
+        amount
+    };
+
+    msg!("Repay Amount (capped at total debt): {:.9}", repay_amount as f64 / 1e9);
+
+    // First pay accumulated interest, then principal
+    let interest_payment = if repay_amount > obligation.borrow_interest {
+        obligation.borrow_interest
+    } else {
+        repay_amount
+    };
+    let principal_payment = repay_amount - interest_payment;
+
+    msg!("Repayment Breakdown - Interest: {:.9}, Principal: {:.9}",
+         interest_payment as f64 / 1e9,
+         principal_payment as f64 / 1e9);
+
+    // Update amounts
+    let previous_borrowed = obligation.amount_borrowed;
+    let previous_interest = obligation.borrow_interest;
+    obligation.borrow_interest -= interest_payment;
+    obligation.amount_borrowed -= principal_payment;
+    pool.total_borrowed -= principal_payment;
+
+    msg!("Obligation Updated - Previous Borrowed: {:.9}, New Borrowed: {:.9}",
+         previous_borrowed as f64 / 1e9,
+         obligation.amount_borrowed as f64 / 1e9);
+    msg!("Obligation Interest Updated - Previous: {:.9}, New: {:.9}",
+         previous_interest as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9);
+
+    // Update utilization and rates
+    update_pool_rates(pool)?;
+
+    msg!("Updated Pool Rates - Supply: {} bp, Borrow: {} bp, Utilization: {} bp",
+         pool.supply_rate,
+         pool.borrow_rate,
+         pool.utilization_rate);
+
+    Ok((interest_payment, principal_payment))
+}
+
+/// Accrue liquidity mining reward indexes for every active reward slot up to now
+pub fn accrue_pool_rewards(pool: &mut Account<LendingPool>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let supply_basis = pool.reward_supply_basis;
+    let borrow_basis = pool.reward_borrow_basis;
+
+    for reward in pool.rewards.iter_mut() {
+        if reward.reward_mint == Pubkey::default() {
+            continue;
+        }
+
+        let accrue_until = now.min(reward.end_timestamp);
+        let elapsed = accrue_until.saturating_sub(reward.last_update_timestamp).max(0) as u128;
+        if elapsed > 0 {
+            // Emissions while nobody holds basis stay unallocated in the reward vault
+            if supply_basis > 0 {
+                let emitted = reward.supply_emission_rate as u128 * elapsed * INDEX_PRECISION;
+                reward.supply_index += emitted / supply_basis as u128;
+            }
+            if borrow_basis > 0 {
+                let emitted = reward.borrow_emission_rate as u128 * elapsed * INDEX_PRECISION;
+                reward.borrow_index += emitted / borrow_basis as u128;
+            }
+        }
+        reward.last_update_timestamp = reward.last_update_timestamp.max(accrue_until);
+    }
+
+    Ok(())
+}
+
+/// Credit an obligation with rewards earned on its previous basis, then move it to the new basis.
+/// zGOLD moved to another wallet keeps counting for the sender until refresh_position resyncs it.
+pub fn sync_rewards(
+    pool: &mut Account<LendingPool>,
+    obligation: &mut Account<Obligation>,
+    receipt_balance: u64,
+) -> Result<()> {
+    accrue_pool_rewards(pool)?;
+
+    let supply_basis = obligation.reward_supply_basis;
+    let borrow_basis = obligation.reward_borrow_basis;
+    for (reward, position) in pool.rewards.iter().zip(obligation.rewards.iter_mut()) {
+        if reward.reward_mint == Pubkey::default() {
+            continue;
+        }
+
+        let earned = (supply_basis as u128 * (reward.supply_index - position.supply_index)
+            + borrow_basis as u128 * (reward.borrow_index - position.borrow_index))
+            / INDEX_PRECISION;
+        position.unclaimed += earned as u64;
+        position.supply_index = reward.supply_index;
+        position.borrow_index = reward.borrow_index;
+    }
+
+    pool.reward_supply_basis = pool.reward_supply_basis - supply_basis + receipt_balance;
+    pool.reward_borrow_basis = pool.reward_borrow_basis - borrow_basis + obligation.amount_borrowed;
+    obligation.reward_supply_basis = receipt_balance;
+    obligation.reward_borrow_basis = obligation.amount_borrowed;
+
+    Ok(())
+}
+
+/// Freeze a zGOLD receipt account so collateral backing a loan cannot be transferred away
+pub fn freeze_receipt_account<'info>(
+    receipt_account: &InterfaceAccount<'info, TokenAccount>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    lending_pool: &Account<'info, LendingPool>,
+    token_program: &Program<'info, Token2022>,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+
+    freeze_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        FreezeAccount {
+            account: receipt_account.to_account_info(),
+            mint: receipt_mint.to_account_info(),
+            authority: lending_pool.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Thaw a frozen zGOLD receipt account
+pub fn thaw_receipt_account<'info>(
+    receipt_account: &InterfaceAccount<'info, TokenAccount>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    lending_pool: &Account<'info, LendingPool>,
+    token_program: &Program<'info, Token2022>,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+
+    thaw_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        ThawAccount {
+            account: receipt_account.to_account_info(),
+            mint: receipt_mint.to_account_info(),
+            authority: lending_pool.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Move zGOLD out of a pool owned escrow, thawing a frozen destination around the transfer
+pub fn release_escrowed_receipt<'info>(
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    receipt_mint: &InterfaceAccount<'info, Mint>,
+    lending_pool: &Account<'info, LendingPool>,
+    token_program: &Program<'info, Token2022>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let is_frozen = destination.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(destination, receipt_mint, lending_pool, token_program)?;
+    }
+
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: escrow.to_account_info(),
+                mint: receipt_mint.to_account_info(),
+                to: destination.to_account_info(),
+                authority: lending_pool.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        receipt_mint.decimals,
+    )?;
+
+    if is_frozen {
+        freeze_receipt_account(destination, receipt_mint, lending_pool, token_program)?;
+    }
+    Ok(())
+}
+
+/// Close an emptied pool owned zGOLD escrow, returning its rent
+pub fn close_receipt_escrow<'info>(
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    lending_pool: &Account<'info, LendingPool>,
+    token_program: &Program<'info, Token2022>,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[SEED_LENDING_POOL, &[lending_pool.bump]]];
+
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination,
+            authority: lending_pool.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
@@ -1,11 +1,17 @@
 use crate::{
-    BorrowerPosition, LenderPosition, LendingPool, SEED_BORROWER_POSITION,
-    SEED_LENDER_POSITION, SEED_LENDING_POOL,
+    instructions::lending::utils::{
+        available_liquidity, check_refreshed, freeze_receipt_account, initialize_obligation,
+        receipt_to_tokens, record_rate_history, refresh_obligation_collateral, sync_rewards,
+        thaw_receipt_account, tokens_to_receipt_round_up, update_pool_rates,
+    },
+    LendingPool, Obligation, RateHistory, SEED_LENDING_POOL, SEED_OBLIGATION, SEED_RATE_HISTORY,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, Token2022, TokenAccount, TransferChecked},
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, Token2022, TokenAccount, TransferChecked,
+    },
 };
 
 #[derive(Accounts)]
@@ -14,31 +20,28 @@ pub struct WithdrawTokens<'info> {
     pub lender: Signer<'info>,
 
     #[account(
-        init_if_needed,
-        payer = lender,
-        space = 8 + LendingPool::INIT_SPACE,
+        mut,
         seeds = [SEED_LENDING_POOL],
-        bump,
+        bump = lending_pool.bump,
+        has_one = receipt_mint,
     )]
     pub lending_pool: Account<'info, LendingPool>,
 
     #[account(
-        init_if_needed,
-        payer = lender,
-        space = 8 + LenderPosition::INIT_SPACE,
-        seeds = [SEED_LENDER_POSITION, lender.key().as_ref()],
-        bump,
+        mut,
+        seeds = [SEED_RATE_HISTORY, lending_pool.key().as_ref()],
+        bump = rate_history.load()?.bump,
     )]
-    pub lender_position: Account<'info, LenderPosition>,
+    pub rate_history: AccountLoader<'info, RateHistory>,
 
     #[account(
         init_if_needed,
         payer = lender,
-        space = 8 + BorrowerPosition::INIT_SPACE,
-        seeds = [SEED_BORROWER_POSITION, lender.key().as_ref()],
+        space = 8 + Obligation::INIT_SPACE,
+        seeds = [SEED_OBLIGATION, lending_pool.key().as_ref(), lender.key().as_ref()],
         bump,
     )]
-    pub borrower_position: Account<'info, BorrowerPosition>,
+    pub obligation: Account<'info, Obligation>,
 
     #[account(mut)]
     pub mint_account: InterfaceAccount<'info, Mint>,
@@ -46,6 +49,17 @@ pub struct WithdrawTokens<'info> {
     #[account(mut)]
     pub lender_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    #[account(mut)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program
+    )]
+    pub lender_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
     #[account(
         mut,
         seeds = [SEED_LENDING_POOL, mint_account.key().as_ref()],
@@ -62,80 +76,44 @@ pub struct WithdrawTokens<'info> {
 
 pub fn process_withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
     msg!("Instruction: WithdrawTokens");
-    
-    let clock = Clock::get()?;
+
     msg!("Withdraw Amount: {:.9}", amount as f64 / 1e9);
 
     // Store values needed for transfer before creating mutable borrows
     let pool_bump = ctx.accounts.lending_pool.bump;
     let lending_pool_info = ctx.accounts.lending_pool.to_account_info();
-    
+    let receipt_balance = ctx.accounts.lender_receipt_account.amount;
+
     let lending_pool = &mut ctx.accounts.lending_pool;
-    let lender_position = &mut ctx.accounts.lender_position;
-    let borrower_position = &mut ctx.accounts.borrower_position;
-
-    // Initialize lending pool if needed
-    if lending_pool.last_update_slot == 0 {
-        lending_pool.total_supplied = 0;
-        lending_pool.total_borrowed = 0;
-        lending_pool.last_update_slot = clock.slot;
-        lending_pool.supply_rate = 0;
-        lending_pool.borrow_rate = 0;
-        lending_pool.utilization_rate = 0;
-        lending_pool.bump = ctx.bumps.lending_pool;
-    }
+    let obligation = &mut ctx.accounts.obligation;
 
-    // Initialize lender position if needed
-    if lender_position.last_update_slot == 0 {
-        lender_position.lender = ctx.accounts.lender.key();
-        lender_position.amount_supplied = 0;
-        lender_position.accumulated_interest = 0;
-        lender_position.last_update_slot = clock.slot;
-        lender_position.bump = ctx.bumps.lender_position;
-    }
+    // Initialize obligation if needed
+    initialize_obligation(obligation, ctx.accounts.lender.key(), lending_pool, ctx.bumps.obligation)?;
+
+    // Pools that require refresh only accept state refreshed earlier in this slot
+    check_refreshed(lending_pool, obligation)?;
 
-    // Initialize borrower position if needed
-    if borrower_position.last_update_slot == 0 {
-        borrower_position.borrower = ctx.accounts.lender.key();
-        borrower_position.amount_borrowed = 0;
-        borrower_position.amount_supplied = 0;
-        borrower_position.accumulated_interest = 0;
-        borrower_position.last_update_slot = clock.slot;
-        borrower_position.bump = ctx.bumps.borrower_position;
-    }
-    
     // Update pool interest rates (this mutates lending_pool)
     crate::instructions::lending::utils::update_lending_pool(lending_pool)?;
 
-    // Update lender position interest
-    let interest = crate::instructions::lending::utils::calculate_lender_interest(
-        lender_position,
-        lending_pool,
-    )?;
-    lender_position.accumulated_interest = interest;
-    lender_position.last_update_slot = clock.slot;
+    // Lender interest is the growth of the zGOLD exchange rate over the principal supplied
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance);
 
-    // Update borrower position interest
-    let borrower_interest = crate::instructions::lending::utils::calculate_borrower_interest(
-        borrower_position,
-        lending_pool,
-    )?;
-    borrower_position.accumulated_interest = borrower_interest;
-    borrower_position.last_update_slot = clock.slot;
-
-    // Calculate available balance (supplied + interest - borrowed debt)
-    let total_debt = borrower_position.amount_borrowed + borrower_position.accumulated_interest;
-    let available_balance = lender_position.amount_supplied
-        + lender_position.accumulated_interest
-        - total_debt;
-
-    msg!("Lender Position - Supplied: {:.9}, Interest: {:.9}, Total: {:.9}",
-         lender_position.amount_supplied as f64 / 1e9,
-         lender_position.accumulated_interest as f64 / 1e9,
-         (lender_position.amount_supplied + lender_position.accumulated_interest) as f64 / 1e9);
-    msg!("Borrower Debt - Principal: {:.9}, Interest: {:.9}, Total: {:.9}",
-         borrower_position.amount_borrowed as f64 / 1e9,
-         borrower_position.accumulated_interest as f64 / 1e9,
+    // Update borrow interest
+    crate::instructions::lending::utils::accrue_obligation_interest(obligation, lending_pool)?;
+
+    // Calculate available balance (zGOLD value - borrowed debt)
+    let total_debt = obligation.amount_borrowed + obligation.borrow_interest;
+    let available_balance = obligation.collateral_value.saturating_sub(total_debt);
+
+    msg!("Obligation Supply - Supplied: {:.9}, Interest: {:.9}, zGOLD: {:.9}, Value: {:.9}",
+         obligation.amount_supplied as f64 / 1e9,
+         obligation.supply_interest as f64 / 1e9,
+         receipt_balance as f64 / 1e9,
+         obligation.collateral_value as f64 / 1e9);
+    msg!("Obligation Debt - Principal: {:.9}, Interest: {:.9}, Total: {:.9}",
+         obligation.amount_borrowed as f64 / 1e9,
+         obligation.borrow_interest as f64 / 1e9,
          total_debt as f64 / 1e9);
     msg!("Available Balance: {:.9}", available_balance as f64 / 1e9);
 
@@ -144,41 +122,89 @@ pub fn process_withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Res
         crate::error::CustomError::InsufficientFunds
     );
 
-    // Check pool has enough liquidity
-    msg!("Pool Liquidity Check - Total Supplied: {:.9}, Requested: {:.9}",
+    // Check pool has enough liquidity, queued withdrawal requests are served first
+    let queued_value = receipt_to_tokens(lending_pool.queued_receipt, lending_pool);
+    let liquidity = available_liquidity(lending_pool, ctx.accounts.lending_pool_vault.amount)
+        .saturating_sub(queued_value);
+    msg!("Pool Liquidity Check - Total Supplied: {:.9}, Vault: {:.9}, Queued: {:.9}, Available: {:.9}, Requested: {:.9}",
          lending_pool.total_supplied as f64 / 1e9,
+         ctx.accounts.lending_pool_vault.amount as f64 / 1e9,
+         queued_value as f64 / 1e9,
+         liquidity as f64 / 1e9,
          amount as f64 / 1e9);
-    
+
     require!(
         lending_pool.total_supplied >= amount,
         crate::error::CustomError::InsufficientFunds
     );
-    
+    require!(
+        amount <= liquidity,
+        crate::error::CustomError::InsufficientLiquidity
+    );
+
+    // zGOLD burned for the withdrawal, rounded up in favour of the pool
+    let receipt_to_burn = tokens_to_receipt_round_up(amount, lending_pool).min(receipt_balance);
+
     // Update amounts - first reduce from interest, then from principal
-    let interest_withdrawal = if amount > lender_position.accumulated_interest {
-        lender_position.accumulated_interest
+    let interest_withdrawal = if amount > obligation.supply_interest {
+        obligation.supply_interest
     } else {
         amount
     };
     let principal_withdrawal = amount - interest_withdrawal;
 
-    let previous_supplied = lender_position.amount_supplied;
-    let previous_interest = lender_position.accumulated_interest;
-    
-    lender_position.accumulated_interest -= interest_withdrawal;
-    lender_position.amount_supplied -= principal_withdrawal;
-    borrower_position.amount_supplied -= principal_withdrawal;
+    let previous_supplied = obligation.amount_supplied;
+    let previous_interest = obligation.supply_interest;
+
+    obligation.amount_supplied = obligation.amount_supplied.saturating_sub(principal_withdrawal);
+    refresh_obligation_collateral(obligation, lending_pool, receipt_balance - receipt_to_burn);
     lending_pool.total_supplied -= amount;
+    update_pool_rates(lending_pool)?;
+    sync_rewards(lending_pool, obligation, receipt_balance - receipt_to_burn)?;
 
-    msg!("Withdrawal Breakdown - Interest: {:.9}, Principal: {:.9}",
+    msg!("Withdrawal Breakdown - Interest: {:.9}, Principal: {:.9}, zGOLD Burned: {:.9}",
          interest_withdrawal as f64 / 1e9,
-         principal_withdrawal as f64 / 1e9);
-    msg!("Lender Position Updated - Previous Supplied: {:.9}, New Supplied: {:.9}",
+         principal_withdrawal as f64 / 1e9,
+         receipt_to_burn as f64 / 1e9);
+    msg!("Obligation Supply Updated - Previous Supplied: {:.9}, New Supplied: {:.9}",
          previous_supplied as f64 / 1e9,
-         lender_position.amount_supplied as f64 / 1e9);
-    msg!("Lender Interest Updated - Previous: {:.9}, New: {:.9}",
+         obligation.amount_supplied as f64 / 1e9);
+    msg!("Obligation Interest Updated - Previous: {:.9}, New: {:.9}",
          previous_interest as f64 / 1e9,
-         lender_position.accumulated_interest as f64 / 1e9);
+         obligation.supply_interest as f64 / 1e9);
+
+    // Burn zGOLD receipt, thawing the account if it was frozen as loan collateral
+    let is_frozen = ctx.accounts.lender_receipt_account.is_frozen();
+    if is_frozen {
+        thaw_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    msg!("Burning zGOLD receipt from lender...");
+    burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                from: ctx.accounts.lender_receipt_account.to_account_info(),
+                authority: ctx.accounts.lender.to_account_info(),
+            },
+        ),
+        receipt_to_burn,
+    )?;
+
+    if is_frozen && total_debt > 0 {
+        freeze_receipt_account(
+            &ctx.accounts.lender_receipt_account,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.lending_pool,
+            &ctx.accounts.token_program,
+        )?;
+    }
 
     // Transfer tokens from pool vault to lender
     msg!("Transferring tokens from pool vault to lender...");
@@ -199,15 +225,16 @@ pub fn process_withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Res
         ctx.accounts.mint_account.decimals,
     )?;
 
+    let obligation = &ctx.accounts.obligation;
     msg!("=== WITHDRAW COMPLETE ===");
     msg!("Withdrew: {:.9} tokens", amount as f64 / 1e9);
-    msg!("Lender Remaining Supplied: {:.9}", lender_position.amount_supplied as f64 / 1e9);
-    msg!("Lender Remaining Interest: {:.9}", lender_position.accumulated_interest as f64 / 1e9);
-    msg!("Total Pool Supplied: {:.9}", lending_pool.total_supplied as f64 / 1e9);
+    msg!("Lender Remaining Supplied: {:.9}", obligation.amount_supplied as f64 / 1e9);
+    msg!("Lender Remaining Interest: {:.9}", obligation.supply_interest as f64 / 1e9);
+    msg!("Total Pool Supplied: {:.9}", ctx.accounts.lending_pool.total_supplied as f64 / 1e9);
     msg!("Remaining Available Balance: {:.9}",
-         (lender_position.amount_supplied + lender_position.accumulated_interest - 
-          (borrower_position.amount_borrowed + borrower_position.accumulated_interest)) as f64 / 1e9);
+         obligation.collateral_value.saturating_sub(
+             obligation.amount_borrowed + obligation.borrow_interest) as f64 / 1e9);
 
+    record_rate_history(&ctx.accounts.rate_history, &ctx.accounts.lending_pool)?;
     Ok(())
 }
-
@@ -8,3 +8,5 @@ pub use lending::*;
 pub mod lending;
 pub use utils::*;
 pub mod utils;
+pub use insurance::*;
+pub mod insurance;
@@ -1,5 +1,7 @@
 use crate::{
-    error::CustomError, Collateral, Config, GOLD_FEED_ID, SOL_FEED_ID, MAXIMUM_AGE, PRICE_FEED_DECIMAL_ADJUSTMENT,
+    error::CustomError, instructions::lending::utils::calculate_borrower_interest, Collateral,
+    Config, LendingPool, Obligation, BASIS_POINTS, GOLD_FEED_ID, SOL_FEED_ID, MAXIMUM_AGE,
+    PRICE_FEED_DECIMAL_ADJUSTMENT,
 };
 use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
 use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
@@ -50,6 +52,103 @@ pub fn calculate_health_factor(
     Ok(health_factor)
 }
 
+// Check a Collateral account stays healthy. Cross margin SOL also backs lending debt, so the combined
+// position with the owner's obligation must stay healthy as well.
+pub fn check_collateral_health(
+    collateral: &Account<Collateral>,
+    lending_pool: Option<&Account<LendingPool>>,
+    obligation: Option<&Account<Obligation>>,
+    config: &Account<Config>,
+    gold_price_feed: &Account<PriceUpdateV2>,
+    sol_price_feed: &Account<PriceUpdateV2>,
+) -> Result<()> {
+    check_health_factor(collateral, config, gold_price_feed, sol_price_feed)?;
+
+    if collateral.cross_margin {
+        let (Some(lending_pool), Some(obligation)) = (lending_pool, obligation) else {
+            return err!(CustomError::CrossMarginAccountsMissing);
+        };
+        require_keys_eq!(obligation.owner, collateral.depositor);
+        require_keys_eq!(obligation.lending_pool, lending_pool.key());
+
+        check_cross_margin_health_factor(
+            collateral,
+            obligation,
+            lending_pool,
+            config,
+            gold_price_feed,
+            sol_price_feed,
+        )?;
+    }
+    Ok(())
+}
+
+// Check the combined health factor of a cross margin position is above the minimum
+pub fn check_cross_margin_health_factor(
+    collateral: &Account<Collateral>,
+    obligation: &Account<Obligation>,
+    lending_pool: &Account<LendingPool>,
+    config: &Account<Config>,
+    gold_price_feed: &Account<PriceUpdateV2>,
+    sol_price_feed: &Account<PriceUpdateV2>,
+) -> Result<()> {
+    let health_factor = calculate_cross_margin_health_factor(
+        collateral,
+        obligation,
+        lending_pool,
+        gold_price_feed,
+        sol_price_feed,
+    )?;
+    require!(
+        health_factor >= config.min_health_factor,
+        CustomError::BelowMinimumHealthFactor
+    );
+    Ok(())
+}
+
+// Combined health factor across the CDP vault and the lending obligation:
+// (SOL collateral value + zGOLD value * collateral factor) / (GOLD minted + lending debt)
+pub fn calculate_cross_margin_health_factor(
+    collateral: &Account<Collateral>,
+    obligation: &Account<Obligation>,
+    lending_pool: &Account<LendingPool>,
+    gold_price_feed: &Account<PriceUpdateV2>,
+    sol_price_feed: &Account<PriceUpdateV2>,
+) -> Result<u64> {
+    let sol_value_in_usd = get_sol_usd_value(&collateral.lamport_balance, sol_price_feed)?;
+    let sol_value_in_gold = get_gold_value_from_usd(&sol_value_in_usd, gold_price_feed, sol_price_feed)?;
+    let supply_value = (obligation.collateral_value * lending_pool.collateral_factor) / BASIS_POINTS;
+
+    let lending_debt = obligation.amount_borrowed + calculate_borrower_interest(obligation, lending_pool)?;
+    let total_debt = collateral.amount_minted + lending_debt;
+
+    msg!("*** CROSS MARGIN ***");
+    msg!("SOL Collateral : {:.9} GOLD", sol_value_in_gold as f64 / 1e9);
+    msg!("zGOLD Weighted : {:.9} GOLD", supply_value as f64 / 1e9);
+    msg!("Minted Amount  : {:.9}", collateral.amount_minted as f64 / 1e9);
+    msg!("Lending Debt   : {:.9}", lending_debt as f64 / 1e9);
+
+    if total_debt == 0 {
+        msg!("Cross Margin Health Factor Max");
+        return Ok(u64::MAX);
+    }
+
+    let health_factor = (sol_value_in_gold + supply_value) / total_debt;
+
+    msg!("Cross Margin Health Factor : {}", health_factor);
+    Ok(health_factor)
+}
+
+// Value of a Collateral account's SOL in GOLD, used to size bad debt on underwater vaults
+pub fn get_collateral_value_in_gold(
+    collateral: &Account<Collateral>,
+    gold_price_feed: &Account<PriceUpdateV2>,
+    sol_price_feed: &Account<PriceUpdateV2>,
+) -> Result<u64> {
+    let sol_value_in_usd = get_sol_usd_value(&collateral.lamport_balance, sol_price_feed)?;
+    get_gold_value_from_usd(&sol_value_in_usd, gold_price_feed, sol_price_feed)
+}
+
 // Get SOL value in USD using SOL/USD price feed
 fn get_sol_usd_value(amount_in_lamports: &u64, sol_price_feed: &Account<PriceUpdateV2>) -> Result<u64> {
     let sol_feed_id = get_feed_id_from_hex(SOL_FEED_ID)
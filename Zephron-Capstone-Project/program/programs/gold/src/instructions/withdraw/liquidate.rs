@@ -1,6 +1,7 @@
 use crate::{
     burn_tokens_internal, calculate_health_factor, error::CustomError, get_lamports_from_gold,
-    withdraw_sol_internal, Collateral, Config, SEED_CONFIG_ACCOUNT,
+    pay_insurance_fee, withdraw_sol_internal, Collateral, Config, InsuranceFund,
+    SEED_CONFIG_ACCOUNT, SEED_INSURANCE_FUND,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
@@ -35,6 +36,15 @@ pub struct Liquidate<'info> {
         associated_token::token_program = token_program
     )]
     pub token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [SEED_INSURANCE_FUND],
+        bump = insurance_fund.bump,
+        has_one = insurance_vault,
+    )]
+    pub insurance_fund: Box<Account<'info, InsuranceFund>>,
+    #[account(mut)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
     pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
@@ -79,6 +89,16 @@ pub fn process_liquidate(ctx: Context<Liquidate>, amount_to_burn: u64) -> Result
         amount_to_burn,
     )?;
 
+    pay_insurance_fee(
+        &mut ctx.accounts.insurance_fund,
+        &ctx.accounts.insurance_vault,
+        &ctx.accounts.mint_account,
+        &ctx.accounts.token_account,
+        &ctx.accounts.liquidator,
+        &ctx.accounts.token_program,
+        amount_to_burn,
+    )?;
+
     let collateral_account = &mut ctx.accounts.collateral_account;
     collateral_account.lamport_balance = ctx.accounts.sol_account.lamports();
     collateral_account.amount_minted -= amount_to_burn;
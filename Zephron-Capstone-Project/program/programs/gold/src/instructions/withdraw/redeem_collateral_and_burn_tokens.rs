@@ -1,6 +1,6 @@
 use crate::{
-    burn_tokens_internal, check_health_factor, withdraw_sol_internal, Collateral, Config,
-    SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
+    burn_tokens_internal, check_collateral_health, withdraw_sol_internal, Collateral, Config,
+    LendingPool, Obligation, SEED_COLLATERAL_ACCOUNT, SEED_CONFIG_ACCOUNT,
 };
 use anchor_lang::prelude::*;
 use anchor_spl::token_interface::{Mint, Token2022, TokenAccount};
@@ -33,6 +33,9 @@ pub struct RedeemCollateralAndBurnTokens<'info> {
     pub mint_account: InterfaceAccount<'info, Mint>,
     #[account(mut)]
     pub token_account: InterfaceAccount<'info, TokenAccount>,
+    // Required when the collateral account is cross margined with a lending obligation
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+    pub obligation: Option<Account<'info, Obligation>>,
     pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
@@ -47,8 +50,11 @@ pub fn process_redeem_collateral_and_burn_tokens(
     collateral_account.lamport_balance = ctx.accounts.sol_account.lamports() - amount_collateral;
     collateral_account.amount_minted -= amount_to_burn;
 
-    check_health_factor(
+    // Cross margin SOL may also back lending debt, the combined position must stay healthy
+    check_collateral_health(
         &ctx.accounts.collateral_account,
+        ctx.accounts.lending_pool.as_ref(),
+        ctx.accounts.obligation.as_ref(),
         &ctx.accounts.config_account,
         &ctx.accounts.gold_price_update,
         &ctx.accounts.sol_price_update,
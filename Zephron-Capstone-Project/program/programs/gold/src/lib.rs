@@ -21,6 +21,102 @@ pub mod gold {
         process_update_config(ctx, min_health_factor)
     }
 
+    pub fn initialize_lending_pool(
+        ctx: Context<InitializeLendingPool>,
+        collateral_factor: u64,
+        reserve_factor: u64,
+    ) -> Result<()> {
+        process_initialize_lending_pool(ctx, collateral_factor, reserve_factor)
+    }
+
+    pub fn migrate_lending_pool(
+        ctx: Context<MigrateLendingPool>,
+        collateral_factor: u64,
+        reserve_factor: u64,
+    ) -> Result<()> {
+        process_migrate_lending_pool(ctx, collateral_factor, reserve_factor)
+    }
+
+    pub fn initialize_rate_history(ctx: Context<InitializeRateHistory>) -> Result<()> {
+        process_initialize_rate_history(ctx)
+    }
+
+    pub fn update_lending_pool_caps(
+        ctx: Context<UpdateLendingPoolCaps>,
+        supply_cap: u64,
+        borrow_cap: u64,
+        max_utilization: u64,
+    ) -> Result<()> {
+        process_update_lending_pool_caps(ctx, supply_cap, borrow_cap, max_utilization)
+    }
+
+    pub fn update_lending_pool_clock_source(
+        ctx: Context<UpdateLendingPoolClockSource>,
+        clock_source: ClockSource,
+    ) -> Result<()> {
+        process_update_lending_pool_clock_source(ctx, clock_source)
+    }
+
+    pub fn update_lending_pool_require_refresh(
+        ctx: Context<UpdateLendingPoolRequireRefresh>,
+        require_refresh: bool,
+    ) -> Result<()> {
+        process_update_lending_pool_require_refresh(ctx, require_refresh)
+    }
+
+    pub fn update_term_loan_params(
+        ctx: Context<UpdateTermLoanParams>,
+        term_rate_premium: u64,
+        max_term_duration: i64,
+        term_grace_period: i64,
+        early_repayment_fee: u64,
+    ) -> Result<()> {
+        process_update_term_loan_params(
+            ctx,
+            term_rate_premium,
+            max_term_duration,
+            term_grace_period,
+            early_repayment_fee,
+        )
+    }
+
+    pub fn initialize_reward(ctx: Context<InitializeReward>, slot: u8) -> Result<()> {
+        process_initialize_reward(ctx, slot)
+    }
+
+    pub fn fund_reward(
+        ctx: Context<FundReward>,
+        slot: u8,
+        amount: u64,
+        supply_emission_rate: u64,
+        borrow_emission_rate: u64,
+        end_timestamp: i64,
+    ) -> Result<()> {
+        process_fund_reward(
+            ctx,
+            slot,
+            amount,
+            supply_emission_rate,
+            borrow_emission_rate,
+            end_timestamp,
+        )
+    }
+
+    pub fn withdraw_reserves(ctx: Context<WithdrawReserves>, amount: u64) -> Result<()> {
+        process_withdraw_reserves(ctx, amount)
+    }
+
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        process_initialize_insurance_fund(ctx)
+    }
+
+    pub fn fund_insurance_from_reserves(
+        ctx: Context<FundInsuranceFromReserves>,
+        amount: u64,
+    ) -> Result<()> {
+        process_fund_insurance_from_reserves(ctx, amount)
+    }
+
     pub fn deposit_collateral_and_mint(
         ctx: Context<DepositCollateralAndMintTokens>,
         amount_collateral: u64,
@@ -29,6 +125,14 @@ pub mod gold {
         process_deposit_collateral_and_mint_tokens(ctx, amount_collateral, amount_to_mint)
     }
 
+    pub fn mint_and_deposit_to_lending(
+        ctx: Context<MintAndDepositToLending>,
+        amount_collateral: u64,
+        amount_to_mint: u64,
+    ) -> Result<u64> {
+        process_mint_and_deposit_to_lending(ctx, amount_collateral, amount_to_mint)
+    }
+
     pub fn redeem_collateral_and_burn_tokens(
         ctx: Context<RedeemCollateralAndBurnTokens>,
         amount_collateral: u64,
@@ -54,7 +158,92 @@ pub mod gold {
         process_repay_tokens(ctx, amount)
     }
 
+    pub fn repay_tokens_for(ctx: Context<RepayTokensFor>, amount: u64) -> Result<()> {
+        process_repay_tokens_for(ctx, amount)
+    }
+
     pub fn withdraw_tokens(ctx: Context<WithdrawTokens>, amount: u64) -> Result<()> {
         process_withdraw_tokens(ctx, amount)
     }
+
+    pub fn refresh_pool(ctx: Context<RefreshPool>) -> Result<()> {
+        process_refresh_pool(ctx)
+    }
+
+    pub fn refresh_position(ctx: Context<RefreshPosition>) -> Result<()> {
+        process_refresh_position(ctx)
+    }
+
+    pub fn set_cross_margin(ctx: Context<SetCrossMargin>, enabled: bool) -> Result<()> {
+        process_set_cross_margin(ctx, enabled)
+    }
+
+    pub fn borrow_tokens_cross_margin(
+        ctx: Context<BorrowTokensCrossMargin>,
+        amount: u64,
+    ) -> Result<()> {
+        process_borrow_tokens_cross_margin(ctx, amount)
+    }
+
+    pub fn liquidate_cross_margin(
+        ctx: Context<LiquidateCrossMargin>,
+        amount: u64,
+        side: MarginSide,
+    ) -> Result<()> {
+        process_liquidate_cross_margin(ctx, amount, side)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>, slot: u8) -> Result<()> {
+        process_claim_rewards(ctx, slot)
+    }
+
+    pub fn migrate_positions(ctx: Context<MigratePositions>) -> Result<()> {
+        process_migrate_positions(ctx)
+    }
+
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
+        process_request_withdrawal(ctx, amount)
+    }
+
+    pub fn fill_withdrawal_request(ctx: Context<FillWithdrawalRequest>) -> Result<()> {
+        process_fill_withdrawal_request(ctx)
+    }
+
+    pub fn borrow_term_loan(
+        ctx: Context<BorrowTermLoan>,
+        loan_id: u64,
+        amount: u64,
+        duration: i64,
+    ) -> Result<()> {
+        process_borrow_term_loan(ctx, loan_id, amount, duration)
+    }
+
+    pub fn repay_term_loan(ctx: Context<RepayTermLoan>) -> Result<()> {
+        process_repay_term_loan(ctx)
+    }
+
+    pub fn liquidate_term_loan(ctx: Context<LiquidateTermLoan>) -> Result<()> {
+        process_liquidate_term_loan(ctx)
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        process_flash_borrow(ctx, amount)
+    }
+
+    pub fn flash_repay(
+        ctx: Context<FlashRepay>,
+        amount: u64,
+        borrow_instruction_index: u8,
+    ) -> Result<()> {
+        process_flash_repay(ctx, amount, borrow_instruction_index)
+    }
+
+    // Insurance fund functions
+    pub fn cover_bad_debt(ctx: Context<CoverBadDebt>) -> Result<()> {
+        process_cover_bad_debt(ctx)
+    }
+
+    pub fn cover_cdp_bad_debt(ctx: Context<CoverCdpBadDebt>) -> Result<()> {
+        process_cover_cdp_bad_debt(ctx)
+    }
 }
@@ -1,3 +1,4 @@
+use crate::{MAX_REWARD_MINTS, RATE_HISTORY_LENGTH};
 use anchor_lang::prelude::*;
 
 #[account]
@@ -11,6 +12,7 @@ pub struct Collateral {
     pub bump: u8,           // store bump seed for this collateral account PDA
     pub bump_sol_account: u8, // store bump seed for the  sol_account PDA
     pub is_initialized: bool, // indicate if account data has already been initialized (for check to prevent overriding certain fields)
+    pub cross_margin: bool,   // SOL collateral also backs the depositor's lending obligation, redeems check the combined health factor
 }
 
 #[account]
@@ -25,18 +27,155 @@ pub struct Config {
     pub bump_mint_account: u8,  // store bump seed for the stablecoin mint account PDA
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ClockSource {
+    Slot,          // elapsed slots over SLOTS_PER_YEAR, assumes ~400ms slots
+    UnixTimestamp, // elapsed seconds over SECONDS_PER_YEAR
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MarginSide {
+    Cdp,     // seize SOL from the owner's CDP collateral account
+    Lending, // seize zGOLD from the owner's receipt account through the pool delegate
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug)]
+pub struct PoolReward {
+    pub reward_mint: Pubkey,         // reward token mint, default pubkey when the slot is unused
+    pub supply_emission_rate: u64,   // reward tokens emitted per second to suppliers
+    pub borrow_emission_rate: u64,   // reward tokens emitted per second to borrowers
+    pub supply_index: u128,          // rewards per zGOLD of supply basis, scaled by INDEX_PRECISION
+    pub borrow_index: u128,          // rewards per token of borrow basis, scaled by INDEX_PRECISION
+    pub end_timestamp: i64,          // unix timestamp emissions stop
+    pub last_update_timestamp: i64,  // unix timestamp the indexes were last accrued
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace, Debug)]
+pub struct ObligationReward {
+    pub supply_index: u128,          // pool supply reward index at the last sync
+    pub borrow_index: u128,          // pool borrow reward index at the last sync
+    pub unclaimed: u64,              // rewards earned and not yet claimed
+}
+
 #[account]
 #[derive(InitSpace, Debug)]
 pub struct LendingPool {
     pub total_supplied: u64,        // total amount of tokens supplied to the pool
     pub total_borrowed: u64,        // total amount of tokens borrowed from the pool
     pub last_update_slot: u64,      // last slot when interest was calculated
+    pub last_update_timestamp: i64, // unix timestamp when interest was calculated
+    pub clock_source: ClockSource,  // clock interest accrues against
+    pub last_refresh_slot: u64,     // last slot the pool was refreshed (refresh_pool / refresh_position)
+    pub require_refresh: bool,      // borrow and withdraw require pool and obligation refreshed in the same slot
     pub supply_rate: u64,           // annual supply interest rate (basis points, e.g., 500 = 5%)
     pub borrow_rate: u64,           // annual borrow interest rate (basis points, e.g., 1000 = 10%)
     pub utilization_rate: u64,     // current utilization rate (basis points)
+    pub collateral_factor: u64,     // share of supplied tokens that can be borrowed against (basis points)
+    pub supply_cap: u64,            // maximum total_supplied accepted by deposits
+    pub borrow_cap: u64,            // maximum total_borrowed allowed by borrows
+    pub max_utilization: u64,       // hard maximum utilization after a borrow (basis points)
+    pub reserve_factor: u64,        // share of borrow interest routed to protocol reserves (basis points)
+    pub protocol_reserves: u64,     // accrued protocol reserves held in the pool vault
+    pub flash_loan_fee: u64,        // fee charged on flash loans (basis points), accrues to lenders
+    pub supply_index: u128,         // zGOLD to GOLD exchange rate, scaled by INDEX_PRECISION, grows with supply interest
+    pub receipt_mint: Pubkey,       // zGOLD receipt token mint, a PDA owned by the lending pool
+    pub queued_receipt: u64,        // zGOLD escrowed by pending withdrawal requests
+    pub withdrawal_queue_head: u64, // id of the next withdrawal request to fill
+    pub withdrawal_queue_tail: u64, // id assigned to the next withdrawal request
+    pub term_rate_premium: u64,     // premium over the floating borrow rate locked into term loans (basis points)
+    pub max_term_duration: i64,     // longest term loan maturity (seconds)
+    pub term_grace_period: i64,     // seconds after maturity before a term loan can be liquidated
+    pub early_repayment_fee: u64,   // fee on principal when a term loan is repaid before maturity (basis points)
+    pub term_borrowed: u64,         // term loan principal within total_borrowed, pays its fixed rate instead of the floating one
+    pub reward_supply_basis: u64,   // sum of obligation zGOLD balances earning supply side rewards
+    pub reward_borrow_basis: u64,   // sum of obligation borrowed principal earning borrow side rewards
+    pub rewards: [PoolReward; MAX_REWARD_MINTS], // liquidity mining reward slots, default reward_mint = empty
     pub bump: u8,                   // bump seed for the lending pool PDA
+    pub bump_receipt_mint: u8,      // bump seed for the receipt mint PDA
 }
 
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Obligation {
+    pub owner: Pubkey,              // obligation owner's wallet address
+    pub lending_pool: Pubkey,       // lending pool (market) this obligation belongs to
+    pub amount_supplied: u64,       // principal tokens supplied by the owner
+    pub supply_interest: u64,       // supply interest earned, zGOLD value above the principal supplied
+    pub collateral_value: u64,      // value of the owner's zGOLD backing borrows
+    pub supply_index: u128,         // pool supply index when collateral_value was last refreshed
+    pub amount_borrowed: u64,       // principal tokens borrowed
+    pub borrow_interest: u64,       // accumulated interest owed
+    pub last_update_slot: u64,      // last slot when borrow interest was calculated
+    pub last_update_timestamp: i64, // unix timestamp when borrow interest was calculated
+    pub last_refresh_slot: u64,     // last slot the obligation was refreshed by refresh_position
+    pub reward_supply_basis: u64,   // zGOLD balance earning supply side rewards since the last sync
+    pub reward_borrow_basis: u64,   // borrowed principal earning borrow side rewards since the last sync
+    pub rewards: [ObligationReward; MAX_REWARD_MINTS], // reward accrual, same slots as the lending pool
+    pub bump: u8,                   // bump seed for the obligation PDA
+    pub is_initialized: bool,       // indicate if account data has already been initialized
+    pub cross_margin: bool,         // borrows count the owner's CDP SOL collateral, zGOLD is delegated to the pool
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct WithdrawalRequest {
+    pub owner: Pubkey,              // lender's wallet address, receives the GOLD
+    pub lending_pool: Pubkey,       // lending pool the request is queued in
+    pub id: u64,                    // position in the FIFO withdrawal queue
+    pub receipt_amount: u64,        // zGOLD still escrowed, redeemed at the exchange rate when filled
+    pub filled_amount: u64,         // GOLD paid out so far by partial fills
+    pub bump: u8,                   // bump seed for the withdrawal request PDA
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct TermLoan {
+    pub owner: Pubkey,              // borrower's wallet address
+    pub lending_pool: Pubkey,       // lending pool the loan was drawn from
+    pub loan_id: u64,               // borrower chosen id, lets a wallet hold several term loans
+    pub principal: u64,             // GOLD borrowed
+    pub fixed_rate: u64,            // annual interest rate locked at origination (basis points)
+    pub start_timestamp: i64,       // unix timestamp the loan was originated
+    pub maturity_timestamp: i64,    // unix timestamp the loan is due
+    pub collateral_receipt: u64,    // zGOLD escrowed as collateral for this loan
+    pub bump: u8,                   // bump seed for the term loan PDA
+}
+
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct RateSnapshot {
+    pub timestamp: i64,             // unix timestamp the pool state below took effect
+    pub utilization_rate: u64,      // utilization rate (basis points)
+    pub supply_rate: u64,           // annual supply rate (basis points)
+    pub borrow_rate: u64,           // annual borrow rate (basis points)
+    pub total_supplied: u64,        // total GOLD supplied to the pool
+    pub total_borrowed: u64,        // total GOLD borrowed from the pool
+}
+
+// Ring buffer of pool snapshots, zero copy since it is too large to deserialize on the stack
+#[account(zero_copy)]
+#[derive(Debug)]
+pub struct RateHistory {
+    pub lending_pool: Pubkey,       // lending pool this history belongs to
+    pub head: u64,                  // index of the most recent snapshot
+    pub count: u64,                 // number of snapshots written, capped at RATE_HISTORY_LENGTH
+    pub snapshots: [RateSnapshot; RATE_HISTORY_LENGTH], // oldest entries are overwritten once full
+    pub bump: u8,                   // bump seed for the rate history PDA
+    pub padding: [u8; 7],           // keeps the account 8 byte aligned for zero copy
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct InsuranceFund {
+    pub insurance_vault: Pubkey,    // GOLD token account holding the fund
+    pub total_deposited: u64,       // GOLD paid in from liquidation fees and protocol reserves
+    pub total_covered: u64,         // GOLD spent by the fund covering bad debt
+    pub total_socialized: u64,      // bad debt the fund could not cover, written off against lenders
+    pub bump: u8,                   // bump seed for the insurance fund PDA
+    pub bump_insurance_vault: u8,   // bump seed for the insurance vault PDA
+}
+
+// Legacy per-wallet positions, only read by migrate_positions to fold them into an Obligation
 #[account]
 #[derive(InitSpace, Debug)]
 pub struct LenderPosition {
@@ -57,3 +196,16 @@ pub struct BorrowerPosition {
     pub last_update_slot: u64,      // last slot when interest was calculated
     pub bump: u8,                   // bump seed for the borrower position PDA
 }
+
+// Legacy lending pool layout, stored under the LendingPool discriminator. Only read by
+// migrate_lending_pool, which rewrites the account in the current layout.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Debug)]
+pub struct LegacyLendingPool {
+    pub total_supplied: u64,        // total amount of tokens supplied to the pool
+    pub total_borrowed: u64,        // total amount of tokens borrowed from the pool
+    pub last_update_slot: u64,      // last slot when interest was calculated
+    pub supply_rate: u64,           // annual supply interest rate (basis points, e.g., 500 = 5%)
+    pub borrow_rate: u64,           // annual borrow interest rate (basis points, e.g., 1000 = 10%)
+    pub utilization_rate: u64,     // current utilization rate (basis points)
+    pub bump: u8,                   // bump seed for the lending pool PDA
+}
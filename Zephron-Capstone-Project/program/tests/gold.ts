@@ -4,6 +4,9 @@ import { PythSolanaReceiver } from "@pythnetwork/pyth-solana-receiver";
 import {
   ASSOCIATED_TOKEN_PROGRAM_ID,
   TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createTransferCheckedInstruction,
+  getAccount,
   getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 
@@ -96,6 +99,8 @@ describe("gold", () => {
         goldPriceUpdate: goldUsdPriceFeedAccount,
         solPriceUpdate: solUsdPriceFeedAccount,
         tokenAccount,
+        lendingPool: null, // only required once the collateral account is cross margined
+        obligation: null,
         tokenProgram,
         associatedTokenProgram,
         systemProgram,
@@ -119,6 +124,159 @@ describe("gold", () => {
         solAccount,
         mintAccount,
         tokenAccount,
+        lendingPool: null, // only required once the collateral account is cross margined
+        obligation: null,
+        tokenProgram,
+        systemProgram,
+      })
+      .signers([])
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Mint GOLD and deposit it into the lending protocol", async () => {
+    // The lending protocol is a separate program, loaded from its own workspace IDL
+    const lendingIdl = require("../../lending/target/idl/lending_protocol.json");
+    const lending = new anchor.Program(lendingIdl, provider) as any;
+    const [lendingBank] = anchor.web3.PublicKey.findProgramAddressSync(
+      [mintAccount.toBuffer()],
+      lending.programId
+    );
+    const [lendingBankTokenAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("treasury"), mintAccount.toBuffer()],
+      lending.programId
+    );
+    const [lendingUserAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [wallet.publicKey.toBuffer()],
+      lending.programId
+    );
+
+    if (!(await connection.getAccountInfo(lendingBank))) {
+      // Listing a bank is reserved to the protocol authority, the wallet that deployed the lending program
+      const [protocol] = anchor.web3.PublicKey.findProgramAddressSync([seed("protocol")], lending.programId);
+      if (!(await connection.getAccountInfo(protocol))) {
+        const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+          [lending.programId.toBuffer()],
+          new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+        );
+        await lending.methods
+          .initProtocol()
+          .accounts({ signer: wallet.publicKey, programData })
+          .rpc({ commitment: "confirmed" });
+      }
+      await lending.methods
+        .initBank({
+          liquidationThreshold: new BN(80),
+          maxLtv: new BN(75),
+          liquidationBonus: new BN(5),
+          liquidationCloseFactor: new BN(50),
+          interestRate: new BN(500),
+          maxInterestRate: new BN(5000),
+          optimalUtilization: new BN(80),
+          isolated: false,
+          isolatedDebtCeiling: new BN(0),
+          emodeCategory: 0,
+          oracleFeedId: Array.from(Buffer.from(GOLD_PRICE_FEED_ID.slice(2), "hex")),
+          maxPriceAge: new BN(100),
+        })
+        .accounts({
+          authority: wallet.publicKey,
+          mint: mintAccount,
+          emodeCategory: null, // outside every e-mode category
+          tokenProgram,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+    if (!(await connection.getAccountInfo(lendingUserAccount))) {
+      await lending.methods
+        .initUser()
+        .accounts({ signer: wallet.publicKey })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const depositShares = async () => {
+      const user = await lending.account.user.fetch(lendingUserAccount);
+      const position = user.positions.find((p: any) => p.bank.equals(lendingBank));
+      return position ? position.depositShares : new BN(0);
+    };
+    const sharesBefore = await depositShares();
+    const bankBefore = await lending.account.bank.fetch(lendingBank);
+
+    const amountCollateral = 1_000_000_000; // 1 SOL collateral
+    const amountToMint = 100_000;
+    const tx = await program.methods
+      .mintAndDepositToLending(new BN(amountCollateral), new BN(amountToMint))
+      .accounts({
+        depositor: wallet.publicKey,
+        configAccount,
+        collateralAccount,
+        solAccount,
+        mintAccount,
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        tokenAccount,
+        lendingPool: null, // only required once the collateral account is cross margined
+        obligation: null,
+        lendingProgram: lending.programId,
+        lendingBank,
+        lendingBankTokenAccount,
+        lendingUserAccount,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .signers([])
+      .rpc({ commitment: "confirmed" });
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+
+    // The instruction returns the lending deposit shares as little endian u64 return data
+    const txInfo = await connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const returnData = txInfo.meta.returnData;
+    if (!returnData || !returnData.programId.equals(program.programId)) {
+      throw new Error("mintAndDepositToLending did not set return data");
+    }
+    const returnedShares = new BN(Buffer.from(returnData.data[0], "base64"), "le");
+
+    const sharesAfter = await depositShares();
+    const bankAfter = await lending.account.bank.fetch(lendingBank);
+    if (returnedShares.isZero()) {
+      throw new Error("Expected the lending deposit to credit shares");
+    }
+    if (!sharesAfter.sub(sharesBefore).eq(returnedShares)) {
+      throw new Error(`User position gained ${sharesAfter.sub(sharesBefore)} shares, returned ${returnedShares}`);
+    }
+    if (!bankAfter.totalDepositShares.sub(bankBefore.totalDepositShares).eq(returnedShares)) {
+      throw new Error(`Bank gained ${bankAfter.totalDepositShares.sub(bankBefore.totalDepositShares)} shares, returned ${returnedShares}`);
+    }
+    console.log("Lending deposit shares:", returnedShares.toString());
+  });
+
+  const [insuranceFund] = anchor.web3.PublicKey.findProgramAddressSync(
+    [seed("insurance_fund")],
+    program.programId
+  );
+  const [insuranceVault] = anchor.web3.PublicKey.findProgramAddressSync(
+    [seed("insurance_vault"), mintAccount.toBuffer()],
+    program.programId
+  );
+
+  it("Initialize insurance fund", async () => {
+    const existing = await connection.getAccountInfo(insuranceFund);
+    if (existing) {
+      console.log("Insurance fund already initialized, skipping initializeInsuranceFund");
+      return;
+    }
+    const tx = await program.methods
+      .initializeInsuranceFund()
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        insuranceFund,
+        mintAccount,
+        insuranceVault,
         tokenProgram,
         systemProgram,
       })
@@ -150,12 +308,17 @@ describe("gold", () => {
         solAccount,
         mintAccount,
         tokenAccount,
+        insuranceFund,
+        insuranceVault,
         tokenProgram,
         systemProgram,
       })
       .signers([])
       .rpc();
     console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+
+    const fund = await program.account.insuranceFund.fetch(insuranceFund);
+    console.log("Insurance fund total deposited:", fund.totalDeposited.toString());
   });
 
   it("Update Config", async () => {
@@ -170,18 +333,176 @@ describe("gold", () => {
     [seed("lending_pool")],
     program.programId
   );
-  const [lenderPosition] = anchor.web3.PublicKey.findProgramAddressSync(
-    [seed("lender_position"), wallet.publicKey.toBuffer()],
+  const [rateHistory] = anchor.web3.PublicKey.findProgramAddressSync(
+    [seed("rate_history"), lendingPool.toBuffer()],
     program.programId
   );
-  const [borrowerPosition] = anchor.web3.PublicKey.findProgramAddressSync(
-    [seed("borrower_position"), wallet.publicKey.toBuffer()],
+  const [obligation] = anchor.web3.PublicKey.findProgramAddressSync(
+    [seed("obligation"), lendingPool.toBuffer(), wallet.publicKey.toBuffer()],
     program.programId
   );
   const [lendingPoolVault] = anchor.web3.PublicKey.findProgramAddressSync(
     [seed("lending_pool"), mintAccount.toBuffer()],
     program.programId
   );
+  const [receiptMint] = anchor.web3.PublicKey.findProgramAddressSync(
+    [seed("receipt_mint")],
+    program.programId
+  );
+  const receiptTokenAccount = getAssociatedTokenAddressSync(
+    receiptMint,
+    wallet.publicKey,
+    false,
+    TOKEN_2022_PROGRAM_ID,
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  );
+
+  it("Migrate legacy lending pool", async () => {
+    // Only a pool in the pre-zGOLD layout (8 byte discriminator, six u64 fields, bump) is migrated.
+    // The localnet validator loads one from tests/fixtures/legacy_lending_pool.json.
+    const LEGACY_LENDING_POOL_LEN = 8 + 6 * 8 + 1;
+    const existing = await connection.getAccountInfo(lendingPool);
+    if (!existing || existing.data.length !== LEGACY_LENDING_POOL_LEN) {
+      console.log("No legacy lending pool, skipping migrateLendingPool");
+      return;
+    }
+    const legacy = {
+      totalSupplied: new BN(existing.data.subarray(8, 16), "le"),
+      totalBorrowed: new BN(existing.data.subarray(16, 24), "le"),
+      lastUpdateSlot: new BN(existing.data.subarray(24, 32), "le"),
+      borrowRate: new BN(existing.data.subarray(40, 48), "le"),
+    };
+
+    const collateralFactor = 7_500; // 75% of supplied tokens can be borrowed
+    const reserveFactor = 1_000; // 10% of borrow interest goes to protocol reserves
+    const tx = await program.methods
+      .migrateLendingPool(new BN(collateralFactor), new BN(reserveFactor))
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        mintAccount,
+        lendingPoolVault,
+        receiptMint,
+        tokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+
+    // The account now deserializes in the current layout with the legacy totals, rates and slot carried over
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    if (!pool.totalSupplied.eq(legacy.totalSupplied) || !pool.totalBorrowed.eq(legacy.totalBorrowed)) {
+      throw new Error("migrateLendingPool did not carry over the legacy pool totals");
+    }
+    if (!pool.lastUpdateSlot.eq(legacy.lastUpdateSlot) || !pool.borrowRate.eq(legacy.borrowRate)) {
+      throw new Error("migrateLendingPool did not carry over the legacy accrual slot and rates");
+    }
+    if (!pool.receiptMint.equals(receiptMint) || pool.clockSource.slot === undefined) {
+      throw new Error("migrated pool is missing its zGOLD mint or left the slot clock");
+    }
+    if (pool.collateralFactor.toNumber() !== collateralFactor || pool.reserveFactor.toNumber() !== reserveFactor) {
+      throw new Error("migrated pool did not take the given collateral and reserve factors");
+    }
+
+    // A second migration finds the current layout and is rejected
+    let rejected = false;
+    try {
+      await program.methods
+        .migrateLendingPool(new BN(collateralFactor), new BN(reserveFactor))
+        .accounts({
+          authority: wallet.publicKey,
+          configAccount,
+          lendingPool,
+          mintAccount,
+          lendingPoolVault,
+          receiptMint,
+          tokenProgram,
+          systemProgram,
+        })
+        .rpc();
+    } catch (error) {
+      rejected = true;
+      console.log("Repeated pool migration rejected:", error.message);
+    }
+    if (!rejected) {
+      throw new Error("migrateLendingPool rewrote a pool that was already migrated");
+    }
+  });
+
+  it("Initialize lending pool", async () => {
+    const existing = await connection.getAccountInfo(lendingPool);
+    if (existing) {
+      console.log("Lending pool already initialized, skipping initializeLendingPool");
+      return;
+    }
+    const collateralFactor = 7_500; // 75% of supplied tokens can be borrowed
+    const reserveFactor = 1_000; // 10% of borrow interest goes to protocol reserves
+    const tx = await program.methods
+      .initializeLendingPool(new BN(collateralFactor), new BN(reserveFactor))
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        mintAccount,
+        lendingPoolVault,
+        receiptMint,
+        tokenProgram,
+        systemProgram,
+      })
+      .signers([])
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Initialize rate history", async () => {
+    const existing = await connection.getAccountInfo(rateHistory);
+    if (existing) {
+      console.log("Rate history already initialized, skipping initializeRateHistory");
+      return;
+    }
+    const tx = await program.methods
+      .initializeRateHistory()
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        systemProgram,
+      })
+      .signers([])
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Update lending pool caps", async () => {
+    const supplyCap = 1_000_000_000_000; // 1,000 GOLD
+    const borrowCap = 750_000_000_000; // 750 GOLD
+    const maxUtilization = 9_500; // 95%
+    const tx = await program.methods
+      .updateLendingPoolCaps(new BN(supplyCap), new BN(borrowCap), new BN(maxUtilization))
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+      })
+      .signers([])
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Update lending pool clock source", async () => {
+    const tx = await program.methods
+      .updateLendingPoolClockSource({ unixTimestamp: {} })
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+      })
+      .rpc();
+    console.log("Your transaction signature", tx, "https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
 
   it("Deposit tokens to lending pool", async () => {
     // Deposit available tokens to lending pool (after previous tests, we have ~25,000 tokens)
@@ -192,10 +513,12 @@ describe("gold", () => {
       .accounts({
         lender: wallet.publicKey,
         lendingPool,
-        lenderPosition,
-        borrowerPosition,
+        rateHistory,
+        obligation,
         mintAccount,
         lenderTokenAccount: tokenAccount,
+        receiptMint,
+        lenderReceiptAccount: receiptTokenAccount,
         lendingPoolVault,
         tokenProgram,
         associatedTokenProgram,
@@ -217,9 +540,12 @@ describe("gold", () => {
         borrower: wallet.publicKey,
         configAccount,
         lendingPool,
-        borrowerPosition,
+        rateHistory,
+        obligation,
         mintAccount,
         borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
         tokenProgram,
         associatedTokenProgram,
         systemProgram,
@@ -240,9 +566,17 @@ describe("gold", () => {
         borrower: wallet.publicKey,
         configAccount,
         lendingPool,
-        borrowerPosition,
+        rateHistory,
+        obligation,
         mintAccount,
         borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        lendingPoolVault,
+        withdrawalRequest: null, // only passed to fill the head of the withdrawal queue
+        withdrawalRequestOwner: null,
+        withdrawalRequestTokenAccount: null,
+        withdrawalQueue: null,
         tokenProgram,
         associatedTokenProgram,
         systemProgram,
@@ -254,6 +588,259 @@ describe("gold", () => {
     console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
   });
 
+  it("Repay tokens on behalf of a borrower", async () => {
+    const repayAmount = 5_000; // 0.000005 GOLD tokens
+
+    // Payer and owner are independent accounts, here the wallet repays its own obligation
+    const tx = await program.methods
+      .repayTokensFor(new BN(repayAmount))
+      .accounts({
+        payer: wallet.publicKey,
+        owner: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        obligation,
+        mintAccount,
+        payerTokenAccount: tokenAccount,
+        receiptMint,
+        ownerReceiptAccount: receiptTokenAccount,
+        lendingPoolVault,
+        withdrawalRequest: null,
+        withdrawalRequestOwner: null,
+        withdrawalRequestTokenAccount: null,
+        withdrawalQueue: null,
+        tokenProgram,
+      })
+      .rpc();
+
+    console.log("\nYour transaction signature", tx);
+    console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Borrow against CDP collateral with cross margin", async () => {
+    const crossMarginAccounts = {
+      owner: wallet.publicKey,
+      lendingPool,
+      obligation,
+      collateralAccount,
+      receiptMint,
+      ownerReceiptAccount: receiptTokenAccount,
+      tokenProgram,
+    };
+
+    const enableTx = await program.methods
+      .setCrossMargin(true)
+      .accounts(crossMarginAccounts)
+      .rpc();
+    console.log("\nYour transaction signature", enableTx);
+
+    const borrowAmount = 1_000; // counted against SOL collateral and zGOLD together
+    const borrowTx = await program.methods
+      .borrowTokensCrossMargin(new BN(borrowAmount))
+      .accounts({
+        borrower: wallet.publicKey,
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        obligation,
+        collateralAccount,
+        solAccount,
+        mintAccount,
+        borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("\nYour transaction signature", borrowTx);
+
+    // Minting against cross margined SOL is checked against the combined position
+    const mintTx = await program.methods
+      .depositCollateralAndMint(new BN(0), new BN(1_000))
+      .accounts({
+        depositor: wallet.publicKey,
+        configAccount,
+        collateralAccount,
+        solAccount,
+        mintAccount,
+        goldPriceUpdate: goldUsdPriceFeedAccount,
+        solPriceUpdate: solUsdPriceFeedAccount,
+        tokenAccount,
+        lendingPool,
+        obligation,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("\nYour transaction signature", mintTx);
+
+    // A separate liquidator, funded with SOL for its zGOLD account and GOLD for half the debt plus fees
+    const before = await program.account.obligation.fetch(obligation);
+    const debtBefore = before.amountBorrowed.add(before.borrowInterest);
+    const liquidator = anchor.web3.Keypair.generate();
+    const liquidatorTokenAccount = getAssociatedTokenAddressSync(
+      mintAccount,
+      liquidator.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const liquidatorReceiptAccount = getAssociatedTokenAddressSync(
+      receiptMint,
+      liquidator.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: liquidator.publicKey,
+          lamports: 0.01 * anchor.web3.LAMPORTS_PER_SOL,
+        }),
+        createAssociatedTokenAccountIdempotentInstruction(
+          wallet.publicKey,
+          liquidatorTokenAccount,
+          liquidator.publicKey,
+          mintAccount,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createTransferCheckedInstruction(
+          tokenAccount,
+          mintAccount,
+          liquidatorTokenAccount,
+          wallet.publicKey,
+          BigInt(debtBefore.divn(2).muln(11).divn(10).addn(100).toString()),
+          9,
+          [],
+          TOKEN_2022_PROGRAM_ID
+        )
+      )
+    );
+
+    // Raise the minimum health factor so the cross margin position can be liquidated
+    await program.methods.updateConfig(new BN(1_000_000_000)).accounts({ configAccount }).rpc();
+    const liquidateCrossMargin = (amount: BN, side: object) =>
+      program.methods
+        .liquidateCrossMargin(amount, side)
+        .accounts({
+          liquidator: liquidator.publicKey,
+          owner: wallet.publicKey,
+          goldPriceUpdate: goldUsdPriceFeedAccount,
+          solPriceUpdate: solUsdPriceFeedAccount,
+          configAccount,
+          lendingPool,
+          rateHistory,
+          obligation,
+          collateralAccount,
+          solAccount,
+          mintAccount,
+          liquidatorTokenAccount,
+          receiptMint,
+          ownerReceiptAccount: receiptTokenAccount,
+          liquidatorReceiptAccount,
+          lendingPoolVault,
+          withdrawalRequest: null,
+          withdrawalRequestOwner: null,
+          withdrawalRequestTokenAccount: null,
+          withdrawalQueue: null,
+          insuranceFund,
+          insuranceVault,
+          tokenProgram,
+          associatedTokenProgram,
+          systemProgram,
+        })
+        .signers([liquidator])
+        .rpc();
+
+    try {
+      // Seizing SOL is rejected while it leaves the CDP's own GOLD mint below the minimum health factor
+      let rejected = false;
+      try {
+        await liquidateCrossMargin(debtBefore, { cdp: {} });
+      } catch (error) {
+        rejected = true;
+        console.log("Cross margin SOL seizure from an unhealthy CDP rejected:", error.message);
+        if (!error.message.includes("BelowMinimumHealthFactor")) {
+          throw new Error(`SOL seizure rejected for the wrong reason: ${error.message}`);
+        }
+      }
+      if (!rejected) {
+        throw new Error("liquidateCrossMargin seized SOL backing an unhealthy CDP");
+      }
+
+      // Asking to repay the whole debt repays at most half of it, the close factor
+      const liquidateTx = await liquidateCrossMargin(debtBefore, { lending: {} });
+      console.log("\nYour transaction signature", liquidateTx);
+      const after = await program.account.obligation.fetch(obligation);
+      const debtAfter = after.amountBorrowed.add(after.borrowInterest);
+      if (debtAfter.lt(debtBefore.divn(2))) {
+        throw new Error("liquidateCrossMargin repaid more than the close factor allows");
+      }
+      if (!debtAfter.lt(debtBefore)) {
+        throw new Error("liquidateCrossMargin did not repay any debt");
+      }
+    } finally {
+      await program.methods.updateConfig(new BN(1)).accounts({ configAccount }).rpc();
+    }
+
+    // zGOLD alone still covers the debt, so the position can leave cross margin again
+    const disableTx = await program.methods
+      .setCrossMargin(false)
+      .accounts(crossMarginAccounts)
+      .rpc();
+    console.log("\nYour transaction signature", disableTx);
+    console.log("https://explorer.solana.com/tx/" + disableTx + "?cluster=devnet");
+  });
+
+  it("Refresh pool and position", async () => {
+    const refreshPoolIx = await program.methods
+      .refreshPool()
+      .accounts({ lendingPool, rateHistory })
+      .instruction();
+
+    const refreshPositionIx = await program.methods
+      .refreshPosition()
+      .accounts({
+        owner: wallet.publicKey,
+        lendingPool,
+        rateHistory,
+        obligation,
+        receiptMint,
+        ownerReceiptAccount: receiptTokenAccount,
+        tokenProgram,
+      })
+      .instruction();
+
+    const transaction = new anchor.web3.Transaction().add(refreshPoolIx, refreshPositionIx);
+    const tx = await anchor.web3.sendAndConfirmTransaction(
+      connection,
+      transaction,
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+
+    console.log("\nYour transaction signature", tx);
+    console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Read rate history", async () => {
+    const history = await program.account.rateHistory.fetch(rateHistory);
+    const latest = history.snapshots[history.head.toNumber()];
+    console.log("Rate history snapshots:", history.count.toString());
+    console.log("Latest snapshot - timestamp:", latest.timestamp.toString(),
+      "utilization:", latest.utilizationRate.toString(),
+      "supply rate:", latest.supplyRate.toString(),
+      "borrow rate:", latest.borrowRate.toString());
+  });
+
   it("Withdraw tokens from lending pool", async () => {
     const withdrawAmount = 10_000; // 0.00001 GOLD tokens (withdraw half of deposit, leaving room for debt)
     
@@ -262,10 +849,12 @@ describe("gold", () => {
       .accounts({
         lender: wallet.publicKey,
         lendingPool,
-        lenderPosition,
-        borrowerPosition,
+        rateHistory,
+        obligation,
         mintAccount,
         lenderTokenAccount: tokenAccount,
+        receiptMint,
+        lenderReceiptAccount: receiptTokenAccount,
         lendingPoolVault,
         tokenProgram,
         associatedTokenProgram,
@@ -278,6 +867,440 @@ describe("gold", () => {
     console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
   });
 
+  it("Queue and fill a withdrawal request", async () => {
+    const requestAmount = 1_000; // 0.000001 GOLD tokens
+    const [withdrawalQueue] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("withdrawal_queue"), receiptMint.toBuffer()],
+      program.programId
+    );
+
+    const tail = (await program.account.lendingPool.fetch(lendingPool)).withdrawalQueueTail;
+    const [withdrawalRequest] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("withdrawal_request"), lendingPool.toBuffer(), tail.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const requestTx = await program.methods
+      .requestWithdrawal(new BN(requestAmount))
+      .accounts({
+        lender: wallet.publicKey,
+        lendingPool,
+        rateHistory,
+        obligation,
+        receiptMint,
+        lenderReceiptAccount: receiptTokenAccount,
+        withdrawalQueue,
+        withdrawalRequest,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("\nYour transaction signature", requestTx);
+
+    // Any wallet can crank the head of the queue
+    const fillTx = await program.methods
+      .fillWithdrawalRequest()
+      .accounts({
+        configAccount,
+        lendingPool,
+        rateHistory,
+        withdrawalRequest,
+        owner: wallet.publicKey,
+        ownerTokenAccount: tokenAccount,
+        mintAccount,
+        receiptMint,
+        withdrawalQueue,
+        lendingPoolVault,
+        tokenProgram,
+      })
+      .rpc();
+
+    console.log("\nYour transaction signature", fillTx);
+    console.log("https://explorer.solana.com/tx/" + fillTx + "?cluster=devnet");
+  });
+
+  it("Repay fills the head of the withdrawal queue", async () => {
+    const requestAmount = 1_000; // 0.000001 GOLD tokens
+    const [withdrawalQueue] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("withdrawal_queue"), receiptMint.toBuffer()],
+      program.programId
+    );
+
+    const tail = (await program.account.lendingPool.fetch(lendingPool)).withdrawalQueueTail;
+    const [withdrawalRequest] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("withdrawal_request"), lendingPool.toBuffer(), tail.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .requestWithdrawal(new BN(requestAmount))
+      .accounts({
+        lender: wallet.publicKey,
+        lendingPool,
+        rateHistory,
+        obligation,
+        receiptMint,
+        lenderReceiptAccount: receiptTokenAccount,
+        withdrawalQueue,
+        withdrawalRequest,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+
+    // The repayment pays out the head of the queue without a separate crank
+    const head = (await program.account.lendingPool.fetch(lendingPool)).withdrawalQueueHead;
+    const [headRequest] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("withdrawal_request"), lendingPool.toBuffer(), head.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const tx = await program.methods
+      .repayTokens(new BN(1_000))
+      .accounts({
+        borrower: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        obligation,
+        mintAccount,
+        borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        lendingPoolVault,
+        withdrawalRequest: headRequest,
+        withdrawalRequestOwner: wallet.publicKey,
+        withdrawalRequestTokenAccount: tokenAccount,
+        withdrawalQueue,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+
+    const request = await program.account.withdrawalRequest.fetchNullable(headRequest);
+    if (request !== null && request.filledAmount.isZero()) {
+      throw new Error("repay should fill the head of the withdrawal queue");
+    }
+    console.log("Withdrawal request", request === null ? "fully filled and closed" : "partially filled");
+    console.log("\nYour transaction signature", tx);
+    console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
+  it("Borrow and repay a fixed-rate term loan", async () => {
+    const loanId = new BN(Date.now());
+    const loanAmount = 1_000; // 0.000001 GOLD tokens
+    const duration = 86_400; // 1 day
+    const [termLoan] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("term_loan"), lendingPool.toBuffer(), wallet.publicKey.toBuffer(), loanId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [termLoanEscrow] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("term_loan_escrow"), termLoan.toBuffer()],
+      program.programId
+    );
+
+    const termBorrowedBefore = (await program.account.lendingPool.fetch(lendingPool)).termBorrowed;
+    const borrowTx = await program.methods
+      .borrowTermLoan(loanId, new BN(loanAmount), new BN(duration))
+      .accounts({
+        borrower: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        obligation,
+        termLoan,
+        mintAccount,
+        borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        termLoanEscrow,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+    console.log("\nYour transaction signature", borrowTx);
+
+    // Term principal is tracked apart from floating borrows, it earns lenders its fixed rate instead
+    const poolDuringLoan = await program.account.lendingPool.fetch(lendingPool);
+    if (!poolDuringLoan.termBorrowed.eq(termBorrowedBefore.add(new BN(loanAmount)))) {
+      throw new Error("borrowTermLoan did not add its principal to the pool's term borrowed total");
+    }
+
+    // Repaying before maturity includes the early repayment fee
+    const repayTx = await program.methods
+      .repayTermLoan()
+      .accounts({
+        borrower: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        termLoan,
+        mintAccount,
+        borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        termLoanEscrow,
+        lendingPoolVault,
+        withdrawalRequest: null,
+        withdrawalRequestOwner: null,
+        withdrawalRequestTokenAccount: null,
+        withdrawalQueue: null,
+        tokenProgram,
+        associatedTokenProgram,
+      })
+      .rpc();
+
+    console.log("\nYour transaction signature", repayTx);
+    console.log("https://explorer.solana.com/tx/" + repayTx + "?cluster=devnet");
+
+    // The fixed interest and fee are booked to lenders and reserves, and the principal leaves the term total
+    const poolAfterRepay = await program.account.lendingPool.fetch(lendingPool);
+    if (!poolAfterRepay.termBorrowed.eq(termBorrowedBefore)) {
+      throw new Error("repayTermLoan did not remove its principal from the pool's term borrowed total");
+    }
+    if (poolAfterRepay.totalSupplied.lt(poolDuringLoan.totalSupplied) || poolAfterRepay.protocolReserves.lt(poolDuringLoan.protocolReserves)) {
+      throw new Error("repayTermLoan did not book the term loan's interest and fee");
+    }
+  });
+
+  it("Flash borrow and repay in one transaction", async () => {
+    const flashAmount = 5_000; // borrowed from the pool vault and repaid with fee in the same transaction
+    const flashAccounts = {
+      borrower: wallet.publicKey,
+      lendingPool,
+      mintAccount,
+      lendingPoolVault,
+      borrowerTokenAccount: tokenAccount,
+      instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      tokenProgram,
+    };
+
+    const borrowIx = await program.methods
+      .flashBorrow(new BN(flashAmount))
+      .accounts(flashAccounts)
+      .instruction();
+    const repayIx = await program.methods
+      .flashRepay(new BN(flashAmount), 0)
+      .accounts(flashAccounts)
+      .instruction();
+
+    const tx = new anchor.web3.Transaction().add(borrowIx, repayIx);
+    const signature = await anchor.web3.sendAndConfirmTransaction(
+      connection,
+      tx,
+      [wallet.payer],
+      { commitment: "confirmed" }
+    );
+
+    console.log("\nYour transaction signature", signature);
+    console.log("https://explorer.solana.com/tx/" + signature + "?cluster=devnet");
+  });
+
+  it("Fund and claim liquidity mining rewards", async () => {
+    // GOLD itself is used as the reward mint in slot 0
+    const rewardSlot = 0;
+    const [rewardVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [seed("reward_vault"), lendingPool.toBuffer(), mintAccount.toBuffer()],
+      program.programId
+    );
+
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    if (pool.rewards[rewardSlot].rewardMint.equals(anchor.web3.PublicKey.default)) {
+      const initTx = await program.methods
+        .initializeReward(rewardSlot)
+        .accounts({
+          authority: wallet.publicKey,
+          configAccount,
+          lendingPool,
+          rewardMint: mintAccount,
+          rewardVault,
+          rewardTokenProgram: tokenProgram,
+          systemProgram,
+        })
+        .rpc();
+      console.log("\nYour transaction signature", initTx);
+    }
+
+    const endTimestamp = Math.floor(Date.now() / 1000) + 3600;
+    const fundTx = await program.methods
+      .fundReward(rewardSlot, new BN(1_000), new BN(1), new BN(1), new BN(endTimestamp))
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rewardMint: mintAccount,
+        rewardVault,
+        authorityRewardAccount: tokenAccount,
+        rewardTokenProgram: tokenProgram,
+      })
+      .rpc();
+    console.log("\nYour transaction signature", fundTx);
+
+    const claimTx = await program.methods
+      .claimRewards(rewardSlot)
+      .accounts({
+        owner: wallet.publicKey,
+        lendingPool,
+        obligation,
+        receiptMint,
+        ownerReceiptAccount: receiptTokenAccount,
+        rewardMint: mintAccount,
+        rewardVault,
+        ownerRewardAccount: tokenAccount,
+        tokenProgram,
+        rewardTokenProgram: tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc();
+
+    // Plain supplied zGOLD earns supply rewards, the receipt account does not need to be frozen
+    const position = await program.account.obligation.fetch(obligation);
+    const receiptBalance = await connection.getTokenAccountBalance(receiptTokenAccount);
+    if (!position.rewardSupplyBasis.eq(new BN(receiptBalance.value.amount))) {
+      throw new Error("claimRewards should sync the supplied zGOLD balance as the supply reward basis");
+    }
+    console.log("Unclaimed rewards:", position.rewards[rewardSlot].unclaimed.toString());
+    console.log("\nYour transaction signature", claimTx);
+    console.log("https://explorer.solana.com/tx/" + claimTx + "?cluster=devnet");
+  });
+
+  // Borrowed GOLD is minted, so the vault backs all of totalSupplied plus queued withdrawals,
+  // only reserves held beyond that can leave it
+  const INDEX_PRECISION = new BN("1000000000000");
+  const withdrawableReserves = (pool: any, vaultAmount: BN) => {
+    const queuedValue = pool.queuedReceipt.mul(pool.supplyIndex).div(INDEX_PRECISION);
+    const backing = BN.max(vaultAmount.sub(pool.totalSupplied).sub(queuedValue), new BN(0));
+    return BN.min(pool.protocolReserves, backing);
+  };
+
+  it("Fund insurance from protocol reserves", async () => {
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    const vault = await connection.getTokenAccountBalance(lendingPoolVault);
+    // Same bound as withdrawReserves, lender GOLD never moves into the insurance fund
+    const amount = withdrawableReserves(pool, new BN(vault.value.amount)).divn(2);
+    if (amount.isZero()) {
+      console.log("No withdrawable protocol reserves yet, skipping fundInsuranceFromReserves");
+      return;
+    }
+    const tx = await program.methods
+      .fundInsuranceFromReserves(amount)
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        insuranceFund,
+        mintAccount,
+        lendingPoolVault,
+        insuranceVault,
+        tokenProgram,
+      })
+      .signers([])
+      .rpc();
+    console.log("\nYour transaction signature", tx);
+    console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+
+    const fund = await program.account.insuranceFund.fetch(insuranceFund);
+    console.log("Insurance fund total deposited:", fund.totalDeposited.toString());
+  });
+
+  it("Cover bad debt seizes non-cross-margin zGOLD only once the debt outgrows it", async () => {
+    // A plain borrow delegates the zGOLD collateral to the pool, which lets coverBadDebt seize it
+    await program.methods
+      .borrowTokens(new BN(100))
+      .accounts({
+        borrower: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        obligation,
+        mintAccount,
+        borrowerTokenAccount: tokenAccount,
+        receiptMint,
+        borrowerReceiptAccount: receiptTokenAccount,
+        tokenProgram,
+        associatedTokenProgram,
+        systemProgram,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const position = await program.account.obligation.fetch(obligation);
+    if (position.crossMargin) {
+      throw new Error("expected a lending-only obligation");
+    }
+    const receiptBefore = await getAccount(connection, receiptTokenAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+    if (!receiptBefore.delegate?.equals(lendingPool)) {
+      throw new Error("borrowed zGOLD should be delegated to the lending pool");
+    }
+
+    let errorCode: string | undefined;
+    try {
+      await program.methods
+        .coverBadDebt()
+        .accounts({
+          caller: wallet.publicKey,
+          owner: wallet.publicKey,
+          configAccount,
+          lendingPool,
+          rateHistory,
+          obligation,
+          receiptMint,
+          ownerReceiptAccount: receiptTokenAccount,
+          mintAccount,
+          lendingPoolVault,
+          insuranceFund,
+          insuranceVault,
+          collateralAccount: null, // only required for cross margin obligations
+          solAccount: null,
+          goldPriceUpdate: null,
+          solPriceUpdate: null,
+          tokenProgram,
+        })
+        .rpc();
+    } catch (err: any) {
+      errorCode = err.error?.errorCode?.code ?? err.message;
+      console.log("Expected failure:", errorCode);
+    }
+    // The zGOLD still covers the debt, so nothing is seized
+    if (errorCode !== "NoBadDebt") {
+      throw new Error("coverBadDebt should only seize zGOLD from an obligation with bad debt");
+    }
+    const receiptAfter = await getAccount(connection, receiptTokenAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+    if (receiptAfter.amount !== receiptBefore.amount) {
+      throw new Error("a healthy obligation's zGOLD should be left in place");
+    }
+  });
+
+  it("Withdraw protocol reserves", async () => {
+    const pool = await program.account.lendingPool.fetch(lendingPool);
+    const vault = await connection.getTokenAccountBalance(lendingPoolVault);
+    const withdrawable = withdrawableReserves(pool, new BN(vault.value.amount));
+    if (withdrawable.isZero()) {
+      console.log("No withdrawable protocol reserves yet, skipping withdrawReserves");
+      return;
+    }
+    const tx = await program.methods
+      .withdrawReserves(withdrawable)
+      .accounts({
+        authority: wallet.publicKey,
+        configAccount,
+        lendingPool,
+        rateHistory,
+        mintAccount,
+        lendingPoolVault,
+        treasuryTokenAccount: tokenAccount,
+        tokenProgram,
+      })
+      .signers([])
+      .rpc();
+    console.log("\nYour transaction signature", tx);
+    console.log("https://explorer.solana.com/tx/" + tx + "?cluster=devnet");
+  });
+
   it("Transaction that combines minting and lending deposit", async () => {
     // This combines minting and lending deposit in a single transaction
     const amountCollateral = 500_000_000; // 0.5 SOL collateral
@@ -296,6 +1319,8 @@ describe("gold", () => {
         goldPriceUpdate: goldUsdPriceFeedAccount,
         solPriceUpdate: solUsdPriceFeedAccount,
         tokenAccount,
+        lendingPool: null, // only required once the collateral account is cross margined
+        obligation: null,
         tokenProgram,
         associatedTokenProgram,
         systemProgram,
@@ -307,10 +1332,12 @@ describe("gold", () => {
       .accounts({
         lender: wallet.publicKey,
         lendingPool,
-        lenderPosition,
-        borrowerPosition,
+        rateHistory,
+        obligation,
         mintAccount,
         lenderTokenAccount: tokenAccount,
+        receiptMint,
+        lenderReceiptAccount: receiptTokenAccount,
         lendingPoolVault,
         tokenProgram,
         associatedTokenProgram,